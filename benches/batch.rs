@@ -0,0 +1,93 @@
+//! Compares stepping a batch of games in lockstep with stepping the same number of
+//! standalone environments one at a time.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use snake::batch::BatchEnvironment;
+use snake::environment::{Encoding, Environment, EnvironmentConfig};
+use snake::player::Direction;
+use std::hint::black_box;
+
+const GAMES: usize = 1024;
+
+fn config(encoding: Encoding) -> EnvironmentConfig {
+    EnvironmentConfig {
+        width: 20,
+        height: 20,
+        encoding,
+        ..EnvironmentConfig::default()
+    }
+}
+
+fn actions(step: usize) -> Vec<Direction> {
+    (0..GAMES)
+        .map(|index| Direction::ALL[(step / 5 + index) % Direction::ALL.len()])
+        .collect()
+}
+
+fn step_and_observe(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step and observe 1024 games");
+    group.throughput(Throughput::Elements(GAMES as u64));
+    for encoding in [
+        Encoding::Features,
+        Encoding::Egocentric { radius: 5 },
+        Encoding::Grid,
+    ] {
+        let config = config(encoding);
+        let observation_len = encoding.len(config.width, config.height);
+        let mut buffer = vec![0.0; observation_len * GAMES];
+
+        let mut environments: Vec<Environment> = (0..GAMES)
+            .map(|index| {
+                let mut environment = Environment::new(config).unwrap();
+                environment.reset(index as u64);
+                environment
+            })
+            .collect();
+        let mut step = 0;
+        group.bench_function(
+            BenchmarkId::new("individual", format!("{:?}", encoding)),
+            |b| {
+                b.iter(|| {
+                    step += 1;
+                    for (index, (environment, direction)) in
+                        environments.iter_mut().zip(actions(step)).enumerate()
+                    {
+                        environment.advance(direction);
+                        if environment.is_done() {
+                            environment.reset(index as u64 + step as u64);
+                        }
+                        environment.observe_into(
+                            &mut buffer[index * observation_len..][..observation_len],
+                        );
+                    }
+                    black_box(&buffer);
+                })
+            },
+        );
+
+        for threads in [1, 4] {
+            let mut batch = BatchEnvironment::new(config, GAMES, 0)
+                .unwrap()
+                .with_threads(threads);
+            let mut step = 0;
+            group.bench_function(
+                BenchmarkId::new(
+                    format!("batch, {} threads", threads),
+                    format!("{:?}", encoding),
+                ),
+                |b| {
+                    b.iter(|| {
+                        step += 1;
+                        batch.step(&actions(step));
+                        batch.observe_into(&mut buffer);
+                        black_box(&buffer);
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step_and_observe);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+//! Measures the engine: ticks at several board sizes and snake lengths, building a frame, and
+//! placing food on nearly full boards. `snake bench` prints a quick summary of the same
+//! workloads.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use snake::bench::{coiled_game, length_for, Slither, BOARD_SIZES, FILLS, SPAWN_FILLS};
+use snake::game::GameBoard;
+use std::hint::black_box;
+
+fn size_and_length(width: usize, height: usize, length: usize) -> String {
+    format!("{}x{}, length {}", width, height, length)
+}
+
+fn ticks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    for (width, height) in BOARD_SIZES {
+        for fill in FILLS {
+            let length = length_for(width, height, fill);
+            let mut slither = Slither::new(width, height, length);
+            group.bench_function(size_and_length(width, height, length), |b| {
+                b.iter(|| {
+                    slither.step();
+                    black_box(slither.game_state());
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame");
+    for (width, height) in BOARD_SIZES {
+        for fill in FILLS {
+            let length = length_for(width, height, fill);
+            let game_state = coiled_game(width, height, length, false);
+            let id = size_and_length(width, height, length);
+            group.bench_with_input(
+                BenchmarkId::new("GameBoard::try_from", &id),
+                &game_state,
+                |b, game_state| b.iter(|| GameBoard::try_from(game_state).unwrap()),
+            );
+            let board = GameBoard::try_from(&game_state).unwrap();
+            group.bench_with_input(BenchmarkId::new("display", &id), &board, |b, board| {
+                b.iter(|| board.to_string())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn food_spawning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eat and place food");
+    for (width, height) in BOARD_SIZES {
+        for fill in SPAWN_FILLS {
+            let length = length_for(width, height, fill);
+            let free_cells = (width - 2) * (height - 2) - length - 1;
+            let game_state = coiled_game(width, height, length, true);
+            group.bench_function(
+                format!("{}x{}, {} cells free", width, height, free_cells),
+                |b| {
+                    b.iter_batched_ref(
+                        || game_state.clone(),
+                        |game_state| black_box(game_state.tick()),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, ticks, frames, food_spawning);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+//! Config files, as read at startup. Anything that loads has to set up a game that plays and
+//! draws in every theme without panicking.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snake::config::Config;
+use snake::game::{GameBoard, GameState};
+use snake::player::Direction;
+use snake::render::Theme;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(config) = Config::read_from(text) {
+        let mut game_state =
+            GameState::seeded(config.width, config.height, 1, 0).with_rules(config.rules());
+        for direction in Direction::ALL.iter().cycle().take(50) {
+            if let Ok(board) = GameBoard::try_from(&game_state) {
+                for theme in Theme::ALL {
+                    board.render(theme, config.wrap);
+                }
+            }
+            game_state.steer(0, *direction);
+            if game_state.tick().is_none() {
+                break;
+            }
+        }
+    }
+});
@@ -0,0 +1,39 @@
+//! The game itself, driven by arbitrary boards and turns.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use snake::game::{GameArea, GameState};
+use snake::player::Direction;
+
+#[derive(Debug, Arbitrary)]
+struct Game {
+    width: u8,
+    height: u8,
+    players: u8,
+    seed: u64,
+    /// The turns made before each tick, as (player, direction) pairs
+    ticks: Vec<Vec<(u8, u8)>>,
+}
+
+fuzz_target!(|game: Game| {
+    // Every front-end refuses boards smaller than 4 x 4, and more players than rows to put them
+    let width = 4 + usize::from(game.width % 60);
+    let height = 4 + usize::from(game.height % 60);
+    let players = 1 + usize::from(game.players) % GameArea::max_players(height).min(8);
+    let mut game_state = GameState::seeded(width, height, players, game.seed);
+    for turns in game.ticks {
+        for (player, direction) in turns {
+            let direction = Direction::ALL[usize::from(direction % 4)];
+            game_state.steer(usize::from(player) % players, direction);
+        }
+        let running = game_state.tick().is_some();
+        for snake in game_state.snakes() {
+            assert_eq!(snake.tail.positions.len(), snake.score + 1);
+        }
+        if !running {
+            break;
+        }
+    }
+});
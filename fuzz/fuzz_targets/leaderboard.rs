@@ -0,0 +1,23 @@
+//! Leaderboard files, as read at startup. Whatever is read has to stay a valid leaderboard,
+//! best first and no longer than the maximum, as scores are added.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snake::leaderboard::{Leaderboard, MAX_ENTRIES};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut leaderboard = Leaderboard::parse(text);
+    for score in [0, 1, leaderboard.high_score(), usize::MAX] {
+        if let Some(rank) = leaderboard.insert(score) {
+            assert_eq!(leaderboard.scores()[rank - 1], score);
+        }
+        let scores = leaderboard.scores();
+        assert!(scores.len() <= MAX_ENTRIES);
+        assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(!scores.contains(&0));
+    }
+});
@@ -0,0 +1,24 @@
+//! Profile files, as read at startup. Anything that loads has to record games, take new
+//! profiles and draw its stats without panicking.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snake::game::{GameState, Mode};
+use snake::profile::Profiles;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(mut profiles) = Profiles::read_from(text) {
+        let stats = GameState::seeded(20, 20, 1, 0).run_stats(&Default::default());
+        for mode in Mode::ALL {
+            profiles.record(mode, &stats);
+        }
+        let _ = profiles.add("fuzz");
+        for profile in profiles.profiles() {
+            let _ = profile.stats.report('#', 40);
+        }
+    }
+});
@@ -0,0 +1,25 @@
+//! Lines from the network, decoded the way the server and the clients decode them. Snapshots
+//! that pass the clients' check are drawn like the clients draw them.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snake::game::GameBoard;
+use snake::net::protocol::{
+    read_message, ClientMessage, ServerMessage, MAX_CLIENT_LINE_LENGTH, MAX_SERVER_LINE_LENGTH,
+};
+
+fuzz_target!(|data: &[u8]| {
+    for line in data.split(|&byte| byte == b'\n') {
+        let _ = read_message::<ClientMessage>(&mut &*line, MAX_CLIENT_LINE_LENGTH);
+        let Ok(Some(message)) = read_message::<ServerMessage>(&mut &*line, MAX_SERVER_LINE_LENGTH)
+        else {
+            continue;
+        };
+        if let (Ok(()), ServerMessage::Snapshot { state, .. }) = (message.check(), &message) {
+            if let Ok(board) = GameBoard::try_from(&**state) {
+                let _ = board.to_string();
+            }
+        }
+    }
+});
@@ -0,0 +1,13 @@
+//! Replay files, as read by `snake replay` and `snake serve --replay`. Anything that parses has
+//! to play back to the end.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snake::replay::Replay;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(replay) = Replay::read_from(data) {
+        for _ in replay.states() {}
+    }
+});
@@ -0,0 +1,22 @@
+//! Saved games, as read from the save slot by the welcome screen. Anything that loads has to
+//! play on and draw without panicking.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snake::game::GameBoard;
+use snake::player::Direction;
+use snake::save::SavedGame;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(saved_game) = SavedGame::read_from(data) {
+        let mut game_state = saved_game.game_state;
+        for direction in Direction::ALL.iter().cycle().take(200) {
+            let _ = GameBoard::try_from(&game_state).map(|board| board.to_string());
+            game_state.steer(0, *direction);
+            if game_state.tick().is_none() {
+                break;
+            }
+        }
+    }
+});
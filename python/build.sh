@@ -0,0 +1,7 @@
+#!/bin/sh
+# Builds the Python extension module and places it next to snake.pyi
+set -e
+cd "$(dirname "$0")/.."
+cargo build --release --features python --lib
+suffix=$(python3 -c 'import sysconfig; print(sysconfig.get_config_var("EXT_SUFFIX"))')
+cp target/release/libsnake.so "python/snake$suffix"
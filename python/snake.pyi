@@ -0,0 +1,98 @@
+"""Type stubs for the snake engine, built from src/python.rs with the `python` cargo feature."""
+
+from typing import Literal, Optional, TypedDict, Union
+
+DirectionName = Literal["up", "right", "down", "left", "u", "r", "d", "l"]
+# Either a direction name or an index into DIRECTIONS
+Action = Union[DirectionName, int]
+EncodingName = Literal["grid", "egocentric", "features"]
+
+DIRECTIONS: list[str]
+
+class SnakeInfo(TypedDict):
+    head: tuple[int, int]
+    heading: str
+    body: list[tuple[int, int]]
+    score: int
+    alive: bool
+
+class StepInfo(TypedDict):
+    score: int
+    length: int
+    steps: int
+    truncated: bool
+
+class Game:
+    """A game that records every turn, so it can be saved as a replay."""
+
+    def __init__(
+        self, width: int = 20, height: int = 20, players: int = 1, seed: Optional[int] = None
+    ) -> None: ...
+    @property
+    def width(self) -> int: ...
+    @property
+    def height(self) -> int: ...
+    @property
+    def seed(self) -> int: ...
+    @property
+    def food(self) -> tuple[int, int]:
+        """Position of the food nearest the first snake's head as (column, row)."""
+    @property
+    def foods(self) -> list[tuple[int, int]]:
+        """Position of every food as (column, row)."""
+    @property
+    def ticks(self) -> int: ...
+    def is_over(self) -> bool: ...
+    def score(self, player: int = 0) -> int: ...
+    def snakes(self) -> list[SnakeInfo]: ...
+    def steer(self, player: int, direction: Action) -> None:
+        """Turns a snake before the next tick; reversing is ignored."""
+    def tick(self) -> bool:
+        """Advances the game by one tick, returning whether it is still running."""
+    def observation(self, encoding: EncodingName = "features", radius: int = 5) -> list[float]:
+        """The first snake's view of the board as a flat list; see observation_shape."""
+    def observation_shape(
+        self, encoding: EncodingName = "features", radius: int = 5
+    ) -> list[int]: ...
+    def save_replay(self, path: str) -> None:
+        """Saves the ticks played so far, which `snake replay` can play back."""
+
+class Replay:
+    @staticmethod
+    def load(path: str) -> "Replay": ...
+    def save(self, path: str) -> None: ...
+    @property
+    def seed(self) -> int: ...
+    @property
+    def width(self) -> int: ...
+    @property
+    def height(self) -> int: ...
+    @property
+    def names(self) -> list[str]: ...
+    @property
+    def ticks(self) -> int: ...
+    def turns(self) -> list[list[tuple[int, str]]]:
+        """The recorded turns, one list of (player, direction) pairs per tick."""
+    def play(self, ticks: Optional[int] = None) -> Game:
+        """Plays the recording back as a game, stopping after `ticks` ticks if given."""
+
+class Env:
+    """The gym-style training environment."""
+
+    def __init__(
+        self,
+        width: int = 20,
+        height: int = 20,
+        encoding: EncodingName = "features",
+        radius: int = 5,
+        max_steps: Optional[int] = 10000,
+        food_reward: float = 1.0,
+        death_reward: float = -1.0,
+        step_reward: float = 0.0,
+        approach_reward: float = 0.0,
+        clear_reward: float = 10.0,
+    ) -> None: ...
+    @property
+    def observation_shape(self) -> list[int]: ...
+    def reset(self, seed: Optional[int] = None) -> list[float]: ...
+    def step(self, action: Action) -> tuple[list[float], float, bool, StepInfo]: ...
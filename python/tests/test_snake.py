@@ -0,0 +1,106 @@
+import os
+import sys
+import tempfile
+import unittest
+
+sys.path.insert(0, os.path.join(os.path.dirname(__file__), ".."))
+
+import snake  # noqa: E402
+
+
+class GameTest(unittest.TestCase):
+    def test_snakes_start_in_the_middle_heading_right(self):
+        game = snake.Game(20, 12, seed=1)
+        [player] = game.snakes()
+        self.assertEqual(player["head"], (10, 6))
+        self.assertEqual(player["heading"], "right")
+        self.assertEqual(player["body"], [(9, 6)])
+        self.assertTrue(player["alive"])
+
+    def test_running_into_the_wall_ends_the_game(self):
+        game = snake.Game(20, 12, seed=1)
+        game.steer(0, "up")
+        ticks = 0
+        while game.tick():
+            ticks += 1
+        self.assertTrue(game.is_over())
+        self.assertFalse(game.snakes()[0]["alive"])
+        self.assertEqual(ticks, 5)
+
+    def test_bad_input_raises(self):
+        game = snake.Game(20, 12)
+        with self.assertRaises(ValueError):
+            game.steer(0, "sideways")
+        with self.assertRaises(IndexError):
+            game.steer(3, "up")
+        with self.assertRaises(ValueError):
+            snake.Game(2, 2)
+        with self.assertRaises(ValueError):
+            snake.Game(20, 12, players=11)
+        with self.assertRaises(ValueError):
+            game.observation("egocentric", radius=100000)
+        with self.assertRaises(ValueError):
+            game.observation_shape("egocentric", radius=2**62)
+        with self.assertRaises(ValueError):
+            snake.Env(encoding="egocentric", radius=100000)
+
+    def test_observations_match_their_shape(self):
+        game = snake.Game(20, 12, seed=1)
+        for encoding in ["grid", "egocentric", "features"]:
+            shape = game.observation_shape(encoding)
+            size = 1
+            for dimension in shape:
+                size *= dimension
+            self.assertEqual(len(game.observation(encoding)), size)
+
+
+class ReplayTest(unittest.TestCase):
+    def test_saved_games_play_back_identically(self):
+        game = snake.Game(30, 15, seed=9)
+        script = ["up", "left", "down", "right"]
+        for step in range(60):
+            if step % 4 == 0:
+                game.steer(0, script[step // 4 % len(script)])
+            if not game.tick():
+                break
+        with tempfile.TemporaryDirectory() as directory:
+            path = os.path.join(directory, "game.replay")
+            game.save_replay(path)
+            replay = snake.Replay.load(path)
+        self.assertEqual(replay.seed, 9)
+        self.assertEqual(replay.ticks, game.ticks)
+        self.assertEqual(replay.turns()[0], [(0, "up")])
+        played = replay.play()
+        self.assertEqual(played.snakes(), game.snakes())
+        self.assertEqual(played.food, game.food)
+        self.assertEqual(played.foods, game.foods)
+        self.assertIn(game.food, game.foods)
+
+
+class EnvTest(unittest.TestCase):
+    def test_episodes_are_reproducible(self):
+        def episode():
+            env = snake.Env(12, 10, encoding="grid")
+            observations = [env.reset(seed=4)]
+            for step in range(30):
+                observation, reward, done, info = env.step(step // 3 % 4)
+                observations.append((observation, reward, done, info))
+                if done:
+                    break
+            return observations
+
+        self.assertEqual(episode(), episode())
+
+    def test_step_reports_death(self):
+        env = snake.Env(12, 8)
+        env.reset(seed=1)
+        done = False
+        while not done:
+            _, reward, done, info = env.step("up")
+        self.assertEqual(reward, -1.0)
+        self.assertFalse(info["truncated"])
+        self.assertEqual(len(env.reset(seed=2)), env.observation_shape[0])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,226 @@
+//! Achievements, earned by the first player and unlocked for good on their profile. They are
+//! worked out from the events of each tick, so they come out the same however the game is
+//! played, watched or replayed.
+
+use crate::event::{Combo, Event, Observer};
+use crate::game::GameState;
+use crate::player::Cause;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::Duration;
+
+/// Bites in a row, each within `COMBO_TICKS` of the last, for a hat trick
+const HAT_TRICK_BITES: usize = 3;
+
+/// Cells long, head included, for the long snake
+const LONG_SNAKE_LENGTH: usize = 50;
+
+/// Time spent playing, pauses left out, for a marathon
+const MARATHON_TIME: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+    FirstBite,
+    HatTrick,
+    LongSnake,
+    Marathon,
+    /// Clearing the board without a single left turn
+    Leftless,
+    /// Another snake running into this one
+    Trapper,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 6] = [
+        Achievement::FirstBite,
+        Achievement::HatTrick,
+        Achievement::LongSnake,
+        Achievement::Marathon,
+        Achievement::Leftless,
+        Achievement::Trapper,
+    ];
+
+    /// What it takes, short enough for the achievements screen
+    pub fn description(self) -> String {
+        match self {
+            Achievement::FirstBite => String::from("eat for the first time"),
+            Achievement::HatTrick => format!("eat {} times in quick succession", HAT_TRICK_BITES),
+            Achievement::LongSnake => format!("reach length {}", LONG_SNAKE_LENGTH),
+            Achievement::Marathon => {
+                format!("play {} minutes in one game", MARATHON_TIME.as_secs() / 60)
+            }
+            Achievement::Leftless => String::from("clear the board, no left turns"),
+            Achievement::Trapper => String::from("get another snake to hit you"),
+        }
+    }
+}
+
+impl fmt::Display for Achievement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Achievement::FirstBite => write!(f, "first bite"),
+            Achievement::HatTrick => write!(f, "hat trick"),
+            Achievement::LongSnake => write!(f, "long snake"),
+            Achievement::Marathon => write!(f, "marathon"),
+            Achievement::Leftless => write!(f, "leftless"),
+            Achievement::Trapper => write!(f, "trapper"),
+        }
+    }
+}
+
+/// Follows the first snake through a game's events, noting the achievements it earns that
+/// weren't unlocked already
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tracker {
+    /// Unlocked before or during this game
+    unlocked: BTreeSet<Achievement>,
+    /// Unlocked during this game, in the order they were earned
+    earned: Vec<Achievement>,
+    turned_left: bool,
+    combo: Combo,
+}
+
+impl Tracker {
+    /// Leaves out `unlocked` from what this game can earn
+    pub fn with_unlocked(mut self, unlocked: &BTreeSet<Achievement>) -> Self {
+        self.unlocked.extend(unlocked);
+        self
+    }
+
+    /// Notes that the game has been played for `played` so far, whatever the speed
+    pub fn play(&mut self, played: Duration) {
+        if played >= MARATHON_TIME {
+            self.earn(Achievement::Marathon);
+        }
+    }
+
+    /// The achievements earned this game that weren't unlocked before it
+    pub fn earned(&self) -> &[Achievement] {
+        &self.earned
+    }
+
+    fn earn(&mut self, achievement: Achievement) {
+        if self.unlocked.insert(achievement) {
+            self.earned.push(achievement);
+        }
+    }
+}
+
+impl Observer for Tracker {
+    fn observe(&mut self, game_state: &GameState, event: &Event) {
+        self.combo.observe(game_state, event);
+        match *event {
+            Event::Turned {
+                player: 0,
+                from,
+                to,
+            } if from.counter_clockwise() == to => self.turned_left = true,
+            Event::Ate { player: 0, .. } => {
+                self.earn(Achievement::FirstBite);
+                if self.combo.length() >= HAT_TRICK_BITES {
+                    self.earn(Achievement::HatTrick);
+                }
+            }
+            Event::Grew { player: 0, length } if length >= LONG_SNAKE_LENGTH => {
+                self.earn(Achievement::LongSnake)
+            }
+            Event::Died {
+                player,
+                cause: Cause::Snake(0),
+            } if player != 0 => self.earn(Achievement::Trapper),
+            Event::LevelCompleted if !self.turned_left => self.earn(Achievement::Leftless),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::COMBO_TICKS;
+    use crate::player::{Direction, Position};
+
+    fn moved() -> Event {
+        Event::Moved {
+            player: 0,
+            position: Position::new(1, 1),
+            heading: Direction::Right,
+        }
+    }
+
+    fn ate(player: usize) -> Event {
+        Event::Ate {
+            player,
+            position: Position::new(1, 1),
+        }
+    }
+
+    fn turned(from: Direction, to: Direction) -> Event {
+        Event::Turned {
+            player: 0,
+            from,
+            to,
+        }
+    }
+
+    /// Has `tracker` observe `events`, returning what they earned
+    fn observe(tracker: &mut Tracker, events: &[Event]) -> Vec<Achievement> {
+        let game_state = GameState::seeded(20, 20, 1, 0);
+        let earned = tracker.earned().len();
+        for event in events {
+            tracker.observe(&game_state, event);
+        }
+        tracker.earned()[earned..].to_vec()
+    }
+
+    #[test]
+    fn achievements_are_earned_once_from_the_first_snakes_events() {
+        let mut tracker = Tracker::default().with_unlocked(&BTreeSet::from([Achievement::Trapper]));
+        assert_eq!(observe(&mut tracker, &[moved(), ate(1)]), []);
+        assert_eq!(
+            observe(&mut tracker, &[moved(), ate(0)]),
+            [Achievement::FirstBite]
+        );
+        // Too slow for a hat trick
+        for _ in 0..2 {
+            for _ in 0..=COMBO_TICKS {
+                observe(&mut tracker, &[moved()]);
+            }
+            assert_eq!(observe(&mut tracker, &[moved(), ate(0)]), []);
+        }
+        observe(&mut tracker, &[turned(Direction::Up, Direction::Left)]);
+        observe(&mut tracker, &[ate(0)]);
+        assert_eq!(tracker.earned(), [Achievement::FirstBite]);
+        let killed = Event::Died {
+            player: 1,
+            cause: Cause::Snake(0),
+        };
+        assert_eq!(observe(&mut tracker, &[killed, Event::LevelCompleted]), []);
+    }
+
+    #[test]
+    fn quick_bites_make_a_hat_trick_and_clearing_without_left_turns_is_leftless() {
+        let mut tracker = Tracker::default();
+        let bite = [moved(), ate(0)];
+        observe(&mut tracker, &bite);
+        observe(&mut tracker, &bite);
+        assert_eq!(observe(&mut tracker, &bite), [Achievement::HatTrick]);
+        observe(&mut tracker, &[turned(Direction::Down, Direction::Left)]);
+        assert_eq!(
+            observe(&mut tracker, &[Event::LevelCompleted]),
+            [Achievement::Leftless]
+        );
+    }
+
+    #[test]
+    fn marathons_go_by_the_time_played() {
+        let mut tracker = Tracker::default();
+        tracker.play(MARATHON_TIME - Duration::from_millis(1));
+        assert_eq!(tracker.earned(), []);
+        tracker.play(MARATHON_TIME);
+        assert_eq!(tracker.earned(), [Achievement::Marathon]);
+    }
+}
@@ -0,0 +1,118 @@
+use super::{BoardView, Controller};
+use crate::player::{Direction, Position};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Finds the shortest path to the food with A*, but only takes it when the snake would still
+/// have room to move afterwards. Otherwise it heads for the largest open space.
+pub struct AStar;
+
+impl Controller for AStar {
+    fn decide(&mut self, view: &BoardView) -> Direction {
+        if let Some(direction) = shortest_path_step(view, view.head(), view.food()) {
+            let next = view.head().neighbour(direction);
+            if next.is_some_and(|next| has_room(view, next)) {
+                return direction;
+            }
+        }
+        most_room(view).unwrap_or(view.heading())
+    }
+}
+
+/// Whether the snake would still fit in the space it could reach after moving to `next`
+fn has_room(view: &BoardView, next: Position) -> bool {
+    let needed = view.length() + 1;
+    view.reachable_area(next, needed) >= needed
+}
+
+/// The safe direction leading into the largest open area, preferring the food on a tie
+pub(super) fn most_room(view: &BoardView) -> Option<Direction> {
+    let limit = view.width() * view.height();
+    view.safe_directions()
+        .into_iter()
+        .filter_map(|direction| Some((direction, view.head().neighbour(direction)?)))
+        .max_by_key(|&(_, position)| {
+            (
+                view.reachable_area(position, limit),
+                Reverse(position.manhattan_distance(view.food())),
+            )
+        })
+        .map(|(direction, _)| direction)
+}
+
+/// The first step of the shortest safe path from `start` to `goal`
+pub(super) fn shortest_path_step(
+    view: &BoardView,
+    start: Position,
+    goal: Position,
+) -> Option<Direction> {
+    let mut open = BinaryHeap::from([Reverse((start.manhattan_distance(goal), 0, start))]);
+    let mut came_from: HashMap<Position, (Position, Direction)> = HashMap::new();
+    let mut cost: HashMap<Position, usize> = HashMap::from([(start, 0)]);
+    while let Some(Reverse((_, distance, position))) = open.pop() {
+        if position == goal {
+            let mut step = position;
+            while let Some(&(previous, direction)) = came_from.get(&step) {
+                if previous == start {
+                    return Some(direction);
+                }
+                step = previous;
+            }
+            return None;
+        }
+        if distance > cost[&position] {
+            continue;
+        }
+        for direction in Direction::ALL {
+            if position == start && direction == view.heading().opposite() {
+                continue;
+            }
+            let Some(neighbour) = position.neighbour(direction) else {
+                continue;
+            };
+            let next_distance = distance + 1;
+            let improves = cost
+                .get(&neighbour)
+                .is_none_or(|&known| next_distance < known);
+            if view.is_safe(neighbour) && improves {
+                cost.insert(neighbour, next_distance);
+                came_from.insert(neighbour, (position, direction));
+                let estimate = next_distance + neighbour.manhattan_distance(goal);
+                open.push(Reverse((estimate, next_distance, neighbour)));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn paths_lead_to_the_food() {
+        let mut game_state = GameState::seeded(30, 20, 1, 4);
+        let mut controller = AStar;
+        let score = game_state.score();
+        for _ in 0..200 {
+            let direction = controller.decide(&BoardView::new(&game_state, 0));
+            game_state.steer(0, direction);
+            game_state.tick().unwrap();
+            if game_state.score() > score {
+                return;
+            }
+        }
+        panic!("never reached the food");
+    }
+
+    #[test]
+    fn no_path_past_walls() {
+        let game_state = GameState::seeded(30, 20, 1, 4);
+        let view = BoardView::new(&game_state, 0);
+        assert_eq!(
+            shortest_path_step(&view, view.head(), Position::new(0, 0)),
+            None
+        );
+    }
+}
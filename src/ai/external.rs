@@ -0,0 +1,446 @@
+//! Bots written in any language, run as a separate process that talks over stdin and stdout.
+//!
+//! # Protocol
+//!
+//! Every message is a single line of JSON. The game writes to the bot's stdin:
+//!
+//! * once, before the first tick:
+//!   `{"type":"start","protocol":1,"width":80,"height":25,"you":0,"time_budget_ms":100}`
+//! * before every tick:
+//!   `{"type":"tick","tick":1,"you":0,"food":{...},"foods":[{...},...],"snakes":[...]}`
+//!   where `foods` lists every food on the board, `food` is the one nearest the bot's head, a
+//!   position such as `{"column_number":10,"row_number":10}`, and each snake is
+//!   `{"head":{...},"heading":"Right","body":[{...},...],"score":0,"alive":true}`.
+//!   `body` lists the tail from just behind the head to the tip. The walls are the outermost
+//!   rows and columns, so the open cells run from 1 to `width - 2` and `height - 2`.
+//! * once the game has ended: `{"type":"end","tick":412,"score":17,"alive":false}`
+//!
+//! After each `tick` message the bot must answer within `time_budget_ms` with one line of at
+//! most 1024 bytes, either `{"direction":"up"}` or just `up`. Directions are `up`, `down`, `left`
+//! and `right` in any case, or their first letters. The first answer gets extra time so the bot
+//! can start up.
+//!
+//! A late, unreadable or overlong answer forfeits the turn and the snake carries on in a
+//! straight line. Answers still waiting when the next tick is sent are thrown away, but an answer
+//! that arrives after that can't be told from one to the new tick unless it says which tick it
+//! is for: `{"tick":12,"direction":"up"}` is only ever applied to tick 12. A bot that exits, or
+//! that uses up its allowance of faults, is disqualified and its snake removed from the game.
+
+use super::{BoardView, Controller};
+use crate::player::{Direction, Position};
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+const STARTUP_GRACE: Duration = Duration::from_secs(2);
+/// Longest answer read from a bot, newline included; anything longer is unreadable
+const MAX_ANSWER_LENGTH: u64 = 1024;
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message<'a> {
+    Start {
+        protocol: u32,
+        width: usize,
+        height: usize,
+        you: usize,
+        time_budget_ms: u128,
+    },
+    Tick {
+        tick: u64,
+        you: usize,
+        food: Position,
+        foods: Vec<Position>,
+        snakes: Vec<SnakeMessage<'a>>,
+    },
+    End {
+        tick: u64,
+        score: usize,
+        alive: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct SnakeMessage<'a> {
+    head: Position,
+    heading: Direction,
+    body: &'a std::collections::VecDeque<Position>,
+    score: usize,
+    alive: bool,
+}
+
+#[derive(Deserialize)]
+struct Answer {
+    direction: String,
+    /// The tick being answered, when the bot says
+    #[serde(default)]
+    tick: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BotSettings {
+    pub time_budget: Duration,
+    /// Number of timeouts and malformed answers tolerated before disqualification
+    pub max_faults: Option<usize>,
+    /// Whether the bot's stderr is shown; hide it while drawing the game in the terminal
+    pub show_stderr: bool,
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        Self {
+            time_budget: Duration::from_millis(100),
+            max_faults: None,
+            show_stderr: true,
+        }
+    }
+}
+
+/// A controller backed by an external program
+pub struct ExternalBot {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    answers: Receiver<String>,
+    settings: BotSettings,
+    tick: u64,
+    pub timeouts: usize,
+    pub malformed: usize,
+    exited: bool,
+}
+
+impl ExternalBot {
+    /// Starts `command` through the shell, so it may include arguments
+    pub fn spawn(command: &str, settings: BotSettings) -> Result<Self> {
+        let stderr = if settings.show_stderr {
+            Stdio::inherit()
+        } else {
+            Stdio::null()
+        };
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(stderr)
+            .spawn()
+            .map_err(|error| eyre!("unable to start bot {:?}: {}", command, error))?;
+        let stdin = child.stdin.take();
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| eyre!("unable to read from bot {:?}", command))?;
+        let (sender, answers) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                let mut line = Vec::new();
+                match (&mut reader)
+                    .take(MAX_ANSWER_LENGTH)
+                    .read_until(b'\n', &mut line)
+                {
+                    Ok(0) | Err(_) => return,
+                    Ok(_) => (),
+                }
+                if line.len() as u64 == MAX_ANSWER_LENGTH && !line.ends_with(b"\n") {
+                    // Skip the rest, passing on something that won't read as an answer
+                    if reader.skip_until(b'\n').is_err() {
+                        return;
+                    }
+                    line.clear();
+                }
+                if sender
+                    .send(String::from_utf8_lossy(&line).into_owned())
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            answers,
+            settings,
+            tick: 0,
+            timeouts: 0,
+            malformed: 0,
+            exited: false,
+        })
+    }
+
+    pub fn faults(&self) -> usize {
+        self.timeouts + self.malformed
+    }
+
+    fn send(&mut self, message: &Message) {
+        let Some(stdin) = self.stdin.as_mut() else {
+            return;
+        };
+        let mut line = serde_json::to_string(message).expect("bot messages always serialize");
+        line.push('\n');
+        if stdin
+            .write_all(line.as_bytes())
+            .and_then(|_| stdin.flush())
+            .is_err()
+        {
+            self.exited = true;
+        }
+    }
+
+    fn await_answer(&mut self) -> Option<Direction> {
+        let budget = if self.tick == 1 {
+            self.settings.time_budget + STARTUP_GRACE
+        } else {
+            self.settings.time_budget
+        };
+        let deadline = Instant::now() + budget;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.answers.recv_timeout(timeout) {
+                Ok(line) => match parse_answer(&line) {
+                    Ok((_, Some(tick))) if tick != self.tick => (),
+                    Ok((direction, _)) => return Some(direction),
+                    Err(_) => {
+                        self.malformed += 1;
+                        return None;
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    self.timeouts += 1;
+                    return None;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.exited = true;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl Controller for ExternalBot {
+    fn decide(&mut self, view: &BoardView) -> Direction {
+        self.tick += 1;
+        if self.tick == 1 {
+            self.send(&Message::Start {
+                protocol: PROTOCOL_VERSION,
+                width: view.width(),
+                height: view.height(),
+                you: view.player_index(),
+                time_budget_ms: self.settings.time_budget.as_millis(),
+            });
+        }
+        // Anything still waiting was meant for an earlier tick
+        while self.answers.try_recv().is_ok() {}
+        let snakes = view
+            .game_state()
+            .snakes()
+            .iter()
+            .map(|snake| SnakeMessage {
+                head: snake.player.head_position,
+                heading: snake.player.heading,
+                body: &snake.tail.positions,
+                score: snake.score,
+                alive: snake.alive,
+            })
+            .collect();
+        self.send(&Message::Tick {
+            tick: self.tick,
+            you: view.player_index(),
+            food: view.food(),
+            foods: view.foods().collect(),
+            snakes,
+        });
+        self.await_answer().unwrap_or(view.heading())
+    }
+
+    fn is_disqualified(&self) -> bool {
+        self.exited
+            || self
+                .settings
+                .max_faults
+                .is_some_and(|max_faults| self.faults() > max_faults)
+    }
+
+    fn game_over(&mut self, view: &BoardView) {
+        self.send(&Message::End {
+            tick: self.tick,
+            score: view.me().score,
+            alive: view.me().alive,
+        });
+        self.stdin = None;
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        // Closing stdin asks the bot to stop; give it the usual budget to wrap up before killing it
+        self.stdin = None;
+        let deadline = Instant::now() + self.settings.time_budget;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Reads a direction from either `{"direction":"up"}` or a bare `up`, along with the tick it
+/// answers if the bot gave one
+fn parse_answer(line: &str) -> Result<(Direction, Option<u64>)> {
+    let line = line.trim();
+    let (word, tick) = if line.starts_with('{') {
+        let answer: Answer = serde_json::from_str(line)?;
+        (answer.direction, answer.tick)
+    } else {
+        (line.to_string(), None)
+    };
+    let direction = match word.to_lowercase().as_str() {
+        "up" | "u" => Direction::Up,
+        "down" | "d" => Direction::Down,
+        "left" | "l" => Direction::Left,
+        "right" | "r" => Direction::Right,
+        _ => return Err(eyre!("{:?} is not a direction", line)),
+    };
+    Ok((direction, tick))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::headless::play_match;
+    use crate::testing::TempPath;
+    use std::ops::ControlFlow;
+
+    fn settings() -> BotSettings {
+        BotSettings {
+            time_budget: Duration::from_millis(200),
+            max_faults: Some(0),
+            show_stderr: false,
+        }
+    }
+
+    #[test]
+    fn answers_can_be_json_or_plain_words() {
+        assert_eq!(
+            parse_answer(r#"{"direction":"Up"}"#).unwrap(),
+            (Direction::Up, None)
+        );
+        assert_eq!(
+            parse_answer(r#"{"tick":3,"direction":"l"}"#).unwrap(),
+            (Direction::Left, Some(3))
+        );
+        assert_eq!(parse_answer("left\r").unwrap(), (Direction::Left, None));
+        assert_eq!(parse_answer("D").unwrap(), (Direction::Down, None));
+        assert!(parse_answer("north").is_err());
+        assert!(parse_answer(r#"{"heading":"up"}"#).is_err());
+    }
+
+    #[test]
+    fn bots_steer_their_snake() {
+        let bot = ExternalBot::spawn("while read line; do echo up; done", settings()).unwrap();
+        let mut controllers: Vec<Box<dyn Controller + Send>> = vec![Box::new(bot)];
+        let mut game_state = GameState::seeded(20, 12, 1, 1);
+        let result = play_match(&mut game_state, &mut controllers, Some(100), |_| {
+            ControlFlow::Continue(())
+        });
+        // Heading straight up from the middle reaches the top wall in six ticks
+        assert_eq!(result.ticks, 6);
+        assert_eq!(game_state.snakes()[0].player.heading, Direction::Up);
+    }
+
+    #[test]
+    fn bots_receive_the_documented_messages() {
+        let log = TempPath::new("bot.log");
+        let command = format!(
+            "while read line; do echo \"$line\" >> {:?}; echo up; done",
+            &*log
+        );
+        let bot = ExternalBot::spawn(&command, settings()).unwrap();
+        let mut controllers: Vec<Box<dyn Controller + Send>> = vec![Box::new(bot)];
+        let mut game_state = GameState::seeded(20, 12, 1, 1);
+        play_match(&mut game_state, &mut controllers, Some(100), |_| {
+            ControlFlow::Continue(())
+        });
+        drop(controllers);
+
+        let text = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["type"], "start");
+        assert_eq!(lines[0]["width"], 20);
+        assert_eq!(lines[1]["type"], "tick");
+        assert_eq!(lines[1]["snakes"][0]["heading"], "Right");
+        assert_eq!(lines.last().unwrap()["type"], "end");
+    }
+
+    #[test]
+    fn slow_bots_are_disqualified() {
+        let bot = ExternalBot::spawn("sleep 10", settings()).unwrap();
+        let mut controllers: Vec<Box<dyn Controller + Send>> = vec![Box::new(bot)];
+        let mut game_state = GameState::seeded(20, 12, 1, 1);
+        let result = play_match(&mut game_state, &mut controllers, Some(100), |_| {
+            ControlFlow::Continue(())
+        });
+        assert_eq!(result.ticks, 1);
+        assert_eq!(result.alive, vec![false]);
+    }
+
+    #[test]
+    fn malformed_answers_forfeit_the_turn() {
+        let settings = BotSettings {
+            max_faults: None,
+            ..settings()
+        };
+        let mut bot =
+            ExternalBot::spawn("while read line; do echo sideways; done", settings).unwrap();
+        let game_state = GameState::seeded(20, 12, 1, 1);
+        let direction = bot.decide(&BoardView::new(&game_state, 0));
+        assert_eq!(direction, Direction::Right);
+        assert_eq!(bot.malformed, 1);
+        assert!(!bot.is_disqualified());
+    }
+
+    #[test]
+    fn answers_to_other_ticks_are_thrown_away() {
+        let command = r#"while read line; do echo '{"tick":9,"direction":"down"}'; echo '{"tick":1,"direction":"up"}'; done"#;
+        let mut bot = ExternalBot::spawn(command, settings()).unwrap();
+        let game_state = GameState::seeded(20, 12, 1, 1);
+        let direction = bot.decide(&BoardView::new(&game_state, 0));
+        assert_eq!(direction, Direction::Up);
+        assert_eq!(bot.faults(), 0);
+    }
+
+    #[test]
+    fn overlong_answers_are_malformed() {
+        let settings = BotSettings {
+            max_faults: None,
+            ..settings()
+        };
+        let command = "while read line; do head -c 100000 /dev/zero | tr '\\0' u; echo; done";
+        let mut bot = ExternalBot::spawn(command, settings).unwrap();
+        let game_state = GameState::seeded(20, 12, 1, 1);
+        let direction = bot.decide(&BoardView::new(&game_state, 0));
+        assert_eq!(direction, Direction::Right);
+        assert_eq!(bot.malformed, 1);
+    }
+
+    #[test]
+    fn bots_that_exit_are_disqualified() {
+        let mut bot = ExternalBot::spawn("true", settings()).unwrap();
+        let game_state = GameState::seeded(20, 12, 1, 1);
+        bot.decide(&BoardView::new(&game_state, 0));
+        assert!(bot.is_disqualified());
+    }
+}
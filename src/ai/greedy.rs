@@ -0,0 +1,40 @@
+use super::{BoardView, Controller};
+use crate::player::Direction;
+
+/// Moves to whichever safe neighbour is closest to the food, without looking any further ahead
+pub struct Greedy;
+
+impl Controller for Greedy {
+    fn decide(&mut self, view: &BoardView) -> Direction {
+        let food = view.food();
+        view.safe_directions()
+            .into_iter()
+            .filter_map(|direction| Some((direction, view.head().neighbour(direction)?)))
+            .min_by_key(|(_, position)| position.manhattan_distance(food))
+            .map_or(view.heading(), |(direction, _)| direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn picks_the_safe_move_closest_to_the_food() {
+        let mut game_state = GameState::seeded(30, 20, 1, 2);
+        for _ in 0..20 {
+            let view = BoardView::new(&game_state, 0);
+            let distance_after = |direction: Direction| {
+                let next = view.head().neighbour(direction).unwrap();
+                next.manhattan_distance(view.food())
+            };
+            let chosen = Greedy.decide(&view);
+            for direction in view.safe_directions() {
+                assert!(distance_after(chosen) <= distance_after(direction));
+            }
+            game_state.steer(0, chosen);
+            game_state.tick().unwrap();
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use super::astar::{most_room, shortest_path_step};
+use super::{BoardView, Controller};
+use crate::player::{Direction, Position};
+use std::collections::HashMap;
+
+/// Follows a fixed cycle that visits every open cell exactly once. The snake joins the cycle in
+/// its first moves, while it is still short, and never leaves it: its body then lies along the
+/// cycle behind the head, so the cell ahead is always free and it keeps eating until the board
+/// is full. Boards with no cycle fall back to chasing the food.
+#[derive(Default)]
+pub struct Hamiltonian {
+    /// The next direction along the cycle for every open cell, built for the current board size
+    cycle: Option<((usize, usize), HashMap<Position, Direction>)>,
+}
+
+impl Controller for Hamiltonian {
+    fn decide(&mut self, view: &BoardView) -> Direction {
+        let size = (view.width(), view.height());
+        if self
+            .cycle
+            .as_ref()
+            .is_none_or(|(built_for, _)| *built_for != size)
+        {
+            self.cycle = Some((size, build_cycle(view.width(), view.height())));
+        }
+        let (_, cycle) = self.cycle.as_ref().expect("the cycle was just built");
+        let safe = view.safe_directions();
+        if cycle.is_empty() {
+            return shortest_path_step(view, view.head(), view.food())
+                .filter(|direction| safe.contains(direction))
+                .or_else(|| most_room(view))
+                .unwrap_or(view.heading());
+        }
+        match cycle.get(&view.head()) {
+            Some(&direction) if safe.contains(&direction) => direction,
+            _ => join(view, cycle, &safe),
+        }
+    }
+}
+
+/// Picks a step towards joining the cycle when following it would reverse the snake. The best
+/// cell to step onto is one the cycle doesn't lead straight back to the head from, so the snake
+/// can follow it from there, and that holds no food, so the snake is still short when it does.
+fn join(view: &BoardView, cycle: &HashMap<Position, Direction>, safe: &[Direction]) -> Direction {
+    let head = view.head();
+    safe.iter()
+        .copied()
+        .min_by_key(|&direction| {
+            let cell = head
+                .neighbour(direction)
+                .expect("safe cells are on the board");
+            let leads_back = cycle
+                .get(&cell)
+                .is_none_or(|&next| cell.neighbour(next) == Some(head));
+            (leads_back, view.foods().any(|food| food == cell))
+        })
+        .unwrap_or(view.heading())
+}
+
+/// Builds a cycle through the cells inside the walls. A cycle only exists when one side of the
+/// open area has an even length; otherwise this returns an empty map.
+fn build_cycle(width: usize, height: usize) -> HashMap<Position, Direction> {
+    let columns = width.saturating_sub(2);
+    let rows = height.saturating_sub(2);
+    let order: Vec<(usize, usize)> = if rows.is_multiple_of(2) && columns >= 2 {
+        snake_order(columns, rows)
+    } else if columns.is_multiple_of(2) && rows >= 2 {
+        snake_order(rows, columns)
+            .into_iter()
+            .map(|(row, column)| (column, row))
+            .collect()
+    } else {
+        return HashMap::new();
+    };
+
+    let cells: Vec<Position> = order
+        .into_iter()
+        .map(|(column, row)| Position::new(column + 1, row + 1))
+        .collect();
+    let next_cells = cells.iter().cycle().skip(1);
+    cells
+        .iter()
+        .zip(next_cells)
+        .map(|(&cell, &next)| {
+            let direction = Direction::ALL
+                .into_iter()
+                .find(|&direction| cell.neighbour(direction) == Some(next))
+                .expect("consecutive cells on the cycle are adjacent");
+            (cell, direction)
+        })
+        .collect()
+}
+
+/// Visits a `columns` x `rows` grid (with an even number of rows) as a closed loop: along the
+/// top row, back and forth across the rest while leaving the first column free, then up that
+/// first column to the start.
+fn snake_order(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let mut order: Vec<(usize, usize)> = (0..columns).map(|column| (column, 0)).collect();
+    for row in 1..rows {
+        if !row.is_multiple_of(2) {
+            order.extend((1..columns).rev().map(|column| (column, row)));
+        } else {
+            order.extend((1..columns).map(|column| (column, row)));
+        }
+    }
+    order.extend((1..rows).rev().map(|row| (0, row)));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::tests::play;
+    use crate::game::GameState;
+
+    #[test]
+    fn cycle_visits_every_open_cell_once() {
+        for (width, height) in [(8, 8), (80, 25), (7, 10), (10, 7)] {
+            let cycle = build_cycle(width, height);
+            assert_eq!(cycle.len(), (width - 2) * (height - 2));
+            let start = Position::new(1, 1);
+            let mut position = start;
+            for step in 1..=cycle.len() {
+                position = position.neighbour(cycle[&position]).unwrap();
+                assert_eq!(position == start, step == cycle.len());
+            }
+        }
+    }
+
+    #[test]
+    fn odd_sized_boards_have_no_cycle() {
+        assert!(build_cycle(9, 9).is_empty());
+    }
+
+    #[test]
+    fn joins_the_cycle_where_it_runs_back_over_the_tail() {
+        let mut game_state = GameState::seeded(8, 8, 1, 11);
+        let cycle = build_cycle(8, 8);
+        assert_eq!(
+            cycle[&game_state.snakes()[0].player.head_position],
+            Direction::Left
+        );
+        let mut hamiltonian = Hamiltonian::default();
+        for _ in 0..2 {
+            let direction = hamiltonian.decide(&BoardView::new(&game_state, 0));
+            game_state.steer(0, direction);
+            game_state.tick();
+        }
+        // The body now lies along the cycle, each cell leading on to the one ahead of it
+        let body: Vec<Position> = game_state.snakes()[0].body().collect();
+        for cells in body.windows(2) {
+            assert_eq!(cells[1].neighbour(cycle[&cells[1]]), Some(cells[0]));
+        }
+    }
+
+    #[test]
+    fn fills_small_boards() {
+        for (width, height) in [(8, 8), (9, 8), (8, 7)] {
+            for seed in 0..20 {
+                let mut game_state = GameState::seeded(width, height, 1, seed);
+                play(&mut Hamiltonian::default(), &mut game_state, 10_000);
+                let snake = &game_state.snakes()[0];
+                assert!(
+                    snake.alive,
+                    "died on {}x{} with seed {}",
+                    width, height, seed
+                );
+                assert_eq!(snake.body().count(), (width - 2) * (height - 2));
+                assert!(game_state.is_over());
+            }
+        }
+    }
+}
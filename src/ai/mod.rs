@@ -0,0 +1,281 @@
+//! Computer controlled snakes. Every bot implements [`Controller`], which only gets to look at
+//! the board through a read-only [`BoardView`] and answers with the direction to head next.
+
+mod astar;
+pub mod external;
+mod greedy;
+mod hamiltonian;
+
+pub use astar::AStar;
+pub use external::ExternalBot;
+pub use greedy::Greedy;
+pub use hamiltonian::Hamiltonian;
+
+use crate::game::{determine_game_cell, GameCell, GameState};
+use crate::player::{Direction, Position, Snake};
+use color_eyre::eyre::{eyre, Report};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+pub trait Controller {
+    /// Chooses the direction for the next tick. Returning a reversal is allowed but ignored.
+    fn decide(&mut self, view: &BoardView) -> Direction;
+
+    /// Whether the controller has broken the rules badly enough for its snake to be removed
+    fn is_disqualified(&self) -> bool {
+        false
+    }
+
+    /// Called once the game has ended
+    fn game_over(&mut self, _view: &BoardView) {}
+}
+
+impl<T: Controller + ?Sized> Controller for Box<T> {
+    fn decide(&mut self, view: &BoardView) -> Direction {
+        (**self).decide(view)
+    }
+
+    fn is_disqualified(&self) -> bool {
+        (**self).is_disqualified()
+    }
+
+    fn game_over(&mut self, view: &BoardView) {
+        (**self).game_over(view)
+    }
+}
+
+/// What a controller is allowed to see: the whole board, from the point of view of one snake
+pub struct BoardView<'a> {
+    game_state: &'a GameState,
+    player_index: usize,
+}
+
+impl<'a> BoardView<'a> {
+    pub fn new(game_state: &'a GameState, player_index: usize) -> Self {
+        Self {
+            game_state,
+            player_index,
+        }
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        self.game_state
+    }
+
+    pub fn player_index(&self) -> usize {
+        self.player_index
+    }
+
+    pub fn width(&self) -> usize {
+        self.game_state.game_area.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.game_state.game_area.height
+    }
+
+    pub fn me(&self) -> &Snake {
+        &self.game_state.snakes()[self.player_index]
+    }
+
+    pub fn head(&self) -> Position {
+        self.me().player.head_position
+    }
+
+    pub fn heading(&self) -> Direction {
+        self.me().player.heading
+    }
+
+    /// Number of cells the snake covers, head included
+    pub fn length(&self) -> usize {
+        self.me().tail.positions.len() + 1
+    }
+
+    /// Every food on the board
+    pub fn foods(&self) -> impl Iterator<Item = Position> + 'a {
+        self.game_state.powerup_positions()
+    }
+
+    /// The food nearest the head, which the bots go after
+    pub fn food(&self) -> Position {
+        self.game_state.nearest_food(self.head())
+    }
+
+    pub fn cell(&self, position: Position) -> GameCell {
+        determine_game_cell(self.game_state, position.column_number, position.row_number)
+    }
+
+    /// Whether moving into `position` next tick would leave the snake alive
+    pub fn is_safe(&self, position: Position) -> bool {
+        matches!(self.cell(position), GameCell::Empty | GameCell::Powerup)
+    }
+
+    /// The directions that do not reverse the snake or run it straight into something
+    pub fn safe_directions(&self) -> Vec<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| direction != self.heading().opposite())
+            .filter(|&direction| {
+                self.head()
+                    .neighbour(direction)
+                    .is_some_and(|position| self.is_safe(position))
+            })
+            .collect()
+    }
+
+    /// Counts the free cells reachable from `start`, stopping early once `limit` is reached
+    pub fn reachable_area(&self, start: Position, limit: usize) -> usize {
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(position) = queue.pop_front() {
+            if seen.len() >= limit {
+                break;
+            }
+            for direction in Direction::ALL {
+                let Some(neighbour) = position.neighbour(direction) else {
+                    continue;
+                };
+                if self.is_safe(neighbour) && seen.insert(neighbour) {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        seen.len()
+    }
+}
+
+/// The bots that ship with the game, from easiest to hardest to beat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// Heads straight for the food, only avoiding immediate collisions
+    Easy,
+    /// Plans a path to the food with A* and refuses moves that would trap it
+    Medium,
+    /// Follows a Hamiltonian cycle through the board, which eventually fills it completely
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn controller(self) -> Box<dyn Controller + Send> {
+        match self {
+            Difficulty::Easy => Box::new(Greedy),
+            Difficulty::Medium => Box::new(AStar),
+            Difficulty::Hard => Box::new(Hamiltonian::default()),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "easy" | "greedy" => Ok(Difficulty::Easy),
+            "medium" | "astar" => Ok(Difficulty::Medium),
+            "hard" | "hamiltonian" => Ok(Difficulty::Hard),
+            _ => Err(eyre!(
+                "unknown difficulty {:?}, expected easy, medium or hard",
+                value
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rules;
+
+    /// Runs a single bot until it dies or `ticks` have passed
+    pub(super) fn play(controller: &mut dyn Controller, game_state: &mut GameState, ticks: usize) {
+        for _ in 0..ticks {
+            let direction = controller.decide(&BoardView::new(game_state, 0));
+            game_state.steer(0, direction);
+            if game_state.tick().is_none() {
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn safe_directions_exclude_walls_and_reversals() {
+        let mut game_state = GameState::seeded(20, 10, 1, 1);
+        game_state.steer(0, Direction::Up);
+        while game_state.snakes()[0].player.head_position.row_number > 1 {
+            game_state.tick();
+        }
+        let view = BoardView::new(&game_state, 0);
+        let directions = view.safe_directions();
+        assert!(!directions.contains(&Direction::Up));
+        assert!(!directions.contains(&Direction::Down));
+        assert!(directions.contains(&Direction::Left));
+        assert!(directions.contains(&Direction::Right));
+    }
+
+    #[test]
+    fn bots_go_for_the_nearest_of_several_foods() {
+        let game_state = GameState::seeded(30, 20, 1, 31).with_rules(Rules {
+            wrap: false,
+            food: 5,
+        });
+        let view = BoardView::new(&game_state, 0);
+        let head = view.head();
+        assert_eq!(view.foods().count(), 5);
+        let nearest = view
+            .foods()
+            .map(|food| food.manhattan_distance(head))
+            .min()
+            .unwrap();
+        assert_eq!(view.food().manhattan_distance(head), nearest);
+        // The first food is further away, behind the snake
+        assert_ne!(view.food(), game_state.powerup_position());
+        for difficulty in [Difficulty::Easy, Difficulty::Medium] {
+            let next = head
+                .neighbour(difficulty.controller().decide(&view))
+                .unwrap();
+            assert!(next.manhattan_distance(view.food()) < nearest);
+        }
+    }
+
+    #[test]
+    fn difficulties_parse_from_their_names() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(
+                difficulty.to_string().parse::<Difficulty>().unwrap(),
+                difficulty
+            );
+        }
+        assert_eq!("astar".parse::<Difficulty>().unwrap(), Difficulty::Medium);
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn every_bot_scores_on_an_open_board() {
+        for difficulty in Difficulty::ALL {
+            let mut game_state = GameState::seeded(24, 14, 1, 9);
+            play(difficulty.controller().as_mut(), &mut game_state, 2_000);
+            assert!(
+                game_state.score() >= 3,
+                "{} scored {}",
+                difficulty,
+                game_state.score()
+            );
+        }
+    }
+}
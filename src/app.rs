@@ -1,11 +1,42 @@
-use crate::game::GameState;
-use crate::welcome::display_welcome_screen;
+use crate::achievement::{Achievement, Tracker};
+use crate::ai::Difficulty;
+use crate::backend::{Backend, Clear};
+use crate::config::Config;
+use crate::game::{GameState, Mode, Outcome, RunStats, Tally};
+use crate::keymap::Bindings;
+use crate::leaderboard::Leaderboard;
+use crate::pages::{pick, prompt, show_page};
+use crate::profile::{Profiles, MAX_NAME_LENGTH};
+use crate::replay::Replay;
+use crate::results::{show_results, Next, Results};
+use crate::save::{SavedGame, DEFAULT_SAVE_PATH};
+use crate::settings::edit_settings;
+use crate::spectator::watch_replay;
+use crate::welcome::{display_welcome_screen, Choice, Menu};
+use color_eyre::eyre::{eyre, Result};
 use std::fs;
-use std::io::{stdin, stdout, Write};
-use termion::event::Key;
-use termion::input::TermRead;
-use termion::raw::IntoRawMode;
-use termion::{clear, cursor};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// The help page: the rules, then what each bound key does, so it always matches the config
+fn help(keys: &Bindings) -> Vec<String> {
+    let mut lines: Vec<String> = [
+        "HELP",
+        "",
+        "EAT THE * TO GROW",
+        "DON'T HIT WALLS OR SNAKES",
+        "",
+    ]
+    .map(String::from)
+    .into();
+    for action in keys.actions() {
+        let bound: Vec<String> = keys.keys(action).iter().map(ToString::to_string).collect();
+        if !bound.is_empty() {
+            lines.push(format!("{}: {}", action, bound.join(" or ")).to_uppercase());
+        }
+    }
+    lines
+}
 
 enum Screen {
     Welcome,
@@ -16,10 +47,37 @@ enum Screen {
 pub struct App {
     state: Screen,
     game_state: GameState,
-    score: Option<usize>,
-    high_score: usize,
-    width: usize,
-    height: usize,
+    /// How the last game went, until the score screen has shown it
+    last_run: Option<RunStats>,
+    leaderboard: Leaderboard,
+    /// Where the leaderboard is written after each game; it lasts until the app exits when
+    /// `None`
+    leaderboard_path: Option<PathBuf>,
+    profiles: Profiles,
+    /// Where the profiles are written after each game and whenever one is picked or made;
+    /// they last until the app exits when `None`
+    profiles_path: Option<PathBuf>,
+    config: Config,
+    /// Where changes made on the settings screen are written; they last until the app exits
+    /// when `None`
+    config_path: Option<PathBuf>,
+    /// Where the next game's seed comes from; random when `None`
+    seed: Option<u64>,
+    /// The save slot written from the pause menu and offered on the welcome screen
+    save_path: PathBuf,
+    menu: Menu,
+    /// The computer snake in the game being played, if there is one
+    opponent: Option<Difficulty>,
+    /// How many people are playing the current game at the keyboard
+    players: usize,
+    /// The first player's stats for the current game
+    tally: Tally,
+    /// The first player's achievements in the current game
+    achievements: Tracker,
+    /// The current game turn by turn, if it was played from the start
+    replay: Option<Replay>,
+    /// Where the replays menu looks for `.replay` files
+    replay_dir: PathBuf,
 }
 
 impl Default for App {
@@ -33,12 +91,178 @@ impl App {
         Self {
             state: Screen::Welcome,
             game_state: GameState::new(width, height),
-            score: None,
-            high_score,
-            width,
-            height,
+            last_run: None,
+            leaderboard: Leaderboard::from_scores(vec![high_score]),
+            leaderboard_path: None,
+            profiles: Profiles::default(),
+            profiles_path: None,
+            config: Config {
+                width,
+                height,
+                ..Config::default()
+            },
+            config_path: None,
+            seed: None,
+            save_path: PathBuf::from(DEFAULT_SAVE_PATH),
+            menu: Menu::default(),
+            opponent: None,
+            players: 1,
+            tally: Tally::default(),
+            achievements: Tracker::default(),
+            replay: None,
+            replay_dir: PathBuf::from("."),
+        }
+    }
+
+    pub fn with_save_path(mut self, save_path: impl Into<PathBuf>) -> Self {
+        self.save_path = save_path.into();
+        self
+    }
+
+    /// Plays by `config` instead of the defaults, keeping the seed of the game already set up
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        let seed = self.game_state.seed();
+        self.game_state = self.seeded_game(1, seed);
+        self
+    }
+
+    /// Starts from `leaderboard`, which is saved to `path` after every game
+    pub fn with_leaderboard(mut self, leaderboard: Leaderboard, path: impl Into<PathBuf>) -> Self {
+        self.leaderboard = leaderboard;
+        self.leaderboard_path = Some(path.into());
+        self
+    }
+
+    /// Plays as the profile picked last in `profiles`, which are saved to `path` as they change
+    pub fn with_profiles(mut self, profiles: Profiles, path: impl Into<PathBuf>) -> Self {
+        self.profiles = profiles;
+        self.profiles_path = Some(path.into());
+        self
+    }
+
+    pub fn with_config_path(mut self, config_path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(config_path.into());
+        self
+    }
+
+    pub fn with_replay_dir(mut self, replay_dir: impl Into<PathBuf>) -> Self {
+        self.replay_dir = replay_dir.into();
+        self
+    }
+
+    /// Plays the same games every time: the demo and each game take the next seed after `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self.game_state = self.new_game();
+        self
+    }
+
+    fn next_seed(&mut self) -> u64 {
+        match &mut self.seed {
+            Some(seed) => {
+                let next = *seed;
+                *seed = seed.wrapping_add(1);
+                next
+            }
+            None => rand::random(),
+        }
+    }
+
+    fn seeded_game(&self, players: usize, seed: u64) -> GameState {
+        GameState::seeded(self.config.width, self.config.height, players, seed)
+            .with_rules(self.config.rules())
+    }
+
+    fn new_game(&mut self) -> GameState {
+        let seed = self.next_seed();
+        self.seeded_game(1, seed)
+    }
+
+    /// Sets up the next game for the mode picked in the menu, keeping the seed it was given
+    fn start_game(&mut self) {
+        self.opponent = (self.menu.mode == Mode::Versus).then_some(self.menu.difficulty);
+        self.players = if self.menu.mode == Mode::TwoPlayer {
+            2
+        } else {
+            1
+        };
+        let seed = self.game_state.seed();
+        self.retry(seed);
+    }
+
+    /// Starts the same kind of game over with the next seed
+    fn restart(&mut self) {
+        let seed = self.next_seed();
+        self.retry(seed);
+    }
+
+    /// Starts the same kind of game over from `seed`, recording it from the first tick
+    fn retry(&mut self, seed: u64) {
+        self.game_state = self.seeded_game(self.snake_count(), seed);
+        let unlocked = &self.profiles.current().achievements;
+        self.tally = Tally::default();
+        self.achievements = Tracker::default().with_unlocked(unlocked);
+        // Player 1 plays as the profile picked on the menu
+        let names = std::iter::once(self.profiles.current().name.clone())
+            .chain((2..=self.players).map(|player| format!("player {}", player)))
+            .chain(
+                self.opponent
+                    .map(|difficulty| format!("computer ({})", difficulty)),
+            )
+            .collect();
+        let steering = (0..self.players)
+            .map(|player| self.config.keys.steering(player))
+            .collect();
+        self.replay = Some(Replay::new(&self.game_state, names).with_steering(steering));
+    }
+
+    fn snake_count(&self) -> usize {
+        self.players + usize::from(self.opponent.is_some())
+    }
+
+    /// The mode of the game being played, which for a resumed game isn't the one on the menu
+    fn mode(&self) -> Mode {
+        match (self.players, self.opponent) {
+            (2, _) => Mode::TwoPlayer,
+            (_, Some(_)) => Mode::Versus,
+            _ => Mode::Solo,
         }
     }
+
+    fn save_profiles(&self) -> Result<()> {
+        match &self.profiles_path {
+            Some(path) => self.profiles.save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Takes the game out of the save slot. The slot is emptied, so each save is resumed once.
+    fn resume(&mut self) -> Result<()> {
+        let saved_game = SavedGame::load(&self.save_path)?;
+        let area = saved_game.game_state.game_area;
+        if (area.width, area.height) != (self.config.width, self.config.height) {
+            return Err(eyre!(
+                "the saved game is on a {}x{} board but this one is {}x{}",
+                area.width,
+                area.height,
+                self.config.width,
+                self.config.height
+            ));
+        }
+        fs::remove_file(&self.save_path)?;
+        self.game_state = saved_game.game_state;
+        self.opponent = saved_game.opponent;
+        self.players = saved_game.players;
+        self.tally = saved_game.tally;
+        // Whoever resumes the game may have unlocked more since it was saved
+        let unlocked = &self.profiles.current().achievements;
+        self.achievements = saved_game.achievements.with_unlocked(unlocked);
+        // The turns made before the game was saved weren't kept
+        self.replay = None;
+        Ok(())
+    }
+
     fn change_screen(&mut self) {
         self.state = match self.state {
             Screen::Welcome => Screen::GamePlay,
@@ -46,66 +270,633 @@ impl App {
             Screen::Score => Screen::Welcome,
         };
     }
-    pub fn run(&mut self) {
+
+    /// Shows the menu and whichever page is picked from it. Moves on to the game once one has
+    /// been started or resumed.
+    fn welcome(&mut self, terminal: &mut impl Backend) -> Result<ControlFlow<()>> {
+        terminal.clear(Clear::All)?;
+        terminal.move_to(0, 0)?;
+        let seed = self.next_seed();
+        self.menu.offer_continue(self.save_path.exists());
+        self.menu
+            .offer_profiles(self.profiles.names(), self.profiles.selected());
+        let Some(choice) = display_welcome_screen(terminal, &self.config, seed, &mut self.menu)?
+        else {
+            return Ok(ControlFlow::Break(()));
+        };
+        if self.menu.profile != self.profiles.selected() {
+            self.profiles.select(self.menu.profile);
+            self.save_profiles()?;
+        }
+        let (width, height) = (self.config.width, self.config.height);
+        match choice {
+            Choice::Play
+                if self.menu.mode == Mode::TwoPlayer && self.config.keys.players.len() < 2 =>
+            {
+                let lines = ["PLAYER 2 HAS NO KEYS", "", "ADD THEM IN SETTINGS"];
+                return show_page(terminal, width, height, &lines);
+            }
+            Choice::Play => self.start_game(),
+            Choice::Continue => {
+                if let Err(error) = self.resume() {
+                    let error = error.to_string();
+                    let lines = ["THE SAVED GAME COULD NOT BE LOADED", "", &error];
+                    return show_page(terminal, width, height, &lines);
+                }
+            }
+            Choice::Leaderboard => {
+                let mut lines = vec![String::from("LEADERBOARD"), String::new()];
+                lines.extend(
+                    self.leaderboard
+                        .scores()
+                        .iter()
+                        .enumerate()
+                        .map(|(index, score)| format!("{}. {}", index + 1, score)),
+                );
+                if self.leaderboard.scores().is_empty() {
+                    lines.push(String::from("NO SCORES YET"));
+                }
+                let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                return show_page(terminal, width, height, &lines);
+            }
+            Choice::NewProfile => return self.new_profile(terminal),
+            Choice::Stats => {
+                let profile = self.profiles.current();
+                let mut lines = vec![profile.name.to_uppercase(), String::new()];
+                lines.extend(profile.stats.report(self.config.theme.fill(), width - 4));
+                let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                return show_page(terminal, width, height, &lines);
+            }
+            Choice::Achievements => {
+                let profile = self.profiles.current();
+                let unlocked = profile.achievements.len();
+                let total = Achievement::ALL.len();
+                let mut lines = vec![
+                    format!("ACHIEVEMENTS {}/{}", unlocked, total),
+                    String::new(),
+                ];
+                lines.extend(profile.achievement_lines());
+                let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                return show_page(terminal, width, height, &lines);
+            }
+            Choice::Replays => return self.replays(terminal),
+            Choice::Settings => return self.settings(terminal),
+            Choice::Help => {
+                let lines = help(&self.config.keys);
+                let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                return show_page(terminal, width, height, &lines);
+            }
+        }
+        self.change_screen();
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Lets the player change the config, which is saved to the config file if there is one.
+    /// Boards are kept small enough for the terminal, with a row spare for the pause message.
+    fn settings(&mut self, terminal: &mut impl Backend) -> Result<ControlFlow<()>> {
+        let (columns, rows) = terminal.size()?;
+        let seed = self.next_seed();
+        let config = match edit_settings(
+            terminal,
+            &self.config,
+            seed,
+            (columns, rows.saturating_sub(1)),
+        )? {
+            ControlFlow::Break(()) => return Ok(ControlFlow::Break(())),
+            ControlFlow::Continue(None) => return Ok(ControlFlow::Continue(())),
+            ControlFlow::Continue(Some(config)) => config,
+        };
+        if let Some(config_path) = &self.config_path {
+            config.save(config_path)?;
+        }
+        self.config = config;
+        self.game_state = self.new_game();
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Asks for a name and plays as a new profile by that name
+    fn new_profile(&mut self, terminal: &mut impl Backend) -> Result<ControlFlow<()>> {
+        let (width, height) = (self.config.width, self.config.height);
+        let name = match prompt(terminal, width, height, "NEW PLAYER", MAX_NAME_LENGTH)? {
+            ControlFlow::Break(()) => return Ok(ControlFlow::Break(())),
+            ControlFlow::Continue(None) => return Ok(ControlFlow::Continue(())),
+            ControlFlow::Continue(Some(name)) => name,
+        };
+        if let Err(error) = self.profiles.add(&name) {
+            let error = error.to_string().to_uppercase();
+            let lines = ["NO NEW PLAYER", "", &error];
+            return show_page(terminal, width, height, &lines);
+        }
+        self.save_profiles()?;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Lists the replays in the replay folder and plays the one picked
+    fn replays(&mut self, terminal: &mut impl Backend) -> Result<ControlFlow<()>> {
+        let (width, height) = (self.config.width, self.config.height);
+        let paths = replay_files(&self.replay_dir)?;
+        if paths.is_empty() {
+            let lines = ["REPLAYS", "", "NO .replay FILES FOUND"];
+            return show_page(terminal, width, height, &lines);
+        }
+        let names: Vec<String> = paths
+            .iter()
+            .map(|path| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into()
+            })
+            .collect();
+        let Some(index) = (match pick(terminal, width, height, "REPLAYS", &names)? {
+            ControlFlow::Break(()) => return Ok(ControlFlow::Break(())),
+            ControlFlow::Continue(index) => index,
+        }) else {
+            return Ok(ControlFlow::Continue(()));
+        };
+        match Replay::load(&paths[index]) {
+            Ok(replay) => watch_replay(terminal, &replay, None, self.config.tick_duration())?,
+            Err(error) => {
+                let error = error.to_string();
+                let lines = ["THE REPLAY COULD NOT BE LOADED", "", &error];
+                return show_page(terminal, width, height, &lines);
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    pub fn run(&mut self, terminal: &mut impl Backend) -> Result<()> {
         'game_loop: loop {
             match self.state {
                 Screen::Welcome => {
-                    print!("{}{}", clear::All, cursor::Goto(1, 1));
-                    if display_welcome_screen(self.width, self.height).is_none() {
+                    if self.welcome(terminal)?.is_break() {
                         break 'game_loop;
                     }
-                    self.change_screen();
                 }
                 Screen::GamePlay => {
-                    let score = self.game_state.run().unwrap();
-                    self.score = Some(score);
-                    self.change_screen();
-                    self.game_state = GameState::new(self.width, self.height)
+                    let mut opponents: Vec<_> = self
+                        .opponent
+                        .map(Difficulty::controller)
+                        .into_iter()
+                        .collect();
+                    let outcome = self.game_state.run(
+                        terminal,
+                        &mut opponents,
+                        &self.config,
+                        &mut self.tally,
+                        &mut self.achievements,
+                        self.replay.as_mut(),
+                    )?;
+                    // Achievements stay unlocked however the game ended
+                    if self.profiles.unlock(self.achievements.earned()) {
+                        self.save_profiles()?;
+                    }
+                    match outcome {
+                        Outcome::Over(stats) => {
+                            self.last_run = Some(stats);
+                            self.change_screen();
+                            self.game_state = self.new_game();
+                        }
+                        Outcome::Quit => break 'game_loop,
+                        Outcome::Save => {
+                            SavedGame::new(&self.game_state, self.opponent)
+                                .with_players(self.players)
+                                .with_tally(self.tally.clone())
+                                .with_achievements(self.achievements.clone())
+                                .save(&self.save_path)?;
+                            self.state = Screen::Welcome;
+                            self.game_state = self.new_game();
+                        }
+                        Outcome::Restart => self.restart(),
+                    }
                 }
                 Screen::Score => {
-                    let stdin = stdin();
-                    let mut stdout = stdout().into_raw_mode().unwrap();
-                    if let Some(score) = self.score {
-                        if score > self.high_score {
-                            let content = score.to_string();
-                            fs::write("high_score.txt", content).unwrap();
-                            write!(
-                                stdout,
-                                "{}{}YOU GOT THE NEW HIGH SCORE: {}!!! \n\r\n\rTHE PREVIOUS HIGH SCRORE WAS {}\n\r\n\rPress the SPACEBAR to contiue",
-                                clear::All,
-                                cursor::Goto(1, 1),
-                                score,
-                                self.high_score
-                                )
-                                .unwrap();
-                            self.high_score = score;
-                        } else {
-                            write!(
-                                stdout,
-                                "{}{}YOU SCORED: {}!!! \n\r\n\rHIGH SCORE: {}\n\r\n\rPress the SPACEBAR to contiue",
-                                clear::All,
-                                cursor::Goto(1, 1),
-                                score,
-                                self.high_score
-                            )
-                            .unwrap();
-                        }
+                    let Some(stats) = self.last_run.take() else {
+                        self.change_screen();
+                        continue;
+                    };
+                    let previous_best = self.leaderboard.high_score();
+                    let rank = self.leaderboard.insert(stats.score);
+                    if let (Some(_), Some(path)) = (rank, &self.leaderboard_path) {
+                        self.leaderboard.save(path)?;
                     }
-                    stdout.flush().unwrap();
-                    for c in stdin.keys() {
-                        match c.unwrap() {
-                            Key::Char('q') | Key::Ctrl('c') => {
-                                break 'game_loop;
-                            }
-                            Key::Char(' ') => {
-                                break;
-                            }
-                            _ => (),
+                    self.profiles.record(self.mode(), &stats);
+                    self.save_profiles()?;
+                    let results = Results {
+                        stats,
+                        previous_best,
+                        rank,
+                    };
+                    let replay = self.replay.as_ref();
+                    match show_results(terminal, &self.config, &results, replay, &self.replay_dir)?
+                    {
+                        Next::Quit => break 'game_loop,
+                        Next::Menu => self.change_screen(),
+                        Next::Retry => {
+                            self.retry(stats.seed);
+                            self.state = Screen::GamePlay;
+                        }
+                        Next::NewGame => {
+                            self.restart();
+                            self.state = Screen::GamePlay;
                         }
                     }
-                    self.change_screen();
                 }
             };
         }
+        Ok(())
+    }
+}
+
+/// The `.replay` files in `dir`, sorted by name
+fn replay_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "replay")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::memory::{Input, MemoryBackend};
+    use crate::backend::Key;
+    use crate::keymap::Action;
+    use crate::testing::TempPath;
+
+    /// Plays a seeded 30 x 12 game on a 40 x 16 terminal, with a high score that won't be beaten
+    fn play(script: impl IntoIterator<Item = Input>) -> MemoryBackend {
+        let mut terminal = MemoryBackend::new(40, 16).with_script(script);
+        App::new(100, 30, 12)
+            .with_seed(7)
+            .run(&mut terminal)
+            .unwrap();
+        terminal
+    }
+
+    #[test]
+    fn welcome_screen() {
+        let terminal = play([Input::Wait(1), Key::Char('q').into()]);
+        assert!(terminal.cursor_visible());
+        insta::assert_snapshot!(terminal.screen());
+    }
+
+    #[test]
+    fn gameplay_frames() {
+        let terminal = play([
+            Key::Char(' ').into(),
+            Input::Wait(2),
+            Key::Up.into(),
+            Input::Wait(2),
+            Key::Char('q').into(),
+        ]);
+        // Skips the welcome screen and the blank screen the game starts from
+        let gameplay: Vec<&str> = terminal
+            .frames()
+            .iter()
+            .map(String::as_str)
+            .filter(|frame| !frame.contains("HAPPY") && !frame.trim().is_empty())
+            .collect();
+        assert_eq!(gameplay.len(), 4);
+        insta::assert_snapshot!(gameplay.join("\n----\n"));
+    }
+
+    #[test]
+    fn score_screen() {
+        let terminal = play([
+            Key::Char(' ').into(),
+            Input::Wait(100),
+            Key::Char('q').into(),
+        ]);
+        insta::assert_snapshot!(terminal.screen());
+    }
+
+    #[test]
+    fn time_in_the_pause_menu_is_left_out_of_the_duration() {
+        let duration = |terminal: &MemoryBackend| {
+            let screen = terminal.screen();
+            let line = screen
+                .lines()
+                .find(|line| line.contains("DURATION"))
+                .unwrap();
+            line.trim().to_string()
+        };
+        let straight = play([
+            Key::Char(' ').into(),
+            Input::Wait(100),
+            Key::Char('q').into(),
+        ]);
+        let paused = play([
+            Key::Char(' ').into(),
+            Key::Esc.into(),
+            Input::Wait(500),
+            Key::Esc.into(),
+            Input::Wait(100),
+            Key::Char('q').into(),
+        ]);
+        assert!(paused.frames().iter().any(|frame| frame.contains("PAUSED")));
+        assert_eq!(duration(&paused), duration(&straight));
+    }
+
+    #[test]
+    fn dying_flashes_the_wall_and_dissolves_the_snake() {
+        let terminal = play([
+            Key::Char(' ').into(),
+            Input::Wait(100),
+            Key::Char('q').into(),
+        ]);
+        let dying: Vec<&String> = terminal
+            .frames()
+            .iter()
+            .filter(|frame| frame.contains("HIT WALL") && !frame.contains("MAIN MENU"))
+            .collect();
+        // Three flashes of the head in the wall, then the two cells of the snake one at a time
+        assert_eq!(dying.len(), 8);
+        assert!(dying[0].contains("O>>"));
+        assert!(dying[1].contains("O>║"));
+        assert!(dying[6].contains(" >║"));
+        assert!(!dying[7].contains('>'));
+    }
+
+    #[test]
+    fn games_can_be_saved_as_replays_and_retried_from_the_same_seed() {
+        let replay_dir = TempPath::new("results");
+        fs::create_dir(&replay_dir).unwrap();
+        let mut terminal = MemoryBackend::new(40, 16).with_script([
+            Key::Char(' ').into(),
+            Input::Wait(100),
+            // Save the replay, then retry
+            Key::Up.into(),
+            Key::Up.into(),
+            Key::Char('\n').into(),
+            Key::Down.into(),
+            Key::Char('\n').into(),
+            Input::Wait(100),
+            Key::Char('q').into(),
+        ]);
+        App::new(100, 30, 12)
+            .with_seed(7)
+            .with_replay_dir(&replay_dir)
+            .run(&mut terminal)
+            .unwrap();
+        let results = terminal
+            .frames()
+            .iter()
+            .find(|frame| frame.contains("> MAIN MENU <"))
+            .unwrap();
+        assert_eq!(&terminal.screen(), results);
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.contains("SAVED AS snake-")));
+
+        let paths = replay_files(&replay_dir).unwrap();
+        assert_eq!(paths.len(), 1);
+        let replay = Replay::load(&paths[0]).unwrap();
+        let last = replay.states().last().unwrap();
+        assert!(last.is_over());
+        assert_eq!(last.ticks(), 14);
+    }
+
+    #[test]
+    fn new_profiles_are_played_as_and_their_games_counted() {
+        let profiles_path = TempPath::new("app.profiles");
+        let to_player = [Key::Down, Key::Down, Key::Down, Key::Char('\n')];
+        let name = [
+            Key::Char('a'),
+            Key::Char('d'),
+            Key::Char('a'),
+            Key::Char('\n'),
+        ];
+        let to_stats = [Key::Down, Key::Down, Key::Char('\n')];
+        let to_play = [Key::Esc, Key::Up, Key::Up, Key::Up, Key::Up, Key::Up];
+        let mut terminal = MemoryBackend::new(40, 16).with_script(
+            [Input::Wait(1)]
+                .into_iter()
+                .chain(to_player.into_iter().chain(name).map(Input::from))
+                .chain([Input::Wait(1)])
+                .chain(to_stats.into_iter().chain(to_play).map(Input::from))
+                .chain([Input::Wait(1), Key::Char(' ').into(), Input::Wait(100)])
+                .chain([Key::Char('q').into()]),
+        );
+        App::new(100, 30, 12)
+            .with_seed(7)
+            .with_profiles(Profiles::default(), &profiles_path)
+            .run(&mut terminal)
+            .unwrap();
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.contains("> PLAYER: ADA <")));
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.contains("ADA") && frame.contains("GAMES PLAYED: 0")));
+
+        let profiles = Profiles::load(&profiles_path).unwrap();
+        assert_eq!(profiles.current().name, "ada");
+        assert_eq!(profiles.current().stats.games, 1);
+        assert_eq!(profiles.current().stats.endings["hit wall"], 1);
+        assert_eq!(profiles.find("guest").unwrap().stats.games, 0);
+    }
+
+    #[test]
+    fn achievements_are_announced_and_kept_on_the_profile() {
+        let profiles_path = TempPath::new("achievements.profiles");
+        // Down to the row of the food, then left onto it
+        let mut terminal = MemoryBackend::new(40, 16).with_script([
+            Key::Char(' ').into(),
+            Key::Down.into(),
+            Input::Wait(4),
+            Key::Left.into(),
+            Input::Wait(6),
+            Key::Char('q').into(),
+        ]);
+        App::new(100, 30, 12)
+            .with_seed(7)
+            .with_profiles(Profiles::default(), &profiles_path)
+            .run(&mut terminal)
+            .unwrap();
+        assert!(terminal.screen().contains("UNLOCKED: FIRST BITE"));
+        let profiles = Profiles::load(&profiles_path).unwrap();
+        let unlocked: Vec<Achievement> = profiles.current().achievements.iter().copied().collect();
+        assert_eq!(unlocked, [Achievement::FirstBite]);
+    }
+
+    #[test]
+    fn saved_games_are_offered_on_the_welcome_screen_and_resumed_once() {
+        let save_path = TempPath::new("app.save");
+        let mut terminal = MemoryBackend::new(40, 16).with_script([
+            Key::Char(' ').into(),
+            Input::Wait(3),
+            Key::Char('p').into(),
+            Input::Wait(1),
+            Key::Char('s').into(),
+            Input::Wait(1),
+            Key::Char('q').into(),
+        ]);
+        App::new(100, 30, 12)
+            .with_seed(7)
+            .with_save_path(&save_path)
+            .run(&mut terminal)
+            .unwrap();
+        let paused = terminal
+            .frames()
+            .iter()
+            .find(|frame| frame.contains("PAUSED"))
+            .unwrap();
+        assert!(terminal.screen().contains("> CONTINUE <"));
+        assert!(!SavedGame::load(&save_path).unwrap().game_state.is_over());
+
+        let mut terminal = MemoryBackend::new(40, 16).with_script([
+            Input::Wait(1),
+            Key::Char('\n').into(),
+            Key::Char('p').into(),
+            Input::Wait(1),
+            Key::Char('q').into(),
+        ]);
+        App::new(100, 30, 12)
+            .with_seed(8)
+            .with_save_path(&save_path)
+            .run(&mut terminal)
+            .unwrap();
+        // Pausing straight away shows the saved board, untouched by the new seed
+        assert_eq!(terminal.screen(), *paused);
+        assert!(!save_path.exists());
+    }
+
+    #[test]
+    fn versus_mode_adds_a_computer_snake() {
+        let terminal = play([
+            Key::Down.into(),
+            Key::Char('\n').into(),
+            Key::Up.into(),
+            Key::Char('\n').into(),
+            Input::Wait(3),
+            Key::Char('q').into(),
+        ]);
+        let board = terminal.screen();
+        assert!(board.contains('O') && board.contains('@'));
+    }
+
+    #[test]
+    fn two_players_steer_their_own_snakes() {
+        let terminal = play([
+            Key::Down.into(),
+            Key::Char('\n').into(),
+            Key::Char('\n').into(),
+            Key::Up.into(),
+            Key::Char('\n').into(),
+            Input::Wait(1),
+            Key::Char('s').into(),
+            Key::Up.into(),
+            Input::Wait(2),
+            Key::Char('q').into(),
+        ]);
+        let board = terminal.screen();
+        assert!(board.contains('O') && board.contains('@'));
+        assert!(board.contains('Ʌ') && board.contains('V'));
+    }
+
+    #[test]
+    fn keys_can_be_rebound_from_the_config() {
+        let mut config = Config {
+            width: 30,
+            height: 12,
+            ..Config::default()
+        };
+        config.keys.set(Action::Quit, vec![Key::Char('x')]);
+        config.keys.set(Action::Restart, vec![Key::Char('n')]);
+        let mut terminal = MemoryBackend::new(40, 16).with_script([
+            Key::Char(' ').into(),
+            Input::Wait(2),
+            Key::Char('q').into(),
+            Input::Wait(1),
+            Key::Char('n').into(),
+            Input::Wait(1),
+            Key::Char('x').into(),
+        ]);
+        App::new(100, 30, 12)
+            .with_seed(7)
+            .with_config(config)
+            .run(&mut terminal)
+            .unwrap();
+        // The restarted game is back to a snake of two cells
+        let board = terminal.screen();
+        assert_eq!(board.matches('O').count(), 1);
+        assert!(terminal.frames().len() > 4);
+    }
+
+    #[test]
+    fn pages_from_the_menu_go_back_to_it() {
+        let terminal = play([
+            Key::Up.into(),
+            Key::Up.into(),
+            Key::Char('\n').into(),
+            Input::Wait(1),
+            Key::Esc.into(),
+            Input::Wait(1),
+            Key::Char('q').into(),
+        ]);
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.contains("DON'T HIT WALLS OR SNAKES")));
+        assert!(terminal.screen().contains("> HELP <"));
+    }
+
+    #[test]
+    fn help_lists_the_keys_from_the_config() {
+        let mut keys = Bindings::default();
+        keys.set(Action::Pause, vec![Key::Char('x')]);
+        keys.set(Action::Boost(1), Vec::new());
+        let lines = help(&keys);
+        assert!(lines.contains(&String::from("PLAYER 1 UP: UP OR K")));
+        assert!(lines.contains(&String::from("PAUSE: X")));
+        assert!(lines.contains(&String::from("QUIT: Q OR CTRL-C")));
+        assert!(!lines.iter().any(|line| line.starts_with("PLAYER 2 BOOST")));
+    }
+
+    #[test]
+    fn settings_are_saved_to_the_config_file_and_played_by() {
+        let config_path = TempPath::new("app.toml");
+        let to_settings = [Key::Up, Key::Up, Key::Up, Key::Char('\n')];
+        // Theme, one to the right, then up to Done
+        let edit = [Key::Down, Key::Down, Key::Down, Key::Right];
+        let done = [Key::Up, Key::Up, Key::Up, Key::Up, Key::Char('\n')];
+        let mut terminal = MemoryBackend::new(40, 16).with_script(
+            to_settings
+                .into_iter()
+                .chain(edit)
+                .chain(done)
+                .map(Input::from)
+                // Back on the menu, from Settings down past Help and Quit to Play
+                .chain([Input::Wait(1), Key::Down.into(), Key::Down.into()])
+                .chain([Key::Down, Key::Char(' ')].map(Input::from))
+                .chain([Input::Wait(2), Key::Char('q').into()]),
+        );
+        App::new(100, 30, 12)
+            .with_seed(7)
+            .with_config_path(&config_path)
+            .run(&mut terminal)
+            .unwrap();
+        let config = Config::load(&config_path).unwrap();
+        assert_eq!(config.theme, crate::render::Theme::Ascii);
+        assert_eq!((config.width, config.height), (30, 12));
+        assert!(terminal.screen().starts_with("+----"));
+    }
+
+    #[test]
+    fn running_out_of_input_is_an_error() {
+        let mut terminal = MemoryBackend::new(40, 16).with_script([Input::Wait(1)]);
+        assert!(App::new(100, 30, 12).run(&mut terminal).is_err());
     }
 }
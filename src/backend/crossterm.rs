@@ -0,0 +1,98 @@
+use super::{Backend, Clear, Key};
+use color_eyre::eyre::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, queue, terminal};
+use std::io::{stdout, Stdout, Write};
+use std::time::Duration;
+
+pub struct CrosstermBackend {
+    stdout: Stdout,
+}
+
+impl CrosstermBackend {
+    /// Switches the terminal to raw mode until the backend is dropped
+    pub fn new() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self { stdout: stdout() })
+    }
+}
+
+impl Drop for CrosstermBackend {
+    fn drop(&mut self) {
+        let _ = queue!(self.stdout, cursor::Show);
+        let _ = self.stdout.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Maps a key to what termion would have reported, so both backends behave the same
+fn convert(key: KeyEvent) -> Key {
+    match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => Key::Ctrl(c),
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Tab => Key::Char('\t'),
+        KeyCode::Enter => Key::Char('\n'),
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Esc => Key::Esc,
+        _ => Key::Other,
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn poll_key(&mut self) -> Result<Option<Key>> {
+        while event::poll(Duration::ZERO)? {
+            // Some terminals also report releases and repeats; only presses count
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Release {
+                    return Ok(Some(convert(key)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn size(&self) -> Result<(usize, usize)> {
+        let (columns, rows) = terminal::size()?;
+        Ok((usize::from(columns), usize::from(rows)))
+    }
+
+    fn write(&mut self, text: &str) -> Result<()> {
+        self.stdout.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    fn move_to(&mut self, column: usize, row: usize) -> Result<()> {
+        let column = u16::try_from(column).unwrap_or(u16::MAX);
+        let row = u16::try_from(row).unwrap_or(u16::MAX);
+        queue!(self.stdout, cursor::MoveTo(column, row))?;
+        Ok(())
+    }
+
+    fn clear(&mut self, area: Clear) -> Result<()> {
+        let clear_type = match area {
+            Clear::All => terminal::ClearType::All,
+            Clear::UntilNewline => terminal::ClearType::UntilNewLine,
+            Clear::AfterCursor => terminal::ClearType::FromCursorDown,
+        };
+        queue!(self.stdout, terminal::Clear(clear_type))?;
+        Ok(())
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) -> Result<()> {
+        if visible {
+            queue!(self.stdout, cursor::Show)?;
+        } else {
+            queue!(self.stdout, cursor::Hide)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.stdout.flush()?;
+        Ok(())
+    }
+}
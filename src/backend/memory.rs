@@ -0,0 +1,227 @@
+//! A backend that draws into a character grid and reads keys from a script, for testing screens
+//! without a TTY.
+
+use super::{Backend, Clear, Key};
+use color_eyre::eyre::{eyre, Result};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// One step of the scripted input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Key(Key),
+    /// Lets this many frames go by before the next key. A frame is one call to `sleep`.
+    Wait(usize),
+}
+
+impl From<Key> for Input {
+    fn from(key: Key) -> Self {
+        Input::Key(key)
+    }
+}
+
+pub struct MemoryBackend {
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<char>>,
+    cursor: (usize, usize),
+    cursor_visible: bool,
+    script: VecDeque<Input>,
+    /// Set once a frame has gone by with nothing left in the script
+    idle: bool,
+    frames: Vec<String>,
+    /// The clock only moves by what is slept, so timings don't depend on how fast tests run
+    started: Instant,
+    slept: Duration,
+}
+
+impl MemoryBackend {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Self {
+            columns,
+            rows,
+            cells: vec![vec![' '; columns]; rows],
+            cursor: (0, 0),
+            cursor_visible: true,
+            script: VecDeque::new(),
+            idle: false,
+            frames: Vec::new(),
+            started: Instant::now(),
+            slept: Duration::ZERO,
+        }
+    }
+
+    /// Queues keys and waits to be read in order. Once the script has run out for a whole frame,
+    /// reading a key fails rather than waiting forever.
+    pub fn with_script(mut self, script: impl IntoIterator<Item = Input>) -> Self {
+        self.script.extend(script);
+        self
+    }
+
+    /// The screen as it is now, one line per row with trailing spaces removed
+    pub fn screen(&self) -> String {
+        self.cells
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// The screen at every flush that changed it, oldest first
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    fn new_line(&mut self) {
+        if self.cursor.1 + 1 < self.rows {
+            self.cursor.1 += 1;
+        } else {
+            // Like a real terminal, a new line at the bottom scrolls everything up
+            self.cells.remove(0);
+            self.cells.push(vec![' '; self.columns]);
+        }
+    }
+
+    fn clear_row_from(&mut self, row: usize, column: usize) {
+        if let Some(cells) = self.cells.get_mut(row) {
+            cells.iter_mut().skip(column).for_each(|cell| *cell = ' ');
+        }
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn poll_key(&mut self) -> Result<Option<Key>> {
+        while let Some(Input::Wait(0)) = self.script.front() {
+            self.script.pop_front();
+        }
+        match self.script.front() {
+            Some(&Input::Key(key)) => {
+                self.script.pop_front();
+                Ok(Some(key))
+            }
+            Some(Input::Wait(_)) => Ok(None),
+            None if self.idle => Err(eyre!("the scripted input ran out")),
+            None => Ok(None),
+        }
+    }
+
+    fn size(&self) -> Result<(usize, usize)> {
+        Ok((self.columns, self.rows))
+    }
+
+    fn write(&mut self, text: &str) -> Result<()> {
+        for c in text.chars() {
+            match c {
+                '\n' => self.new_line(),
+                '\r' => self.cursor.0 = 0,
+                c => {
+                    if self.cursor.0 >= self.columns {
+                        self.cursor.0 = 0;
+                        self.new_line();
+                    }
+                    let (column, row) = self.cursor;
+                    if let Some(cell) = self.cells.get_mut(row).and_then(|row| row.get_mut(column))
+                    {
+                        *cell = c;
+                    }
+                    self.cursor.0 += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn move_to(&mut self, column: usize, row: usize) -> Result<()> {
+        self.cursor = (
+            column.min(self.columns.saturating_sub(1)),
+            row.min(self.rows.saturating_sub(1)),
+        );
+        Ok(())
+    }
+
+    fn clear(&mut self, area: Clear) -> Result<()> {
+        let (column, row) = self.cursor;
+        match area {
+            Clear::All => (0..self.rows).for_each(|row| self.clear_row_from(row, 0)),
+            Clear::UntilNewline => self.clear_row_from(row, column),
+            Clear::AfterCursor => {
+                self.clear_row_from(row, column);
+                (row + 1..self.rows).for_each(|row| self.clear_row_from(row, 0));
+            }
+        }
+        Ok(())
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) -> Result<()> {
+        self.cursor_visible = visible;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let screen = self.screen();
+        if self.frames.last() != Some(&screen) {
+            self.frames.push(screen);
+        }
+        Ok(())
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.slept += duration;
+        match self.script.front_mut() {
+            Some(Input::Wait(frames)) => *frames = frames.saturating_sub(1),
+            Some(Input::Key(_)) => (),
+            None => self.idle = true,
+        }
+    }
+
+    fn now(&self) -> Instant {
+        self.started + self.slept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_land_at_the_cursor_and_clears_erase() {
+        let mut terminal = MemoryBackend::new(6, 3);
+        terminal.write("abc\n\rdef").unwrap();
+        terminal.move_to(1, 0).unwrap();
+        terminal.write("X").unwrap();
+        terminal.clear(Clear::UntilNewline).unwrap();
+        assert_eq!(terminal.screen(), "aX\ndef\n");
+        terminal.clear(Clear::All).unwrap();
+        assert_eq!(terminal.screen(), "\n\n");
+    }
+
+    #[test]
+    fn new_lines_at_the_bottom_scroll() {
+        let mut terminal = MemoryBackend::new(4, 2);
+        terminal.write("one\n\rtwo\n\rsix").unwrap();
+        assert_eq!(terminal.screen(), "two\nsix");
+    }
+
+    #[test]
+    fn keys_wait_for_frames_and_running_out_is_an_error() {
+        let mut terminal = MemoryBackend::new(4, 2).with_script([
+            Input::Key(Key::Char('a')),
+            Input::Wait(2),
+            Input::Key(Key::Up),
+        ]);
+        assert_eq!(terminal.poll_key().unwrap(), Some(Key::Char('a')));
+        assert_eq!(terminal.poll_key().unwrap(), None);
+        terminal.sleep(Duration::ZERO);
+        assert_eq!(terminal.poll_key().unwrap(), None);
+        terminal.sleep(Duration::ZERO);
+        assert_eq!(terminal.poll_key().unwrap(), Some(Key::Up));
+        assert_eq!(terminal.poll_key().unwrap(), None);
+        terminal.sleep(Duration::ZERO);
+        assert!(terminal.poll_key().is_err());
+        assert!(terminal.read_key().is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Terminal input and drawing behind a small trait, so the screens don't depend on a particular
+//! terminal library. termion and crossterm implementations are picked with the cargo feature of
+//! the same name; `Terminal` is whichever one was built. `memory` draws into a character grid
+//! for tests.
+
+pub use crate::keymap::Key;
+use color_eyre::eyre::Result;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "crossterm")]
+pub mod crossterm;
+pub mod memory;
+#[cfg(feature = "termion")]
+pub mod termion;
+
+#[cfg(not(any(feature = "termion", feature = "crossterm")))]
+compile_error!("the terminal feature needs a backend: enable either termion or crossterm");
+
+/// The backend the snake binary uses. termion wins when both are enabled, since it is the default.
+#[cfg(feature = "termion")]
+pub type Terminal = self::termion::TermionBackend;
+#[cfg(all(feature = "crossterm", not(feature = "termion")))]
+pub type Terminal = self::crossterm::CrosstermBackend;
+
+/// What `Backend::clear` erases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clear {
+    All,
+    /// From the cursor to the end of its line
+    UntilNewline,
+    /// From the cursor to the end of the screen
+    AfterCursor,
+}
+
+/// A terminal in raw mode. Text is written at the cursor, with `"\n\r"` starting a new line;
+/// nothing is guaranteed to show until `flush`. Implementations restore the terminal on drop.
+pub trait Backend {
+    /// Returns the next key press without waiting, or `None` if there isn't one
+    fn poll_key(&mut self) -> Result<Option<Key>>;
+
+    /// Waits for the next key press
+    fn read_key(&mut self) -> Result<Key> {
+        loop {
+            if let Some(key) = self.poll_key()? {
+                return Ok(key);
+            }
+            self.sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Columns and rows
+    fn size(&self) -> Result<(usize, usize)>;
+
+    fn write(&mut self, text: &str) -> Result<()>;
+
+    /// Moves the cursor, counting from 0 at the top left
+    fn move_to(&mut self, column: usize, row: usize) -> Result<()>;
+
+    fn clear(&mut self, area: Clear) -> Result<()>;
+
+    fn set_cursor_visible(&mut self, visible: bool) -> Result<()>;
+
+    fn flush(&mut self) -> Result<()>;
+
+    /// Waits between frames
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    /// The time now, for measuring how long the game has been played
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+impl<T: Backend + ?Sized> Backend for &mut T {
+    fn poll_key(&mut self) -> Result<Option<Key>> {
+        (**self).poll_key()
+    }
+
+    fn read_key(&mut self) -> Result<Key> {
+        (**self).read_key()
+    }
+
+    fn size(&self) -> Result<(usize, usize)> {
+        (**self).size()
+    }
+
+    fn write(&mut self, text: &str) -> Result<()> {
+        (**self).write(text)
+    }
+
+    fn move_to(&mut self, column: usize, row: usize) -> Result<()> {
+        (**self).move_to(column, row)
+    }
+
+    fn clear(&mut self, area: Clear) -> Result<()> {
+        (**self).clear(area)
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) -> Result<()> {
+        (**self).set_cursor_visible(visible)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
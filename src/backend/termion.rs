@@ -0,0 +1,88 @@
+use super::{Backend, Clear, Key};
+use color_eyre::eyre::Result;
+use std::io::{stdout, Stdout, Write};
+use termion::input::{Keys, TermRead};
+use termion::raw::{IntoRawMode, RawTerminal};
+use termion::{async_stdin, clear, cursor, event, AsyncReader};
+
+pub struct TermionBackend {
+    stdout: RawTerminal<Stdout>,
+    input: Keys<AsyncReader>,
+}
+
+impl TermionBackend {
+    /// Switches the terminal to raw mode until the backend is dropped
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            stdout: stdout().into_raw_mode()?,
+            input: async_stdin().keys(),
+        })
+    }
+}
+
+impl Drop for TermionBackend {
+    fn drop(&mut self) {
+        let _ = write!(self.stdout, "{}", cursor::Show);
+        let _ = self.stdout.flush();
+    }
+}
+
+fn convert(key: event::Key) -> Key {
+    match key {
+        event::Key::Char(c) => Key::Char(c),
+        event::Key::Ctrl(c) => Key::Ctrl(c),
+        event::Key::Up => Key::Up,
+        event::Key::Down => Key::Down,
+        event::Key::Left => Key::Left,
+        event::Key::Right => Key::Right,
+        event::Key::Backspace => Key::Backspace,
+        event::Key::Esc => Key::Esc,
+        _ => Key::Other,
+    }
+}
+
+impl Backend for TermionBackend {
+    fn poll_key(&mut self) -> Result<Option<Key>> {
+        Ok(self.input.next().transpose()?.map(convert))
+    }
+
+    fn size(&self) -> Result<(usize, usize)> {
+        let (columns, rows) = termion::terminal_size()?;
+        Ok((usize::from(columns), usize::from(rows)))
+    }
+
+    fn write(&mut self, text: &str) -> Result<()> {
+        self.stdout.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    fn move_to(&mut self, column: usize, row: usize) -> Result<()> {
+        let column = u16::try_from(column + 1).unwrap_or(u16::MAX);
+        let row = u16::try_from(row + 1).unwrap_or(u16::MAX);
+        write!(self.stdout, "{}", cursor::Goto(column, row))?;
+        Ok(())
+    }
+
+    fn clear(&mut self, area: Clear) -> Result<()> {
+        match area {
+            Clear::All => write!(self.stdout, "{}", clear::All)?,
+            Clear::UntilNewline => write!(self.stdout, "{}", clear::UntilNewline)?,
+            Clear::AfterCursor => write!(self.stdout, "{}", clear::AfterCursor)?,
+        }
+        Ok(())
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) -> Result<()> {
+        if visible {
+            write!(self.stdout, "{}", cursor::Show)?;
+        } else {
+            write!(self.stdout, "{}", cursor::Hide)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.stdout.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+//! Many independent training games stepped in lockstep. The per-game state is kept as one
+//! vector per field rather than a vector of [`Environment`](crate::environment::Environment)s,
+//! so rewards and flags come out as contiguous slices and the games split cleanly across
+//! threads. The threads are started once per batch and kept for every step after.
+
+use crate::environment::{advance, encode, truncated, EnvironmentConfig};
+use crate::game::GameState;
+use crate::player::Direction;
+use color_eyre::eyre::Result;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+pub struct BatchEnvironment {
+    config: EnvironmentConfig,
+    seed: u64,
+    /// Number of threads used to step and encode the games
+    threads: usize,
+    /// The threads, when there is more than one
+    pool: Option<ThreadPool>,
+    games: Vec<GameState>,
+    steps: Vec<u64>,
+    episodes: Vec<u64>,
+    rewards: Vec<f32>,
+    dones: Vec<bool>,
+    truncations: Vec<bool>,
+    final_scores: Vec<usize>,
+}
+
+/// The slice of every field that belongs to one thread
+struct Lanes<'a> {
+    first_index: usize,
+    games: &'a mut [GameState],
+    steps: &'a mut [u64],
+    episodes: &'a mut [u64],
+    rewards: &'a mut [f32],
+    dones: &'a mut [bool],
+    truncations: &'a mut [bool],
+    final_scores: &'a mut [usize],
+}
+
+impl BatchEnvironment {
+    /// Creates `count` games; game `i` of episode `n` is seeded from `seed`, `i` and `n`, so a
+    /// batch plays out the same way whatever the thread count
+    pub fn new(config: EnvironmentConfig, count: usize, seed: u64) -> Result<Self> {
+        config.check()?;
+        let mut batch = Self {
+            config,
+            seed,
+            threads: 1,
+            pool: None,
+            games: Vec::with_capacity(count),
+            steps: vec![0; count],
+            episodes: vec![0; count],
+            rewards: vec![0.0; count],
+            dones: vec![false; count],
+            truncations: vec![false; count],
+            final_scores: vec![0; count],
+        };
+        batch.games = (0..count)
+            .map(|index| GameState::seeded(config.width, config.height, 1, batch.seed_for(index)))
+            .collect();
+        Ok(batch)
+    }
+
+    /// Steps and encodes the games on up to `threads` threads. Where threads can't be started
+    /// the games are played on the calling thread.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self.pool = (self.threads > 1)
+            .then(|| ThreadPoolBuilder::new().num_threads(self.threads).build())
+            .and_then(Result::ok);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    pub fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    pub fn games(&self) -> &[GameState] {
+        &self.games
+    }
+
+    fn seed_for(&self, index: usize) -> u64 {
+        episode_seed(self.seed, self.steps.len(), index, self.episodes[index])
+    }
+
+    /// Restarts every game from a new base seed
+    pub fn reset(&mut self, seed: u64) {
+        self.seed = seed;
+        for index in 0..self.len() {
+            self.episodes[index] = 0;
+            self.steps[index] = 0;
+            self.games[index] = GameState::seeded(
+                self.config.width,
+                self.config.height,
+                1,
+                self.seed_for(index),
+            );
+        }
+        self.rewards.fill(0.0);
+        self.dones.fill(false);
+        self.truncations.fill(false);
+        self.final_scores.fill(0);
+    }
+
+    /// Applies one action per game and advances them all by a tick. Games that finish are
+    /// restarted straight away with their next seed; [`BatchEnvironment::dones`] says which
+    /// did, and their observations already show the new episode.
+    pub fn step(&mut self, actions: &[Direction]) {
+        assert_eq!(actions.len(), self.len(), "expected one action per game");
+        let (config, seed, count) = (self.config, self.seed, self.len());
+        let pool = self.pool.take();
+        let lanes = self.lanes();
+        match &pool {
+            Some(pool) => pool.scope(|scope| {
+                for lanes in lanes {
+                    scope.spawn(move |_| step_lanes(lanes, actions, &config, seed, count));
+                }
+            }),
+            None => {
+                for lanes in lanes {
+                    step_lanes(lanes, actions, &config, seed, count);
+                }
+            }
+        }
+        self.pool = pool;
+    }
+
+    fn lanes(&mut self) -> Vec<Lanes<'_>> {
+        let chunk = self.len().div_ceil(self.threads).max(1);
+        let mut lanes = Vec::new();
+        let mut steps = self.steps.chunks_mut(chunk);
+        let mut episodes = self.episodes.chunks_mut(chunk);
+        let mut rewards = self.rewards.chunks_mut(chunk);
+        let mut dones = self.dones.chunks_mut(chunk);
+        let mut truncations = self.truncations.chunks_mut(chunk);
+        let mut final_scores = self.final_scores.chunks_mut(chunk);
+        for games in self.games.chunks_mut(chunk) {
+            lanes.push(Lanes {
+                first_index: lanes.len() * chunk,
+                games,
+                steps: steps.next().expect("fields have the same length"),
+                episodes: episodes.next().expect("fields have the same length"),
+                rewards: rewards.next().expect("fields have the same length"),
+                dones: dones.next().expect("fields have the same length"),
+                truncations: truncations.next().expect("fields have the same length"),
+                final_scores: final_scores.next().expect("fields have the same length"),
+            });
+        }
+        lanes
+    }
+
+    /// The reward each game earned on the last step
+    pub fn rewards(&self) -> &[f32] {
+        &self.rewards
+    }
+
+    /// Which games finished an episode on the last step
+    pub fn dones(&self) -> &[bool] {
+        &self.dones
+    }
+
+    /// Which of the finished games were cut short by `max_steps`
+    pub fn truncations(&self) -> &[bool] {
+        &self.truncations
+    }
+
+    /// The score each finished game ended its episode with
+    pub fn final_scores(&self) -> &[usize] {
+        &self.final_scores
+    }
+
+    /// Length of one game's observation
+    pub fn observation_len(&self) -> usize {
+        self.config
+            .encoding
+            .len(self.config.width, self.config.height)
+    }
+
+    /// Writes every game's observation into `buffer`, one after the other, as a
+    /// `[games, ..shape]` array
+    pub fn observe_into(&self, buffer: &mut [f32]) {
+        let observation_len = self.observation_len();
+        assert_eq!(
+            buffer.len(),
+            observation_len * self.len(),
+            "the buffer must hold one observation per game"
+        );
+        let encoding = self.config.encoding;
+        let chunk = self.len().div_ceil(self.threads).max(1);
+        let encode_chunk = |games: &[GameState], buffer: &mut [f32]| {
+            for (game_state, observation) in games.iter().zip(buffer.chunks_mut(observation_len)) {
+                encode(game_state, encoding, observation);
+            }
+        };
+        let Some(pool) = &self.pool else {
+            encode_chunk(&self.games, buffer);
+            return;
+        };
+        pool.scope(|scope| {
+            for (games, buffer) in self
+                .games
+                .chunks(chunk)
+                .zip(buffer.chunks_mut(chunk * observation_len))
+            {
+                scope.spawn(move |_| encode_chunk(games, buffer));
+            }
+        });
+    }
+}
+
+fn episode_seed(seed: u64, count: usize, index: usize, episode: u64) -> u64 {
+    seed.wrapping_add(episode.wrapping_mul(count as u64))
+        .wrapping_add(index as u64)
+}
+
+fn step_lanes(
+    lanes: Lanes<'_>,
+    actions: &[Direction],
+    config: &EnvironmentConfig,
+    seed: u64,
+    count: usize,
+) {
+    for offset in 0..lanes.games.len() {
+        let index = lanes.first_index + offset;
+        let game_state = &mut lanes.games[offset];
+        let steps = &mut lanes.steps[offset];
+        let (reward, done) = advance(game_state, steps, config, actions[index]);
+        lanes.rewards[offset] = reward;
+        lanes.dones[offset] = done;
+        lanes.truncations[offset] = done && truncated(game_state, *steps, config);
+        if done {
+            lanes.final_scores[offset] = game_state.score();
+            lanes.episodes[offset] += 1;
+            *steps = 0;
+            let seed = episode_seed(seed, count, index, lanes.episodes[offset]);
+            *game_state = GameState::seeded(config.width, config.height, 1, seed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{Encoding, Environment};
+
+    fn config() -> EnvironmentConfig {
+        EnvironmentConfig {
+            width: 12,
+            height: 10,
+            encoding: Encoding::Grid,
+            max_steps: Some(30),
+            ..EnvironmentConfig::default()
+        }
+    }
+
+    fn actions(step: usize, count: usize) -> Vec<Direction> {
+        (0..count)
+            .map(|index| Direction::ALL[(step / 4 + index) % Direction::ALL.len()])
+            .collect()
+    }
+
+    fn play(threads: usize) -> (Vec<f32>, Vec<f32>, Vec<bool>) {
+        let mut batch = BatchEnvironment::new(config(), 13, 5)
+            .unwrap()
+            .with_threads(threads);
+        let mut rewards = Vec::new();
+        let mut dones = Vec::new();
+        for step in 0..50 {
+            batch.step(&actions(step, batch.len()));
+            rewards.extend_from_slice(batch.rewards());
+            dones.extend_from_slice(batch.dones());
+        }
+        let mut observations = vec![0.0; batch.observation_len() * batch.len()];
+        batch.observe_into(&mut observations);
+        (rewards, observations, dones)
+    }
+
+    #[test]
+    fn threads_do_not_change_the_outcome() {
+        let single = play(1);
+        assert_eq!(play(4), single);
+        assert_eq!(play(32), single);
+        assert!(single.2.iter().any(|&done| done));
+    }
+
+    #[test]
+    fn games_match_standalone_environments_until_they_finish() {
+        let mut batch = BatchEnvironment::new(config(), 3, 100).unwrap();
+        let mut environments: Vec<Environment> = (0..3)
+            .map(|index| {
+                let mut environment = Environment::new(config()).unwrap();
+                environment.reset(100 + index);
+                environment
+            })
+            .collect();
+        let mut observations = vec![0.0; batch.observation_len() * 3];
+        for step in 0..20 {
+            let actions = actions(step, 3);
+            batch.step(&actions);
+            batch.observe_into(&mut observations);
+            for (index, environment) in environments.iter_mut().enumerate() {
+                if environment.is_done() {
+                    continue;
+                }
+                let (observation, reward, done, _) = environment.step(actions[index]);
+                assert_eq!(batch.rewards()[index], reward);
+                assert_eq!(batch.dones()[index], done);
+                if !done {
+                    let length = batch.observation_len();
+                    assert_eq!(observations[index * length..][..length], observation.data);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finished_games_restart_with_a_fresh_episode() {
+        let mut batch = BatchEnvironment::new(config(), 2, 0).unwrap();
+        for _ in 0..30 {
+            batch.step(&[Direction::Right, Direction::Right]);
+            if batch.dones()[0] {
+                break;
+            }
+        }
+        assert!(batch.dones()[0]);
+        assert!(!batch.truncations()[0]);
+        let snake = &batch.games()[0].snakes()[0];
+        assert!(snake.alive);
+        assert_eq!(snake.score, 0);
+    }
+}
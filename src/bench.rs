@@ -0,0 +1,322 @@
+//! Workloads for measuring the engine, shared by the criterion benchmarks in
+//! `benches/simulation.rs` and `snake bench`. Snakes are laid out along a path that zigzags row
+//! by row across the board, so they can be any length and keep moving without dying.
+
+use crate::game::{GameBoard, GameState};
+use crate::player::{Direction, Position};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Board sizes measured by `snake bench`, from a small window to a very large terminal
+pub const BOARD_SIZES: [(usize, usize); 3] = [(20, 20), (80, 25), (200, 60)];
+
+/// How much of the free space the snake covers in the tick and frame measurements
+pub const FILLS: [f64; 3] = [0.0, 0.5, 0.9];
+
+/// How much of the free space is covered when food has to be placed. The last one leaves a
+/// single free cell.
+pub const SPAWN_FILLS: [f64; 4] = [0.5, 0.9, 0.99, 1.0];
+
+/// The cells inside the walls, left to right along the first row, right to left along the
+/// next and so on
+pub fn zigzag(width: usize, height: usize) -> Vec<Position> {
+    (1..height - 1)
+        .flat_map(|row| {
+            let columns: Vec<usize> = if row % 2 == 1 {
+                (1..width - 1).collect()
+            } else {
+                (1..width - 1).rev().collect()
+            };
+            columns
+                .into_iter()
+                .map(move |column| Position::new(column, row))
+        })
+        .collect()
+}
+
+fn direction_between(from: Position, to: Position) -> Direction {
+    if to.row_number < from.row_number {
+        Direction::Up
+    } else if to.row_number > from.row_number {
+        Direction::Down
+    } else if to.column_number < from.column_number {
+        Direction::Left
+    } else {
+        Direction::Right
+    }
+}
+
+/// The length of a snake covering `fill` of the cells inside the walls. It is kept between 2
+/// and two short of full, so the snake always has somewhere to go and somewhere to put food.
+pub fn length_for(width: usize, height: usize, fill: f64) -> usize {
+    let free = (width - 2) * (height - 2);
+    ((free as f64 * fill) as usize).clamp(2, free - 2)
+}
+
+/// A one player game whose snake lies along the start of the zigzag, head at cell
+/// `length - 1` and facing the next one. With `food_ahead` the food is in that next cell,
+/// otherwise it is somewhere random.
+pub fn coiled_game(width: usize, height: usize, length: usize, food_ahead: bool) -> GameState {
+    let path = zigzag(width, height);
+    let mut game_state = GameState::seeded(width, height, 1, 0);
+    let body: Vec<Position> = path[..length].iter().rev().copied().collect();
+    let heading = direction_between(path[length - 1], path[length]);
+    game_state.reshape_snake(&body, heading, food_ahead.then(|| path[length]));
+    game_state
+}
+
+/// A snake that follows the zigzag until it reaches the end of the board or its game ends,
+/// then starts over from a copy of the first state. The copies are part of what is measured,
+/// but happen at most once every `width * height - length` ticks or so.
+pub struct Slither {
+    start: GameState,
+    game_state: GameState,
+    path: Vec<Position>,
+    length: usize,
+    next: usize,
+}
+
+impl Slither {
+    pub fn new(width: usize, height: usize, length: usize) -> Self {
+        let start = coiled_game(width, height, length, false);
+        Self {
+            game_state: start.clone(),
+            start,
+            path: zigzag(width, height),
+            length,
+            next: length,
+        }
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    /// Steers towards the next cell of the zigzag and ticks once
+    pub fn step(&mut self) {
+        if self.next >= self.path.len() || self.game_state.is_over() {
+            self.game_state = self.start.clone();
+            self.next = self.length;
+        }
+        let head = self.game_state.snakes()[0].player.head_position;
+        self.game_state
+            .steer(0, direction_between(head, self.path[self.next]));
+        self.game_state.tick();
+        self.next += 1;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickResult {
+    pub width: usize,
+    pub height: usize,
+    pub length: usize,
+    pub ticks_per_second: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameResult {
+    pub width: usize,
+    pub height: usize,
+    pub length: usize,
+    /// Time to work out every cell with `GameBoard::try_from`
+    pub board: Duration,
+    /// Time to turn the board into text with its `Display` impl
+    pub display: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnResult {
+    pub width: usize,
+    pub height: usize,
+    /// Cells left for the food once the snake has eaten
+    pub free_cells: usize,
+    /// Time for the tick in which the snake eats and new food is placed
+    pub tick: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Results {
+    pub ticks: Vec<TickResult>,
+    pub frames: Vec<FrameResult>,
+    pub spawns: Vec<SpawnResult>,
+}
+
+/// Calls `f` over and over for about `duration` and returns the mean time per call
+fn time_per_call(duration: Duration, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    let mut calls = 0;
+    // Reading the clock after every call would be measured too, so calls are made in batches
+    let mut batch = 1;
+    while calls == 0 || start.elapsed() < duration {
+        for _ in 0..batch {
+            f();
+        }
+        calls += batch;
+        batch = (batch * 2).min(1024);
+    }
+    start.elapsed() / calls
+}
+
+/// Like `time_per_call`, but only `f` is timed and not the `setup` that makes its input
+fn time_with_setup<T>(
+    duration: Duration,
+    mut setup: impl FnMut() -> T,
+    mut f: impl FnMut(&mut T),
+) -> Duration {
+    let start = Instant::now();
+    let mut measured = Duration::ZERO;
+    let mut calls = 0;
+    while calls == 0 || start.elapsed() < duration {
+        let mut input = setup();
+        let before = Instant::now();
+        f(&mut input);
+        measured += before.elapsed();
+        calls += 1;
+        black_box(input);
+    }
+    measured / calls
+}
+
+/// Measures every workload, spending about `duration` on each
+pub fn run(duration: Duration) -> Results {
+    let mut results = Results::default();
+    for (width, height) in BOARD_SIZES {
+        for fill in FILLS {
+            let length = length_for(width, height, fill);
+            let mut slither = Slither::new(width, height, length);
+            let tick = time_per_call(duration, || {
+                slither.step();
+                black_box(slither.game_state());
+            });
+            results.ticks.push(TickResult {
+                width,
+                height,
+                length,
+                ticks_per_second: 1.0 / tick.as_secs_f64(),
+            });
+
+            let game_state = coiled_game(width, height, length, false);
+            let board = GameBoard::try_from(&game_state).expect("the board fits");
+            results.frames.push(FrameResult {
+                width,
+                height,
+                length,
+                board: time_per_call(duration, || {
+                    black_box(GameBoard::try_from(black_box(&game_state)).ok());
+                }),
+                display: time_per_call(duration, || {
+                    black_box(black_box(&board).to_string());
+                }),
+            });
+        }
+        for fill in SPAWN_FILLS {
+            let length = length_for(width, height, fill);
+            let game_state = coiled_game(width, height, length, true);
+            let tick = time_with_setup(
+                duration,
+                || game_state.clone(),
+                |game_state| {
+                    black_box(game_state.tick());
+                },
+            );
+            results.spawns.push(SpawnResult {
+                width,
+                height,
+                free_cells: (width - 2) * (height - 2) - length - 1,
+                tick,
+            });
+        }
+    }
+    results
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
+
+/// The results as three tables: ticks, frames and food placement
+pub fn table(results: &Results) -> String {
+    let mut lines = vec![format!(
+        "{:>7}  {:>6}  {:>12}",
+        "board", "length", "ticks/sec"
+    )];
+    for result in &results.ticks {
+        lines.push(format!(
+            "{:>7}  {:>6}  {:>12.0}",
+            format!("{}x{}", result.width, result.height),
+            result.length,
+            result.ticks_per_second
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "{:>7}  {:>6}  {:>10}  {:>10}  {:>10}",
+        "board", "length", "board µs", "display µs", "frame µs"
+    ));
+    for result in &results.frames {
+        lines.push(format!(
+            "{:>7}  {:>6}  {:>10.1}  {:>10.1}  {:>10.1}",
+            format!("{}x{}", result.width, result.height),
+            result.length,
+            micros(result.board),
+            micros(result.display),
+            micros(result.board + result.display)
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "{:>7}  {:>10}  {:>10}",
+        "board", "free cells", "eat µs"
+    ));
+    for result in &results.spawns {
+        lines.push(format!(
+            "{:>7}  {:>10}  {:>10.1}",
+            format!("{}x{}", result.width, result.height),
+            result.free_cells,
+            micros(result.tick)
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_zigzag_covers_the_inside_one_step_at_a_time() {
+        let path = zigzag(6, 5);
+        assert_eq!(path.len(), 4 * 3);
+        assert_eq!(path[0], Position::new(1, 1));
+        assert_eq!(path[4], Position::new(4, 2));
+        for pair in path.windows(2) {
+            let distance = pair[0].column_number.abs_diff(pair[1].column_number)
+                + pair[0].row_number.abs_diff(pair[1].row_number);
+            assert_eq!(distance, 1);
+        }
+    }
+
+    #[test]
+    fn slithering_snakes_never_die() {
+        for fill in FILLS {
+            let length = length_for(20, 10, fill);
+            let mut slither = Slither::new(20, 10, length);
+            for _ in 0..500 {
+                slither.step();
+                assert!(slither.game_state().snakes()[0].alive);
+            }
+        }
+    }
+
+    #[test]
+    fn food_ahead_is_eaten_on_the_next_tick() {
+        for fill in SPAWN_FILLS {
+            let length = length_for(10, 8, fill);
+            let mut game_state = coiled_game(10, 8, length, true);
+            game_state.tick();
+            assert_eq!(game_state.score(), length - 1);
+            assert!(!game_state.is_over());
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use crate::ai::external::BotSettings;
+use crate::game::GameArea;
+use crate::headless::MatchConfig;
+use crate::net::server::ServerConfig;
+use crate::net::DEFAULT_PORT;
+use crate::player::Steering;
+use crate::tournament::{Entrant, TournamentConfig};
+use color_eyre::eyre::{eyre, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+usage:
+    snake                   play a local game
+    snake serve [options]   host a networked game
+        --bind <address>        address to listen on (default 0.0.0.0:7373)
+        --players <count>       players needed before a round starts (default 2)
+        --width <columns>       board width (default 80)
+        --height <rows>         board height (default 25)
+        --tick-ms <ms>          milliseconds per tick (default 75)
+        --record <file>         save a replay of the most recent round
+        --replay <file>         broadcast a recorded game to spectators instead
+        --bots <count>          computer opponents to add to every round (default 0)
+        --bot-level <level>     easy, medium or hard (default medium)
+    snake join <address>    join a networked game
+        --name <name>           name shown to other players
+        --relative              steer with left and right turns only
+    snake spectate <address>    watch a networked game
+        --name <name>           name shown to the server
+        --follow <player>       keep the camera on this player (1-9)
+    snake replay <file>     watch a recorded game
+        --follow <player>       keep the camera on this player (1-9)
+        --tick-ms <ms>          milliseconds per tick (default 75)
+    snake bot --cmd <program>   let an external program play, see src/ai/external.rs
+        --width <columns>       board width (default 80)
+        --height <rows>         board height (default 25)
+        --seed <number>         seed for food placement (default random)
+        --max-ticks <count>     stop the game after this many ticks
+        --budget-ms <ms>        time the bot gets to answer each tick (default 100)
+        --max-faults <count>    late or malformed answers allowed before disqualification
+        --watch                 draw the game in the terminal instead of running headless
+        --tick-ms <ms>          milliseconds per tick while watching (default 75)
+    snake tournament [options]  compare bots across many headless games
+        --bot <level|program>   easy, medium, hard or a bot program; repeat for more bots
+                                (default easy, medium and hard)
+        --seeds <count|a..b>    seeds to play, a count starting from 0 or a range (default 10)
+        --size <columns>x<rows> board size; repeat for more sizes (default 40x20)
+        --mode <solo|versus>    repeat to play both (default solo)
+        --max-ticks <count>     stop each game after this many ticks (default 5000)
+        --jobs <count>          games to play at the same time (default 1)
+        --budget-ms <ms>        time bot programs get to answer each tick (default 100)
+        --max-faults <count>    late or malformed answers allowed before disqualification
+        --csv <file>            write the results table as CSV
+        --json <file>           write the results table and every game as JSON
+    snake bench             measure ticks per second and the time to build a frame
+        --ms <ms>               milliseconds spent on each measurement (default 300)
+    snake stats             show every profile's stats and achievements
+        --profile <name>        show only this profile";
+
+pub enum Command {
+    Play,
+    Serve {
+        bind: String,
+        config: ServerConfig,
+        replay: Option<PathBuf>,
+    },
+    Join {
+        addr: String,
+        name: String,
+        steering: Steering,
+    },
+    Spectate {
+        addr: String,
+        name: String,
+        follow: Option<usize>,
+    },
+    WatchReplay {
+        path: PathBuf,
+        follow: Option<usize>,
+        tick_duration: Duration,
+    },
+    Bot {
+        command: String,
+        settings: BotSettings,
+        config: MatchConfig,
+        /// How fast to draw the game, or `None` to play headless
+        watch: Option<Duration>,
+    },
+    Tournament {
+        config: TournamentConfig,
+        csv: Option<PathBuf>,
+        json: Option<PathBuf>,
+    },
+    Bench {
+        /// Time spent on each measurement
+        duration: Duration,
+    },
+    Stats {
+        /// The only profile to show, or all of them when `None`
+        profile: Option<String>,
+    },
+}
+
+/// Parses the command line arguments, not including the program name
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        return Ok(Command::Play);
+    };
+    match command.as_str() {
+        "serve" => {
+            let mut bind = format!("0.0.0.0:{}", DEFAULT_PORT);
+            let mut config = ServerConfig::default();
+            let mut replay = None;
+            while let Some(flag) = args.next() {
+                let value = flag_value(&flag, args.next())?;
+                match flag.as_str() {
+                    "--bind" => bind = value,
+                    "--players" => config.min_players = parse_number(&flag, &value)?,
+                    "--width" => config.width = parse_number(&flag, &value)?,
+                    "--height" => config.height = parse_number(&flag, &value)?,
+                    "--tick-ms" => config.tick_duration = parse_tick_duration(&flag, &value)?,
+                    "--record" => config.record_path = Some(PathBuf::from(value)),
+                    "--replay" => replay = Some(PathBuf::from(value)),
+                    "--bots" => config.bots = parse_number(&flag, &value)?,
+                    "--bot-level" => config.bot_difficulty = value.parse()?,
+                    _ => return Err(unknown_flag(&flag)),
+                }
+            }
+            GameArea::check(config.width, config.height)?;
+            GameArea::check_players(config.height, config.min_players + config.bots)?;
+            Ok(Command::Serve {
+                bind,
+                config,
+                replay,
+            })
+        }
+        "join" | "spectate" => {
+            let mut addr = args
+                .next()
+                .ok_or_else(|| eyre!("{} needs the address of a server\n\n{}", command, USAGE))?;
+            if !addr.contains(':') {
+                addr = format!("{}:{}", addr, DEFAULT_PORT);
+            }
+            let mut name = std::env::var("USER").unwrap_or_else(|_| String::from("player"));
+            let mut follow = None;
+            let mut steering = Steering::Absolute;
+            while let Some(flag) = args.next() {
+                if flag == "--relative" && command == "join" {
+                    steering = Steering::Relative;
+                    continue;
+                }
+                let value = flag_value(&flag, args.next())?;
+                match flag.as_str() {
+                    "--name" => name = value,
+                    "--follow" if command == "spectate" => {
+                        follow = Some(parse_player(&flag, &value)?)
+                    }
+                    _ => return Err(unknown_flag(&flag)),
+                }
+            }
+            if command == "join" {
+                Ok(Command::Join {
+                    addr,
+                    name,
+                    steering,
+                })
+            } else {
+                Ok(Command::Spectate { addr, name, follow })
+            }
+        }
+        "replay" => {
+            let path = args
+                .next()
+                .map(PathBuf::from)
+                .ok_or_else(|| eyre!("replay needs the path of a replay file\n\n{}", USAGE))?;
+            let mut follow = None;
+            let mut tick_duration = ServerConfig::default().tick_duration;
+            while let Some(flag) = args.next() {
+                let value = flag_value(&flag, args.next())?;
+                match flag.as_str() {
+                    "--follow" => follow = Some(parse_player(&flag, &value)?),
+                    "--tick-ms" => tick_duration = parse_tick_duration(&flag, &value)?,
+                    _ => return Err(unknown_flag(&flag)),
+                }
+            }
+            Ok(Command::WatchReplay {
+                path,
+                follow,
+                tick_duration,
+            })
+        }
+        "bot" => {
+            let mut program = None;
+            let mut settings = BotSettings::default();
+            let mut config = MatchConfig::default();
+            let mut watch = false;
+            let mut tick_duration = ServerConfig::default().tick_duration;
+            while let Some(flag) = args.next() {
+                if flag == "--watch" {
+                    watch = true;
+                    continue;
+                }
+                let value = flag_value(&flag, args.next())?;
+                match flag.as_str() {
+                    "--cmd" => program = Some(value),
+                    "--width" => config.width = parse_number(&flag, &value)?,
+                    "--height" => config.height = parse_number(&flag, &value)?,
+                    "--seed" => config.seed = Some(parse_number(&flag, &value)? as u64),
+                    "--max-ticks" => config.max_ticks = Some(parse_number(&flag, &value)? as u64),
+                    "--budget-ms" => settings.time_budget = parse_tick_duration(&flag, &value)?,
+                    "--max-faults" => settings.max_faults = Some(parse_number(&flag, &value)?),
+                    "--tick-ms" => tick_duration = parse_tick_duration(&flag, &value)?,
+                    _ => return Err(unknown_flag(&flag)),
+                }
+            }
+            let command =
+                program.ok_or_else(|| eyre!("bot needs a program to run\n\n{}", USAGE))?;
+            GameArea::check(config.width, config.height)?;
+            // The bot's stderr would scribble over the board
+            settings.show_stderr = !watch;
+            Ok(Command::Bot {
+                command,
+                settings,
+                config,
+                watch: watch.then_some(tick_duration),
+            })
+        }
+        "tournament" => {
+            let mut config = TournamentConfig::default();
+            let (mut entrants, mut sizes, mut modes) = (Vec::new(), Vec::new(), Vec::new());
+            let (mut csv, mut json) = (None, None);
+            while let Some(flag) = args.next() {
+                let value = flag_value(&flag, args.next())?;
+                match flag.as_str() {
+                    "--bot" => entrants.push(Entrant::parse(&value)),
+                    "--seeds" => config.seeds = parse_seeds(&flag, &value)?,
+                    "--size" => sizes.push(parse_size(&flag, &value)?),
+                    "--mode" => modes.push(value.parse()?),
+                    "--max-ticks" => config.max_ticks = parse_number(&flag, &value)? as u64,
+                    "--jobs" => config.jobs = parse_number(&flag, &value)?.max(1),
+                    "--budget-ms" => {
+                        config.bot_settings.time_budget = parse_tick_duration(&flag, &value)?
+                    }
+                    "--max-faults" => {
+                        config.bot_settings.max_faults = Some(parse_number(&flag, &value)?)
+                    }
+                    "--csv" => csv = Some(PathBuf::from(value)),
+                    "--json" => json = Some(PathBuf::from(value)),
+                    _ => return Err(unknown_flag(&flag)),
+                }
+            }
+            if !entrants.is_empty() {
+                config.entrants = entrants;
+            }
+            if !sizes.is_empty() {
+                config.sizes = sizes;
+            }
+            if !modes.is_empty() {
+                config.modes = modes;
+            }
+            Ok(Command::Tournament { config, csv, json })
+        }
+        "bench" => {
+            let mut duration = Duration::from_millis(300);
+            while let Some(flag) = args.next() {
+                let value = flag_value(&flag, args.next())?;
+                match flag.as_str() {
+                    "--ms" => duration = parse_tick_duration(&flag, &value)?,
+                    _ => return Err(unknown_flag(&flag)),
+                }
+            }
+            Ok(Command::Bench { duration })
+        }
+        "stats" => {
+            let mut profile = None;
+            while let Some(flag) = args.next() {
+                let value = flag_value(&flag, args.next())?;
+                match flag.as_str() {
+                    "--profile" => profile = Some(value),
+                    _ => return Err(unknown_flag(&flag)),
+                }
+            }
+            Ok(Command::Stats { profile })
+        }
+        _ => Err(eyre!("unknown command {:?}\n\n{}", command, USAGE)),
+    }
+}
+
+fn flag_value(flag: &str, value: Option<String>) -> Result<String> {
+    value.ok_or_else(|| eyre!("{} needs a value\n\n{}", flag, USAGE))
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<usize> {
+    value
+        .parse()
+        .map_err(|_| eyre!("{} expects a number, got {:?}", flag, value))
+}
+
+fn parse_tick_duration(flag: &str, value: &str) -> Result<Duration> {
+    Ok(Duration::from_millis(parse_number(flag, value)? as u64))
+}
+
+/// Either a count of seeds starting from 0 or a half-open range such as `100..150`
+fn parse_seeds(flag: &str, value: &str) -> Result<Vec<u64>> {
+    let (first, last) = match value.split_once("..") {
+        Some((first, last)) => (parse_number(flag, first)?, parse_number(flag, last)?),
+        None => (0, parse_number(flag, value)?),
+    };
+    if first >= last {
+        return Err(eyre!("{} needs at least one seed, got {:?}", flag, value));
+    }
+    Ok((first as u64..last as u64).collect())
+}
+
+fn parse_size(flag: &str, value: &str) -> Result<(usize, usize)> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| eyre!("{} expects <columns>x<rows>, got {:?}", flag, value))?;
+    let size = (parse_number(flag, width)?, parse_number(flag, height)?);
+    GameArea::check(size.0, size.1)?;
+    Ok(size)
+}
+
+/// Players are numbered from 1 on the command line
+fn parse_player(flag: &str, value: &str) -> Result<usize> {
+    match parse_number(flag, value)? {
+        0 => Err(eyre!("{} counts players from 1", flag)),
+        player => Ok(player - 1),
+    }
+}
+
+fn unknown_flag(flag: &str) -> color_eyre::eyre::Report {
+    eyre!("unknown option {:?}\n\n{}", flag, USAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_plays_locally() {
+        assert!(matches!(parse(&[]).unwrap(), Command::Play));
+    }
+
+    #[test]
+    fn serve_reads_options() {
+        let arguments = [
+            "serve",
+            "--bind",
+            "127.0.0.1:9000",
+            "--players",
+            "3",
+            "--bots",
+            "2",
+            "--bot-level",
+            "hard",
+        ];
+        let Command::Serve { bind, config, .. } = parse(&arguments).unwrap() else {
+            panic!("expected serve");
+        };
+        assert_eq!(bind, "127.0.0.1:9000");
+        assert_eq!(config.min_players, 3);
+        assert_eq!(config.bots, 2);
+        assert_eq!(config.bot_difficulty, Difficulty::Hard);
+    }
+
+    #[test]
+    fn board_sizes_are_checked() {
+        assert!(parse(&["serve", "--width", "2"]).is_err());
+        assert!(parse(&["serve", "--height", "100000"]).is_err());
+        assert!(parse(&["serve", "--height", "6", "--players", "3", "--bots", "2"]).is_err());
+        assert!(parse(&["tournament", "--bot", "easy", "--size", "3x40"]).is_err());
+        assert!(parse(&["serve", "--width", "4", "--height", "1000"]).is_ok());
+    }
+
+    #[test]
+    fn join_adds_the_default_port() {
+        let Command::Join {
+            addr,
+            name,
+            steering,
+        } = parse(&["join", "localhost", "--name", "ada"]).unwrap()
+        else {
+            panic!("expected join");
+        };
+        assert_eq!(addr, "localhost:7373");
+        assert_eq!(name, "ada");
+        assert_eq!(steering, Steering::Absolute);
+    }
+
+    #[test]
+    fn join_can_steer_relative() {
+        let Command::Join { steering, .. } =
+            parse(&["join", "localhost", "--relative", "--name", "ada"]).unwrap()
+        else {
+            panic!("expected join");
+        };
+        assert_eq!(steering, Steering::Relative);
+        assert!(parse(&["spectate", "localhost", "--relative"]).is_err());
+    }
+
+    #[test]
+    fn spectators_follow_players_counted_from_one() {
+        let Command::Spectate { follow, .. } =
+            parse(&["spectate", "10.0.0.2", "--follow", "2"]).unwrap()
+        else {
+            panic!("expected spectate");
+        };
+        assert_eq!(follow, Some(1));
+        assert!(parse(&["spectate", "10.0.0.2", "--follow", "0"]).is_err());
+        assert!(parse(&["join", "10.0.0.2", "--follow", "1"]).is_err());
+    }
+
+    #[test]
+    fn bots_need_a_program_and_run_headless_by_default() {
+        let arguments = [
+            "bot",
+            "--cmd",
+            "python3 bot.py",
+            "--seed",
+            "4",
+            "--max-faults",
+            "2",
+        ];
+        let Command::Bot {
+            command,
+            settings,
+            config,
+            watch,
+        } = parse(&arguments).unwrap()
+        else {
+            panic!("expected bot");
+        };
+        assert_eq!(command, "python3 bot.py");
+        assert_eq!(settings.max_faults, Some(2));
+        assert!(settings.show_stderr);
+        assert_eq!(config.seed, Some(4));
+        assert_eq!(watch, None);
+
+        let Command::Bot {
+            settings, watch, ..
+        } = parse(&["bot", "--watch", "--cmd", "./bot", "--tick-ms", "20"]).unwrap()
+        else {
+            panic!("expected bot");
+        };
+        assert!(!settings.show_stderr);
+        assert_eq!(watch, Some(Duration::from_millis(20)));
+        assert!(parse(&["bot", "--watch"]).is_err());
+    }
+
+    #[test]
+    fn tournaments_replace_defaults_with_repeated_options() {
+        let arguments = [
+            "tournament",
+            "--bot",
+            "hard",
+            "--bot",
+            "./my-bot --fast",
+            "--seeds",
+            "5..8",
+            "--size",
+            "30x15",
+            "--mode",
+            "versus",
+            "--jobs",
+            "4",
+        ];
+        let Command::Tournament { config, csv, .. } = parse(&arguments).unwrap() else {
+            panic!("expected tournament");
+        };
+        assert_eq!(
+            config.entrants,
+            vec![
+                Entrant::Builtin(Difficulty::Hard),
+                Entrant::External(String::from("./my-bot --fast"))
+            ]
+        );
+        assert_eq!(config.seeds, vec![5, 6, 7]);
+        assert_eq!(config.sizes, vec![(30, 15)]);
+        assert_eq!(config.jobs, 4);
+        assert_eq!(csv, None);
+
+        let Command::Tournament { config, .. } = parse(&["tournament", "--seeds", "3"]).unwrap()
+        else {
+            panic!("expected tournament");
+        };
+        assert_eq!(config.seeds, vec![0, 1, 2]);
+        assert_eq!(config.entrants.len(), 3);
+        assert!(parse(&["tournament", "--size", "30"]).is_err());
+        assert!(parse(&["tournament", "--seeds", "4..4"]).is_err());
+    }
+
+    #[test]
+    fn bench_takes_the_time_per_measurement() {
+        let Command::Bench { duration } = parse(&["bench", "--ms", "50"]).unwrap() else {
+            panic!("expected bench");
+        };
+        assert_eq!(duration, Duration::from_millis(50));
+        assert!(parse(&["bench", "--ms"]).is_err());
+    }
+
+    #[test]
+    fn stats_can_be_shown_for_one_profile() {
+        let Command::Stats { profile } = parse(&["stats", "--profile", "ada"]).unwrap() else {
+            panic!("expected stats");
+        };
+        assert_eq!(profile.as_deref(), Some("ada"));
+        assert!(matches!(
+            parse(&["stats"]).unwrap(),
+            Command::Stats { profile: None }
+        ));
+        assert!(parse(&["stats", "--profile"]).is_err());
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        assert!(parse(&["serve", "--colour", "red"]).is_err());
+        assert!(parse(&["dance"]).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Settings for local games, kept in a TOML file next to the high score. Missing keys take their
+//! default, so an empty file or no file at all is a valid config.
+
+use crate::game::{GameArea, Rules, MAX_FOOD};
+use crate::keymap::Bindings;
+use crate::render::Theme;
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::Duration;
+
+/// Where the config is read from and saved to unless told otherwise
+pub const DEFAULT_CONFIG_PATH: &str = "snake.toml";
+
+/// Shown above the menu unless the config says otherwise
+pub const DEFAULT_BANNER: &str = "HAPPY BIRTHDAY LAURENCE!!!";
+
+/// The speeds a game can be played at, in milliseconds per tick
+pub const TICK_MS: RangeInclusive<u64> = 20..=500;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub width: usize,
+    pub height: usize,
+    /// Milliseconds per tick
+    pub tick_ms: u64,
+    pub theme: Theme,
+    /// Whether the walls let snakes through to the other side
+    pub wrap: bool,
+    /// How much food is out at once
+    pub food: usize,
+    /// The message above the menu; empty for none
+    pub banner: String,
+    pub keys: Bindings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            height: 25,
+            tick_ms: 75,
+            theme: Theme::Classic,
+            wrap: false,
+            food: 1,
+            banner: String::from(DEFAULT_BANNER),
+            keys: Bindings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Refuses settings the game can't be played with
+    pub fn check(&self) -> Result<()> {
+        GameArea::check(self.width, self.height)?;
+        if !TICK_MS.contains(&self.tick_ms) {
+            return Err(eyre!(
+                "tick_ms must be between {} and {}",
+                TICK_MS.start(),
+                TICK_MS.end()
+            ));
+        }
+        if !(1..=MAX_FOOD).contains(&self.food) {
+            return Err(eyre!("food must be between 1 and {}", MAX_FOOD));
+        }
+        self.keys.check()
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules {
+            wrap: self.wrap,
+            food: self.food,
+        }
+    }
+
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_millis(self.tick_ms)
+    }
+
+    pub fn banner(&self) -> Option<&str> {
+        Some(self.banner.as_str()).filter(|banner| !banner.is_empty())
+    }
+
+    pub fn read_from(text: &str) -> Result<Self> {
+        let config: Config =
+            toml::from_str(text).map_err(|error| eyre!("invalid config: {}", error))?;
+        config
+            .check()
+            .map_err(|error| eyre!("invalid config: {}", error))?;
+        Ok(config)
+    }
+
+    /// Reads the config at `path`, or the defaults if there is no file there yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::read_from(&text),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::{Action, Key};
+
+    #[test]
+    fn configs_survive_a_round_trip_through_text() {
+        let mut config = Config {
+            width: 40,
+            theme: Theme::Blocks,
+            wrap: true,
+            food: 3,
+            banner: String::new(),
+            ..Config::default()
+        };
+        config
+            .keys
+            .set(Action::Quit, vec![Key::Char('x'), Key::Ctrl('x')]);
+        let text = toml::to_string(&config).unwrap();
+        assert_eq!(Config::read_from(&text).unwrap(), config);
+        assert_eq!(config.banner(), None);
+    }
+
+    #[test]
+    fn missing_settings_take_their_defaults() {
+        let config = Config::read_from("wrap = true\ntheme = \"ascii\"").unwrap();
+        assert!(config.wrap);
+        assert_eq!(config.theme, Theme::Ascii);
+        assert_eq!(config.width, Config::default().width);
+        assert_eq!(Config::read_from("").unwrap(), Config::default());
+        let config = Config::read_from("[keys]\nrestart = [\"n\", \"enter\"]").unwrap();
+        assert_eq!(config.keys.action(Key::Char('\n')), Some(Action::Restart));
+        assert_eq!(config.keys.action(Key::Char('q')), Some(Action::Quit));
+    }
+
+    #[test]
+    fn unknown_or_unplayable_settings_are_rejected() {
+        for text in [
+            "colour = \"red\"",
+            "width = 2",
+            "tick_ms = 0",
+            "food = 0",
+            "food = 100",
+            "theme = \"neon\"",
+            "width = \"wide\"",
+            "[keys]\nquit = [\"w\"]",
+            "[keys]\npause = [\"shift\"]",
+        ] {
+            let error = Config::read_from(text).unwrap_err();
+            assert!(error.to_string().contains("invalid config"), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,525 @@
+//! A gym-style training environment: one snake, driven by [`Environment::step`], with the
+//! board encoded as a flat `f32` observation and a configurable reward. Nothing here touches
+//! the terminal, so it can be stepped as fast as the game logic allows.
+
+use crate::game::{determine_game_cell, GameArea, GameCell, GameState, MAX_BOARD_SIZE};
+use crate::player::{Direction, Position};
+use color_eyre::eyre::{eyre, Result};
+
+/// How the board is turned into numbers for the agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// The whole board as `[4, height, width]` planes: walls, head, body and food
+    Grid,
+    /// A `[2, size, size]` window centred on the head, where `size = 2 * radius + 1`: obstacles
+    /// (walls and body, including everything beyond the board) and food
+    Egocentric { radius: usize },
+    /// Eleven flags: danger straight ahead, to the left and to the right, the heading as up,
+    /// right, down, left, and whether the food is up, right, down or left of the head
+    Features,
+}
+
+impl Encoding {
+    pub const FEATURE_COUNT: usize = 11;
+
+    /// Refuses windows wider than the largest board, which would see nothing but walls and
+    /// could take more memory than there is
+    pub fn check(&self) -> Result<()> {
+        match *self {
+            Encoding::Egocentric { radius } if radius > MAX_BOARD_SIZE => Err(eyre!(
+                "the egocentric radius can be at most {}",
+                MAX_BOARD_SIZE
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The dimensions of an observation on a `width` x `height` board
+    pub fn shape(&self, width: usize, height: usize) -> Vec<usize> {
+        match *self {
+            Encoding::Grid => vec![4, height, width],
+            Encoding::Egocentric { radius } => vec![2, 2 * radius + 1, 2 * radius + 1],
+            Encoding::Features => vec![Self::FEATURE_COUNT],
+        }
+    }
+
+    pub fn len(&self, width: usize, height: usize) -> usize {
+        self.shape(width, height).iter().product()
+    }
+}
+
+/// Rewards for what happened during a step; they are added together
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rewards {
+    pub food: f32,
+    pub death: f32,
+    /// Given on every step, usually a small penalty to discourage dawdling
+    pub step: f32,
+    /// Multiplied by how many cells closer to the food the head moved, negative when moving away
+    pub approach: f32,
+    /// Given when the snake fills the whole board
+    pub clear: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Self {
+            food: 1.0,
+            death: -1.0,
+            step: 0.0,
+            approach: 0.0,
+            clear: 10.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvironmentConfig {
+    pub width: usize,
+    pub height: usize,
+    pub encoding: Encoding,
+    pub rewards: Rewards,
+    /// Episodes are cut short after this many steps, reported as `truncated`
+    pub max_steps: Option<u64>,
+}
+
+impl EnvironmentConfig {
+    /// Refuses boards and encodings the environment can't be played with
+    pub fn check(&self) -> Result<()> {
+        GameArea::check(self.width, self.height)?;
+        self.encoding.check()
+    }
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            width: 20,
+            height: 20,
+            encoding: Encoding::Features,
+            rewards: Rewards::default(),
+            max_steps: Some(10_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub shape: Vec<usize>,
+    /// Row-major values for `shape`, each 0.0 or 1.0
+    pub data: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Info {
+    pub score: usize,
+    /// Number of cells the snake covers, head included
+    pub length: usize,
+    pub steps: u64,
+    /// Whether the episode ended because it hit `max_steps` rather than by dying or winning
+    pub truncated: bool,
+}
+
+pub struct Environment {
+    config: EnvironmentConfig,
+    game_state: GameState,
+    steps: u64,
+    done: bool,
+}
+
+impl Environment {
+    pub fn new(config: EnvironmentConfig) -> Result<Self> {
+        config.check()?;
+        Ok(Self {
+            game_state: GameState::seeded(config.width, config.height, 1, 0),
+            config,
+            steps: 0,
+            done: false,
+        })
+    }
+
+    pub fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    /// Starts a new episode; the same seed and the same actions always give the same episode
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game_state = GameState::seeded(self.config.width, self.config.height, 1, seed);
+        self.steps = 0;
+        self.done = false;
+        self.observe()
+    }
+
+    /// Turns the snake towards `action` and advances one tick. Reversing is ignored, as it is
+    /// for players. Stepping a finished episode changes nothing and gives no reward.
+    pub fn step(&mut self, action: Direction) -> (Observation, f32, bool, Info) {
+        let reward = self.advance(action);
+        (self.observe(), reward, self.done, self.info())
+    }
+
+    /// Same as [`Environment::step`] without building an observation
+    pub fn advance(&mut self, action: Direction) -> f32 {
+        if self.done {
+            return 0.0;
+        }
+        let (reward, done) = advance(&mut self.game_state, &mut self.steps, &self.config, action);
+        self.done = done;
+        reward
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn info(&self) -> Info {
+        let snake = &self.game_state.snakes()[0];
+        Info {
+            score: snake.score,
+            length: snake.tail.positions.len() + 1,
+            steps: self.steps,
+            truncated: truncated(&self.game_state, self.steps, &self.config),
+        }
+    }
+
+    pub fn observe(&self) -> Observation {
+        let (width, height) = (self.config.width, self.config.height);
+        let mut data = vec![0.0; self.config.encoding.len(width, height)];
+        self.observe_into(&mut data);
+        Observation {
+            shape: self.config.encoding.shape(width, height),
+            data,
+        }
+    }
+
+    /// Writes the observation into `buffer`, which must be exactly as long as the encoding
+    pub fn observe_into(&self, buffer: &mut [f32]) {
+        encode(&self.game_state, self.config.encoding, buffer);
+    }
+}
+
+/// Plays one step of an episode, returning the reward and whether the episode has ended
+pub(crate) fn advance(
+    game_state: &mut GameState,
+    steps: &mut u64,
+    config: &EnvironmentConfig,
+    action: Direction,
+) -> (f32, bool) {
+    let rewards = config.rewards;
+    let before = &game_state.snakes()[0];
+    let head = before.player.head_position;
+    let (score, distance) = (
+        before.score,
+        head.manhattan_distance(game_state.nearest_food(head)),
+    );
+    game_state.steer(0, action);
+    let running = game_state.tick().is_some();
+    *steps += 1;
+
+    let snake = &game_state.snakes()[0];
+    let mut reward = rewards.step;
+    if snake.score > score {
+        reward += rewards.food;
+    } else if snake.alive {
+        let head = snake.player.head_position;
+        let new_distance = head.manhattan_distance(game_state.nearest_food(head));
+        reward += rewards.approach * (distance as f32 - new_distance as f32);
+    }
+    if !snake.alive {
+        reward += rewards.death;
+    } else if !running {
+        reward += rewards.clear;
+    }
+    (reward, !running || truncated(game_state, *steps, config))
+}
+
+/// Whether a running episode has reached the step limit
+pub(crate) fn truncated(game_state: &GameState, steps: u64, config: &EnvironmentConfig) -> bool {
+    game_state.snakes()[0].alive
+        && !game_state.is_over()
+        && config.max_steps.is_some_and(|max_steps| steps >= max_steps)
+}
+
+/// Encodes the first snake's view of `game_state` into `buffer`
+pub fn encode(game_state: &GameState, encoding: Encoding, buffer: &mut [f32]) {
+    let (width, height) = (game_state.game_area.width, game_state.game_area.height);
+    assert_eq!(
+        buffer.len(),
+        encoding.len(width, height),
+        "the observation buffer does not match the encoding"
+    );
+    buffer.fill(0.0);
+    let snake = &game_state.snakes()[0];
+    let head = snake.player.head_position;
+    let food = game_state.nearest_food(head);
+    match encoding {
+        Encoding::Grid => {
+            let plane = width * height;
+            let mut set = |channel: usize, position: Position| {
+                if position.column_number < width && position.row_number < height {
+                    buffer
+                        [channel * plane + position.row_number * width + position.column_number] =
+                        1.0;
+                }
+            };
+            for column in 0..width {
+                set(0, Position::new(column, 0));
+                set(0, Position::new(column, height - 1));
+            }
+            for row in 0..height {
+                set(0, Position::new(0, row));
+                set(0, Position::new(width - 1, row));
+            }
+            if snake.alive {
+                set(1, head);
+                for &position in &snake.tail.positions {
+                    set(2, position);
+                }
+            }
+            for food in game_state.powerup_positions() {
+                set(3, food);
+            }
+        }
+        Encoding::Egocentric { radius } => {
+            let size = 2 * radius + 1;
+            let plane = size * size;
+            // Window coordinates of a board position, if it falls inside the window
+            let window = |position: Position| {
+                let column = (position.column_number + radius).checked_sub(head.column_number)?;
+                let row = (position.row_number + radius).checked_sub(head.row_number)?;
+                (column < size && row < size).then_some(row * size + column)
+            };
+            for row in 0..size {
+                for column in 0..size {
+                    let board_column = (head.column_number + column).checked_sub(radius);
+                    let board_row = (head.row_number + row).checked_sub(radius);
+                    let on_board = board_column.is_some_and(|c| c > 0 && c < width - 1)
+                        && board_row.is_some_and(|r| r > 0 && r < height - 1);
+                    if !on_board {
+                        buffer[row * size + column] = 1.0;
+                    }
+                }
+            }
+            if snake.alive {
+                for &position in &snake.tail.positions {
+                    if let Some(index) = window(position) {
+                        buffer[index] = 1.0;
+                    }
+                }
+            }
+            for food in game_state.powerup_positions() {
+                if let Some(index) = window(food) {
+                    buffer[plane + index] = 1.0;
+                }
+            }
+        }
+        Encoding::Features => {
+            let heading = snake.player.heading;
+            let dangers = [heading, heading.counter_clockwise(), heading.clockwise()];
+            for (flag, direction) in buffer.iter_mut().zip(dangers) {
+                let safe = head.neighbour(direction).is_some_and(|position| {
+                    matches!(
+                        determine_game_cell(
+                            game_state,
+                            position.column_number,
+                            position.row_number
+                        ),
+                        GameCell::Empty | GameCell::Powerup
+                    )
+                });
+                *flag = if safe { 0.0 } else { 1.0 };
+            }
+            let heading_index = Direction::ALL
+                .iter()
+                .position(|&direction| direction == heading)
+                .expect("every direction is listed");
+            buffer[3 + heading_index] = 1.0;
+            let food_directions = [
+                food.row_number < head.row_number,
+                food.column_number > head.column_number,
+                food.row_number > head.row_number,
+                food.column_number < head.column_number,
+            ];
+            for (flag, present) in buffer[7..].iter_mut().zip(food_directions) {
+                *flag = if present { 1.0 } else { 0.0 };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rules;
+
+    fn environment(encoding: Encoding) -> Environment {
+        Environment::new(EnvironmentConfig {
+            width: 12,
+            height: 8,
+            encoding,
+            ..EnvironmentConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn every_food_is_observed() {
+        let game_state = GameState::seeded(12, 8, 1, 5).with_rules(Rules {
+            wrap: false,
+            food: 3,
+        });
+        let mut grid = vec![0.0; Encoding::Grid.len(12, 8)];
+        encode(&game_state, Encoding::Grid, &mut grid);
+        assert_eq!(grid[3 * 12 * 8..].iter().sum::<f32>(), 3.0);
+        let radius = 11;
+        let encoding = Encoding::Egocentric { radius };
+        let mut window = vec![0.0; encoding.len(12, 8)];
+        encode(&game_state, encoding, &mut window);
+        let plane = (2 * radius + 1) * (2 * radius + 1);
+        assert_eq!(window[plane..].iter().sum::<f32>(), 3.0);
+    }
+
+    #[test]
+    fn episodes_are_reproducible_from_the_seed() {
+        let actions = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ];
+        let play = || {
+            let mut env = environment(Encoding::Grid);
+            let mut observations = vec![env.reset(42)];
+            for step in 0..40 {
+                let (observation, _, done, _) = env.step(actions[step / 3 % actions.len()]);
+                observations.push(observation);
+                if done {
+                    break;
+                }
+            }
+            observations
+        };
+        assert_eq!(play(), play());
+    }
+
+    #[test]
+    fn dying_ends_the_episode_with_the_death_penalty() {
+        let mut env = environment(Encoding::Features);
+        env.reset(1);
+        let mut last = (0.0, false);
+        for _ in 0..10 {
+            let (_, reward, done, _) = env.step(Direction::Up);
+            last = (reward, done);
+            if done {
+                break;
+            }
+        }
+        assert_eq!(last, (-1.0, true));
+        assert!(!env.info().truncated);
+        assert_eq!(env.advance(Direction::Left), 0.0);
+    }
+
+    #[test]
+    fn long_episodes_are_truncated() {
+        let mut env = Environment::new(EnvironmentConfig {
+            max_steps: Some(3),
+            rewards: Rewards {
+                step: -0.5,
+                ..Rewards::default()
+            },
+            ..EnvironmentConfig::default()
+        })
+        .unwrap();
+        env.reset(7);
+        let steps: Vec<(f32, bool)> = (0..3)
+            .map(|_| {
+                let (_, reward, done, _) = env.step(Direction::Right);
+                (reward, done)
+            })
+            .collect();
+        assert_eq!(steps, vec![(-0.5, false), (-0.5, false), (-0.5, true)]);
+        assert!(env.info().truncated);
+    }
+
+    #[test]
+    fn windows_wider_than_any_board_are_refused() {
+        let config = |encoding| EnvironmentConfig {
+            encoding,
+            ..EnvironmentConfig::default()
+        };
+        let widest = Encoding::Egocentric {
+            radius: MAX_BOARD_SIZE,
+        };
+        assert!(Environment::new(config(widest)).is_ok());
+        for radius in [MAX_BOARD_SIZE + 1, usize::MAX / 2] {
+            let error = Environment::new(config(Encoding::Egocentric { radius })).err();
+            assert_eq!(
+                error.unwrap().to_string(),
+                "the egocentric radius can be at most 1000"
+            );
+        }
+        assert!(Environment::new(EnvironmentConfig {
+            width: 2,
+            ..EnvironmentConfig::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn the_grid_marks_walls_snake_and_food() {
+        let mut env = environment(Encoding::Grid);
+        let observation = env.reset(3);
+        assert_eq!(observation.shape, vec![4, 8, 12]);
+        let plane = 8 * 12;
+        let channel_total =
+            |channel: usize| -> f32 { observation.data[channel * plane..][..plane].iter().sum() };
+        assert_eq!(channel_total(0), (2 * 12 + 2 * 6) as f32);
+        assert_eq!(channel_total(1), 1.0);
+        assert_eq!(channel_total(2), 1.0);
+        assert_eq!(channel_total(3), 1.0);
+        // The snake starts in the middle column heading right
+        let head = env.game_state().snakes()[0].player.head_position;
+        assert_eq!(
+            observation.data[plane + head.row_number * 12 + head.column_number],
+            1.0
+        );
+    }
+
+    #[test]
+    fn the_egocentric_window_treats_the_outside_as_walls() {
+        let mut env = environment(Encoding::Egocentric { radius: 7 });
+        let observation = env.reset(3);
+        assert_eq!(observation.shape, vec![2, 15, 15]);
+        // The head sits at (6, 4), so the top rows and the left column are beyond the board
+        let obstacle = |column: usize, row: usize| observation.data[row * 15 + column];
+        assert!((0..15).all(|column| obstacle(column, 0) == 1.0));
+        assert!((0..15).all(|row| obstacle(0, row) == 1.0));
+        assert_eq!(obstacle(7, 7), 0.0);
+        assert_eq!(obstacle(6, 7), 1.0);
+        assert_eq!(obstacle(8, 7), 0.0);
+    }
+
+    #[test]
+    fn features_point_at_danger_and_food() {
+        let mut env = environment(Encoding::Features);
+        let mut observation = env.reset(3);
+        while env.game_state().snakes()[0]
+            .player
+            .head_position
+            .column_number
+            < 10
+        {
+            observation = env.step(Direction::Right).0;
+        }
+        let food = env.game_state().powerup_position();
+        // Heading right with the wall straight ahead
+        assert_eq!(observation.data[..7], [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(
+            observation.data[10],
+            if food.column_number < 10 { 1.0 } else { 0.0 }
+        );
+    }
+}
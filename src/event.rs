@@ -0,0 +1,140 @@
+//! What happens in a game, as it happens. `GameState` notes every change it makes to the board
+//! as an event, and anything that follows a game, such as the stats, the achievements or a log,
+//! is an `Observer` of them rather than a comparison of the states before and after each tick.
+
+use crate::game::GameState;
+use crate::player::{Cause, Direction, Position};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A snake was steered onto a new heading, which it takes on the next tick
+    Turned {
+        player: usize,
+        from: Direction,
+        to: Direction,
+    },
+    /// A snake's head moved into `position`, going `heading`
+    Moved {
+        player: usize,
+        position: Position,
+        heading: Direction,
+    },
+    Ate {
+        player: usize,
+        position: Position,
+    },
+    /// A snake grew to `length` cells, head included
+    Grew {
+        player: usize,
+        length: usize,
+    },
+    Died {
+        player: usize,
+        cause: Cause,
+    },
+    /// Food was put out in place of some that was eaten
+    FoodSpawned {
+        position: Position,
+    },
+    /// The snakes filled the board, leaving no room for any more food
+    LevelCompleted,
+}
+
+/// Follows a game through its events. `game_state` is the game as it is after them.
+pub trait Observer {
+    fn observe(&mut self, game_state: &GameState, event: &Event);
+}
+
+impl<F: FnMut(&GameState, &Event)> Observer for F {
+    fn observe(&mut self, game_state: &GameState, event: &Event) {
+        self(game_state, event)
+    }
+}
+
+/// Bites of food at most this many ticks apart make a combo
+pub const COMBO_TICKS: u64 = 20;
+
+/// Counts the first snake's bites in a row, each within `COMBO_TICKS` of the last, for the
+/// stats and the achievements alike
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Combo {
+    /// Ticks the snake has moved on
+    ticks: u64,
+    last_meal: Option<u64>,
+    length: usize,
+    longest: usize,
+}
+
+impl Combo {
+    /// Bites in the combo going on, 0 before the first
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// The longest combo so far
+    pub fn longest(&self) -> usize {
+        self.longest
+    }
+}
+
+impl Observer for Combo {
+    fn observe(&mut self, _game_state: &GameState, event: &Event) {
+        match *event {
+            Event::Moved { player: 0, .. } => self.ticks += 1,
+            Event::Ate { player: 0, .. } => {
+                let in_time = self
+                    .last_meal
+                    .is_some_and(|last_meal| self.ticks.saturating_sub(last_meal) <= COMBO_TICKS);
+                self.length = if in_time { self.length + 1 } else { 1 };
+                self.longest = self.longest.max(self.length);
+                self.last_meal = Some(self.ticks);
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_tagged_by_name_for_logs() {
+        let event = Event::Died {
+            player: 1,
+            cause: Cause::Snake(0),
+        };
+        let text = serde_json::to_string(&event).unwrap();
+        assert_eq!(text, r#"{"event":"died","player":1,"cause":{"snake":0}}"#);
+        assert_eq!(serde_json::from_str::<Event>(&text).unwrap(), event);
+        let text = serde_json::to_string(&Event::LevelCompleted).unwrap();
+        assert_eq!(text, r#"{"event":"level_completed"}"#);
+    }
+
+    #[test]
+    fn combos_count_the_first_snakes_bites_in_quick_succession() {
+        let game_state = GameState::seeded(20, 20, 1, 0);
+        let moved = Event::Moved {
+            player: 0,
+            position: Position::new(1, 1),
+            heading: Direction::Right,
+        };
+        let ate = |player| Event::Ate {
+            player,
+            position: Position::new(1, 1),
+        };
+        let mut combo = Combo::default();
+        for event in [moved, ate(0), moved, ate(0), ate(1)] {
+            combo.observe(&game_state, &event);
+        }
+        assert_eq!((combo.length(), combo.longest()), (2, 2));
+        for _ in 0..=COMBO_TICKS {
+            combo.observe(&game_state, &moved);
+        }
+        combo.observe(&game_state, &ate(0));
+        assert_eq!((combo.length(), combo.longest()), (1, 2));
+    }
+}
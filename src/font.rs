@@ -0,0 +1,158 @@
+//! A block font for titles: each character is drawn five rows tall out of a fill character.
+
+/// Rows in every glyph
+pub const GLYPH_HEIGHT: usize = 5;
+
+/// Columns between two glyphs
+const GLYPH_SPACING: usize = 1;
+
+/// The rows of `character`, with `#` where the fill goes. Lower case letters use the upper case
+/// glyphs. `None` for characters the font doesn't have.
+fn glyph(character: char) -> Option<[&'static str; GLYPH_HEIGHT]> {
+    let rows = match character.to_ascii_uppercase() {
+        'A' => [".#.", "#.#", "###", "#.#", "#.#"],
+        'B' => ["##.", "#.#", "##.", "#.#", "##."],
+        'C' => [".##", "#..", "#..", "#..", ".##"],
+        'D' => ["##.", "#.#", "#.#", "#.#", "##."],
+        'E' => ["###", "#..", "##.", "#..", "###"],
+        'F' => ["###", "#..", "##.", "#..", "#.."],
+        'G' => [".##", "#..", "#.#", "#.#", ".##"],
+        'H' => ["#.#", "#.#", "###", "#.#", "#.#"],
+        'I' => ["###", ".#.", ".#.", ".#.", "###"],
+        'J' => ["..#", "..#", "..#", "#.#", ".#."],
+        'K' => ["#.#", "#.#", "##.", "#.#", "#.#"],
+        'L' => ["#..", "#..", "#..", "#..", "###"],
+        'M' => ["#...#", "##.##", "#.#.#", "#...#", "#...#"],
+        'N' => ["#..#", "##.#", "#.##", "#..#", "#..#"],
+        'O' => [".#.", "#.#", "#.#", "#.#", ".#."],
+        'P' => ["##.", "#.#", "##.", "#..", "#.."],
+        'Q' => [".#.", "#.#", "#.#", "##.", ".##"],
+        'R' => ["##.", "#.#", "##.", "#.#", "#.#"],
+        'S' => [".##", "#..", ".#.", "..#", "##."],
+        'T' => ["###", ".#.", ".#.", ".#.", ".#."],
+        'U' => ["#.#", "#.#", "#.#", "#.#", "###"],
+        'V' => ["#.#", "#.#", "#.#", "#.#", ".#."],
+        'W' => ["#...#", "#...#", "#.#.#", "##.##", "#...#"],
+        'X' => ["#.#", "#.#", ".#.", "#.#", "#.#"],
+        'Y' => ["#.#", "#.#", ".#.", ".#.", ".#."],
+        'Z' => ["###", "..#", ".#.", "#..", "###"],
+        '0' => ["###", "#.#", "#.#", "#.#", "###"],
+        '1' => [".#.", "##.", ".#.", ".#.", "###"],
+        '2' => ["##.", "..#", ".#.", "#..", "###"],
+        '3' => ["##.", "..#", ".#.", "..#", "##."],
+        '4' => ["#.#", "#.#", "###", "..#", "..#"],
+        '5' => ["###", "#..", "##.", "..#", "##."],
+        '6' => [".##", "#..", "###", "#.#", "###"],
+        '7' => ["###", "..#", ".#.", ".#.", ".#."],
+        '8' => ["###", "#.#", "###", "#.#", "###"],
+        '9' => ["###", "#.#", "###", "..#", "##."],
+        ' ' => ["..", "..", "..", "..", ".."],
+        '!' => ["#", "#", "#", ".", "#"],
+        '.' => [".", ".", ".", ".", "#"],
+        ':' => [".", "#", ".", "#", "."],
+        '\'' => ["#", "#", ".", ".", "."],
+        '-' => ["...", "...", "###", "...", "..."],
+        '?' => ["##.", "..#", ".#.", "...", ".#."],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+/// `text` in the block font, drawn with `fill`. Trailing blanks are trimmed from every row.
+/// `None` if the font is missing one of the characters.
+pub fn big_text(text: &str, fill: char) -> Option<Vec<String>> {
+    let glyphs = text.chars().map(glyph).collect::<Option<Vec<_>>>()?;
+    let spacing = " ".repeat(GLYPH_SPACING);
+    let rows = (0..GLYPH_HEIGHT)
+        .map(|row| {
+            glyphs
+                .iter()
+                .map(|glyph| {
+                    glyph[row]
+                        .chars()
+                        .map(|cell| if cell == '#' { fill } else { ' ' })
+                        .collect::<String>()
+                })
+                .collect::<Vec<String>>()
+                .join(&spacing)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    Some(rows)
+}
+
+/// The widest row of `rows`
+fn width_of(rows: &[String]) -> usize {
+    rows.iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// `text` as a banner at most `width` columns and `height` rows: on one line of the block font
+/// if it fits, then with a word per line, and as the plain text otherwise. Every row is padded
+/// to the same width so the banner stays in one piece when centered.
+pub fn banner(text: &str, fill: char, width: usize, height: usize) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let layouts = [vec![text], words];
+    for lines in layouts.iter().filter(|lines| !lines.is_empty()) {
+        // A blank row between the lines of the banner
+        let needed = lines.len() * (GLYPH_HEIGHT + 1) - 1;
+        if needed > height {
+            continue;
+        }
+        let Some(blocks) = lines
+            .iter()
+            .map(|line| big_text(line, fill))
+            .collect::<Option<Vec<_>>>()
+        else {
+            break;
+        };
+        let banner_width = blocks
+            .iter()
+            .map(|block| width_of(block))
+            .max()
+            .unwrap_or(0);
+        if banner_width > width {
+            continue;
+        }
+        let rows: Vec<String> = blocks.join(&String::new());
+        return rows
+            .into_iter()
+            .map(|row| format!("{:<1$}", row, banner_width))
+            .collect();
+    }
+    vec![text.to_string()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_drawn_five_rows_tall() {
+        let rows = big_text("Hi!", '#').unwrap();
+        assert_eq!(
+            rows,
+            ["# # ### #", "# #  #  #", "###  #  #", "# #  #", "# # ### #"]
+        );
+        assert_eq!(big_text("snake", '█').unwrap().len(), GLYPH_HEIGHT);
+        assert_eq!(big_text("~", '#'), None);
+    }
+
+    #[test]
+    fn banners_fall_back_to_a_word_per_line_then_plain_text() {
+        let one_line = banner("GAME OVER", '#', 40, 5);
+        assert_eq!(one_line.len(), GLYPH_HEIGHT);
+        assert!(one_line.iter().all(|row| row.chars().count() == 36));
+
+        let stacked = banner("GAME OVER", '#', 20, 20);
+        assert_eq!(stacked.len(), 2 * GLYPH_HEIGHT + 1);
+        assert!(stacked[GLYPH_HEIGHT].trim().is_empty());
+
+        assert_eq!(banner("GAME OVER", '#', 20, 5), ["GAME OVER"]);
+        assert_eq!(banner("GAME OVER", '#', 80, 4), ["GAME OVER"]);
+        assert_eq!(banner("GAME ~ OVER", '#', 80, 20), ["GAME ~ OVER"]);
+    }
+}
@@ -1,28 +1,145 @@
-use crate::player::{Direction, Player, Position, Tail};
+use crate::event::{Combo, Event, Observer};
+use crate::player::{Cause, Direction, Position, Snake};
 use array2d::Array2D;
 use color_eyre::eyre::{eyre, ErrReport, Result};
-use rand::Rng;
-use std::io::{stdout, StdoutLock, Write};
-use std::process;
-use std::{thread, time::Duration};
-use termion::raw::RawTerminal;
-use termion::{async_stdin, cursor, event::Key, input::TermRead, raw::IntoRawMode};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(feature = "terminal")]
+use {
+    crate::achievement::Tracker,
+    crate::ai::{BoardView, Controller},
+    crate::backend::{Backend, Clear},
+    crate::config::Config,
+    crate::keymap::{Action, Bindings, Key},
+    crate::render::Theme,
+    crate::replay::{Replay, Turn},
+};
 
 pub struct GameBoard(pub Array2D<GameCell>);
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct GameArea {
     pub width: usize,
     pub height: usize,
 }
 
-#[derive(Clone)]
+/// The smallest board side with room for a snake and its food
+pub const MIN_BOARD_SIZE: usize = 4;
+/// The largest board side accepted from the command line, files or the network
+pub const MAX_BOARD_SIZE: usize = 1000;
+
+impl GameArea {
+    /// Refuses boards too small to play on or too large to draw
+    pub fn check(width: usize, height: usize) -> Result<()> {
+        if width < MIN_BOARD_SIZE || height < MIN_BOARD_SIZE {
+            return Err(eyre!(
+                "the board must be at least {0} x {0}",
+                MIN_BOARD_SIZE
+            ));
+        }
+        if width > MAX_BOARD_SIZE || height > MAX_BOARD_SIZE {
+            return Err(eyre!("the board can be at most {0} x {0}", MAX_BOARD_SIZE));
+        }
+        Ok(())
+    }
+
+    /// The most snakes a board `height` rows high has room for, one to each row inside the walls
+    pub fn max_players(height: usize) -> usize {
+        height.saturating_sub(2)
+    }
+
+    /// Refuses games without a snake, or with more than the board has room for
+    pub fn check_players(height: usize, players: usize) -> Result<()> {
+        if players == 0 {
+            return Err(eyre!("a game needs at least one player"));
+        }
+        let max_players = Self::max_players(height);
+        if players > max_players {
+            return Err(eyre!(
+                "a board {} rows high has room for at most {} players",
+                height,
+                max_players
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The most food a game can have out at once
+pub const MAX_FOOD: usize = 9;
+
+/// Local game modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Solo,
+    /// Against a computer snake of the chosen difficulty
+    Versus,
+    /// Two players sharing the keyboard
+    TwoPlayer,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Solo, Mode::Versus, Mode::TwoPlayer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Solo => "solo",
+            Mode::Versus => "versus",
+            Mode::TwoPlayer => "two players",
+        }
+    }
+}
+
+/// Variations on the classic game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    /// Heads that reach a wall come out through the opposite one instead of dying
+    pub wrap: bool,
+    /// How much food is on the board at once
+    pub food: usize,
+}
+
+impl Rules {
+    /// The rules of the classic game, which is what a game follows unless told otherwise
+    pub fn is_classic(&self) -> bool {
+        *self == Rules::default()
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            wrap: false,
+            food: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
-    player: Player,
-    tail: Tail,
+    snakes: Vec<Snake>,
     pub game_area: GameArea,
-    powerup: Powerup,
-    score: usize,
+    powerups: Vec<Powerup>,
+    #[serde(default)]
+    rules: Rules,
+    seed: u64,
+    /// Set when the snakes fill the whole board
+    cleared: bool,
+    /// How many ticks have been played
+    #[serde(default)]
+    ticks: u64,
+    /// What happened on the last tick, and since
+    #[serde(skip)]
+    events: Vec<Event>,
+    /// How many of `events` the last tick left, which the next one clears. Anything after them
+    /// happened between ticks and is kept for whoever observes the next one.
+    #[serde(skip)]
+    tick_events: usize,
+    #[serde(with = "rng_state")]
+    rng: ChaCha8Rng,
 }
 
 impl Default for GameState {
@@ -31,7 +148,7 @@ impl Default for GameState {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 struct Powerup(Position);
 
 impl Powerup {
@@ -40,7 +157,7 @@ impl Powerup {
     }
 }
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Wall {
     Horizontal,
     Vertical,
@@ -50,10 +167,11 @@ pub enum Wall {
     BottomRight,
 }
 
-#[derive(PartialEq, Clone, Copy)]
+/// The contents of a single cell. Snake cells carry the index of the snake they belong to.
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum GameCell {
-    Head(Direction),
-    Tail,
+    Head(usize, Direction),
+    Tail(usize),
     Powerup,
     Empty,
     Edge(Wall),
@@ -76,129 +194,759 @@ pub fn determine_game_cell(
         (c, _) if c == right_edge => GameCell::Edge(Wall::Vertical),
         (_, 0) => GameCell::Edge(Wall::Horizontal),
         (_, r) if r == bottom_edge => GameCell::Edge(Wall::Horizontal),
-        _ if game_state.player.head_position.column_number == column_number
-            && game_state.player.head_position.row_number == row_number =>
-        {
-            GameCell::Head(game_state.player.heading)
-        }
-        _ if game_state.powerup.0.column_number == column_number
-            && game_state.powerup.0.row_number == row_number =>
-        {
-            GameCell::Powerup
+        (c, r) if c > right_edge || r > bottom_edge => GameCell::Edge(Wall::Horizontal),
+        _ => {
+            let living_snakes = game_state
+                .snakes
+                .iter()
+                .enumerate()
+                .filter(|(_, snake)| snake.alive);
+            for (index, snake) in living_snakes.clone() {
+                let head = snake.player.head_position;
+                if head.column_number == column_number && head.row_number == row_number {
+                    return GameCell::Head(index, snake.player.heading);
+                }
+            }
+            if game_state.powerups.iter().any(|powerup| {
+                powerup.0.column_number == column_number && powerup.0.row_number == row_number
+            }) {
+                return GameCell::Powerup;
+            }
+            for (index, snake) in living_snakes {
+                if snake.tail.check(column_number, row_number) {
+                    return GameCell::Tail(index);
+                }
+            }
+            GameCell::Empty
         }
-        _ if game_state.tail.check(column_number, row_number) => GameCell::Tail,
-        _ => GameCell::Empty,
     }
 }
 
 impl GameState {
     pub fn new(width: usize, height: usize) -> Self {
+        Self::with_players(width, height, 1)
+    }
+
+    /// Creates a game with `player_count` snakes spread evenly down the middle column
+    pub fn with_players(width: usize, height: usize, player_count: usize) -> Self {
+        Self::seeded(width, height, player_count, rand::random())
+    }
+
+    /// Creates a game whose food placement is fully determined by `seed`, so the same seed and
+    /// the same turns always play out the same way. `player_count` must fit the board, see
+    /// [`GameArea::check_players`].
+    pub fn seeded(width: usize, height: usize, player_count: usize, seed: u64) -> Self {
+        assert!(
+            player_count <= GameArea::max_players(height),
+            "{} snakes don't fit on a board {} rows high",
+            player_count,
+            height
+        );
         let player_x = width / 2;
-        let player_y = height / 2;
-        let tail_x = player_x - 1;
-        let tail_y = player_y;
-        let player = Player::new(player_x, player_y);
-        let tail = Tail::new(tail_x, tail_y);
+        let snakes = (1..=player_count)
+            .map(|index| Snake::new(player_x, index * height / (player_count + 1)))
+            .collect();
         let game_area = GameArea { width, height };
         let powerup = Powerup::new(10, 10);
 
-        Self {
-            player,
-            tail,
+        let mut game_state = Self {
+            snakes,
             game_area,
-            powerup,
-            score: 0,
-        }
-    }
-
-    fn randomize_powerup_position(&mut self) {
-        let mut rng = rand::thread_rng();
-        let mut powerup_column: usize = rng.gen_range(1..self.game_area.width);
-        let mut powerup_row: usize = rng.gen_range(1..self.game_area.height);
-        while determine_game_cell(self, powerup_column, powerup_row) != GameCell::Empty {
-            powerup_column = rng.gen_range(1..self.game_area.width);
-            powerup_row = rng.gen_range(1..self.game_area.height);
-        }
-        self.powerup = Powerup::new(powerup_column, powerup_row);
-    }
-
-    pub fn run(&mut self) -> Result<usize> {
-        let stdout = stdout();
-        let mut stdout = stdout.lock().into_raw_mode()?;
-        let mut input = async_stdin().keys();
-        write!(
-            stdout,
-            "{}{}{}",
-            termion::clear::All,
-            cursor::Goto(1, 1),
-            cursor::Hide
-        )?;
-        stdout.flush()?;
-        loop {
-            let last_key = input.by_ref().last().unwrap_or(Ok(Key::Null))?;
-            if let Key::Char('q') = last_key {
-                drop(stdout);
-                process::exit(0);
-            }
-            self.handle_input(last_key);
+            powerups: vec![powerup],
+            rules: Rules::default(),
+            seed,
+            cleared: false,
+            ticks: 0,
+            events: Vec::new(),
+            tick_events: 0,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+        let powerup_covered = determine_game_cell(&game_state, 10, 10) != GameCell::Powerup
+            || game_state
+                .snakes
+                .iter()
+                .any(|snake| snake.tail.check(10, 10));
+        if powerup_covered {
+            game_state.randomize_powerup_position(0);
+        }
+        game_state
+    }
 
-            if self.tick().is_none() {
+    /// Changes the rules of a freshly created game, putting out any extra food they call for
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        let food = rules.food.clamp(1, MAX_FOOD);
+        self.powerups.truncate(food);
+        while self.powerups.len() < food {
+            let placed = self.powerups.len();
+            // Starts out on top of the first food, which keeps the other food away from it
+            self.powerups.push(self.powerups[0]);
+            self.randomize_powerup_position(placed);
+            if self.powerups.len() == placed {
                 break;
             }
-            self.render(stdout.by_ref())?;
-            thread::sleep(Duration::from_millis(75));
         }
-        write!(stdout, "{}", cursor::Show)?;
-        stdout.flush()?;
-        Ok(self.score)
+        self
     }
 
-    fn render(&self, stdout: &mut RawTerminal<StdoutLock<'_>>) -> Result<()> {
-        let game_board = GameBoard::try_from(self)?;
-        write!(stdout, "{}{}", cursor::Goto(1, 1), game_board,)?;
-        stdout.flush()?;
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// What happened on the last tick in the order it happened, after any turns made and snakes
+    /// removed from play before it. Turns and removals since are added on the end.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Where the first piece of food is, which is the only one under the default rules
+    pub fn powerup_position(&self) -> Position {
+        self.powerups[0].0
+    }
+
+    pub fn powerup_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.powerups.iter().map(|powerup| powerup.0)
+    }
+
+    /// The food fewest steps from `position`, the first of them on a tie
+    pub fn nearest_food(&self, position: Position) -> Position {
+        self.powerup_positions()
+            .min_by_key(|food| food.manhattan_distance(position))
+            .unwrap_or_else(|| self.powerup_position())
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Score of the first snake, which is the only one in a single player game
+    pub fn score(&self) -> usize {
+        self.snakes[0].score
+    }
+
+    /// A game is over once every snake has died, once only one is left in a multiplayer game,
+    /// or once there is no room left for any more food
+    pub fn is_over(&self) -> bool {
+        let living_snakes = self.snakes.iter().filter(|snake| snake.alive).count();
+        self.cleared || living_snakes == 0 || (self.snakes.len() > 1 && living_snakes == 1)
+    }
+
+    /// Refuses states that didn't come from playing the game, such as a hand edited save: the
+    /// board has to be a playable size and every snake and the food have to be on it
+    pub fn check(&self) -> Result<()> {
+        GameArea::check(self.game_area.width, self.game_area.height)?;
+        if self.snakes.is_empty() {
+            return Err(eyre!("the game has no snakes"));
+        }
+        let on_board = |position: &Position| {
+            position.column_number < self.game_area.width
+                && position.row_number < self.game_area.height
+        };
+        let snakes_on_board = self.snakes.iter().all(|snake| {
+            on_board(&snake.player.head_position) && snake.tail.positions.iter().all(on_board)
+        });
+        if self.powerups.is_empty() {
+            return Err(eyre!("the game has no food"));
+        }
+        if !snakes_on_board || !self.powerups.iter().all(|powerup| on_board(&powerup.0)) {
+            return Err(eyre!("the game has pieces off the board"));
+        }
         Ok(())
     }
 
-    fn handle_input(&mut self, input: Key) {
-        let direction = match input {
-            Key::Char('h') | Key::Left => Some(Direction::Left),
-            Key::Char('j') | Key::Down => Some(Direction::Down),
-            Key::Char('k') | Key::Up => Some(Direction::Up),
-            Key::Char('l') | Key::Right => Some(Direction::Right),
-            _ => None,
+    /// Gives the first snake the cells in `body`, head first, and puts the food at `food` or
+    /// somewhere random. The score is set to match the length. Used to set up benchmarks.
+    pub(crate) fn reshape_snake(
+        &mut self,
+        body: &[Position],
+        heading: Direction,
+        food: Option<Position>,
+    ) {
+        let snake = &mut self.snakes[0];
+        snake.player.head_position = body[0];
+        snake.player.heading = heading;
+        snake.tail.positions = body[1..].iter().copied().collect();
+        snake.score = body.len() - 2;
+        match food {
+            Some(food) => self.powerups[0] = Powerup(food),
+            None => self.randomize_powerup_position(0),
+        }
+    }
+
+    /// Removes a snake from play, for example when its player disconnects
+    pub fn kill(&mut self, player_index: usize) {
+        if let Some(snake) = self
+            .snakes
+            .get_mut(player_index)
+            .filter(|snake| snake.alive)
+        {
+            let head = snake.player.head_position;
+            snake.die(Cause::Left, head);
+            self.events.push(Event::Died {
+                player: player_index,
+                cause: Cause::Left,
+            });
+        }
+    }
+
+    /// Moves the powerup at `index` to a random empty cell. Random guesses get slow as the board
+    /// fills up, so after a while the empty cells are listed and one is picked from those instead.
+    /// When there are none left the food is taken off the board, and once the last food goes the
+    /// board has been cleared and the game is over.
+    fn randomize_powerup_position(&mut self, index: usize) {
+        let attempts = self.game_area.width.saturating_mul(self.game_area.height);
+        for _ in 0..attempts {
+            let powerup_column: usize = self.rng.gen_range(1..self.game_area.width);
+            let powerup_row: usize = self.rng.gen_range(1..self.game_area.height);
+            if determine_game_cell(self, powerup_column, powerup_row) == GameCell::Empty {
+                self.powerups[index] = Powerup::new(powerup_column, powerup_row);
+                return;
+            }
+        }
+        let empty_cells: Vec<Position> = (0..self.game_area.height)
+            .flat_map(|row| (0..self.game_area.width).map(move |column| Position::new(column, row)))
+            .filter(|position| {
+                determine_game_cell(self, position.column_number, position.row_number)
+                    == GameCell::Empty
+            })
+            .collect();
+        if empty_cells.is_empty() && self.powerups.len() > 1 {
+            self.powerups.remove(index);
+        } else if empty_cells.is_empty() {
+            self.cleared = true;
+        } else {
+            let cell = self.rng.gen_range(0..empty_cells.len());
+            self.powerups[index] = Powerup(empty_cells[cell]);
+        }
+    }
+
+    /// Where a head that moved onto a wall comes out when the walls are open: just inside the
+    /// opposite wall
+    fn wrap_around(&self, position: Position) -> Position {
+        let GameArea { width, height } = self.game_area;
+        let column = match position.column_number {
+            0 => width - 2,
+            column if column >= width - 1 => 1,
+            column => column,
         };
-        if let Some(dir) = direction {
-            self.player.change_heading(dir);
+        let row = match position.row_number {
+            0 => height - 2,
+            row if row >= height - 1 => 1,
+            row => row,
+        };
+        Position::new(column, row)
+    }
+
+    /// Turns the given snake, ignoring unknown players and reversals
+    pub fn steer(&mut self, player_index: usize, direction: Direction) {
+        if let Some(snake) = self.snakes.get_mut(player_index) {
+            let from = snake.player.heading;
+            snake.player.change_heading(direction);
+            if snake.player.heading != from {
+                self.events.push(Event::Turned {
+                    player: player_index,
+                    from,
+                    to: snake.player.heading,
+                });
+            }
         }
     }
 
-    fn tick(&mut self) -> Option<()> {
-        let next_position = self.player.calculate_new_position();
+    /// Plays a tick like `tick`, then hands `observer` everything that happened on it and
+    /// before it since the last tick
+    pub fn tick_observed(&mut self, observer: &mut impl Observer) -> Option<()> {
+        let running = self.tick();
+        for event in &self.events {
+            observer.observe(self, event);
+        }
+        running
+    }
 
-        let next_game_cell: GameCell =
-            determine_game_cell(self, next_position.column_number, next_position.row_number);
+    /// Advances every living snake by one cell. Returns `None` once the game is over.
+    pub fn tick(&mut self) -> Option<()> {
+        self.events.drain(..self.tick_events);
+        let was_cleared = self.cleared;
+        let next_positions: Vec<Option<Position>> = self
+            .snakes
+            .iter()
+            .map(|snake| {
+                snake.alive.then(|| {
+                    let position = snake.player.calculate_new_position();
+                    if self.rules.wrap {
+                        self.wrap_around(position)
+                    } else {
+                        position
+                    }
+                })
+            })
+            .collect();
+
+        let next_game_cells: Vec<Option<GameCell>> = next_positions
+            .iter()
+            .map(|next_position| {
+                next_position.map(|position| {
+                    determine_game_cell(self, position.column_number, position.row_number)
+                })
+            })
+            .collect();
 
-        match next_game_cell {
-            GameCell::Empty => {
-                let previous_position = self.player.move_player(next_position);
-                self.tail.positions.push_front(previous_position);
-                let _ = self.tail.positions.pop_back();
-                Some(())
+        let mut eaten = Vec::new();
+        for (index, (next_position, next_game_cell)) in
+            next_positions.iter().zip(next_game_cells).enumerate()
+        {
+            let (Some(next_position), Some(next_game_cell)) = (next_position, next_game_cell)
+            else {
+                continue;
+            };
+            let head_on_collision =
+                next_positions
+                    .iter()
+                    .enumerate()
+                    .position(|(other, position)| {
+                        other != index && position.as_ref() == Some(next_position)
+                    });
+            let snake = &mut self.snakes[index];
+            let cause = match (head_on_collision, next_game_cell) {
+                (Some(other), _) => Some(Cause::Snake(other)),
+                (None, GameCell::Empty) => {
+                    let previous_position = snake.player.move_player(*next_position);
+                    snake.tail.positions.push_front(previous_position);
+                    let _ = snake.tail.positions.pop_back();
+                    self.events.push(Event::Moved {
+                        player: index,
+                        position: *next_position,
+                        heading: snake.player.heading,
+                    });
+                    None
+                }
+                (None, GameCell::Powerup) => {
+                    snake.score += 1;
+                    let previous_position = snake.player.move_player(*next_position);
+                    snake.tail.positions.push_front(previous_position);
+                    eaten.push(*next_position);
+                    self.events.extend([
+                        Event::Moved {
+                            player: index,
+                            position: *next_position,
+                            heading: snake.player.heading,
+                        },
+                        Event::Ate {
+                            player: index,
+                            position: *next_position,
+                        },
+                        Event::Grew {
+                            player: index,
+                            length: snake.tail.positions.len() + 1,
+                        },
+                    ]);
+                    None
+                }
+                (None, GameCell::Tail(other)) if other == index => Some(Cause::OwnTail),
+                (None, GameCell::Tail(other)) => Some(Cause::Snake(other)),
+                (None, GameCell::Edge(_)) => Some(Cause::Wall),
+                (None, GameCell::Head(other, _)) if other != index => Some(Cause::Snake(other)),
+                (None, GameCell::Head(_, _)) => {
+                    panic!("impossible behaviour");
+                }
+            };
+            if let Some(cause) = cause {
+                snake.die(cause, *next_position);
+                self.events.push(Event::Died {
+                    player: index,
+                    cause,
+                });
             }
-            GameCell::Powerup => {
-                self.score += 1;
-                let previous_position = self.player.move_player(next_position);
-                self.tail.positions.push_front(previous_position);
-                self.randomize_powerup_position();
-                Some(())
+        }
+        for position in eaten {
+            let index = self.powerup_positions().position(|food| food == position);
+            if let Some(index) = index {
+                let food = self.powerups.len();
+                self.randomize_powerup_position(index);
+                // Unless there was no room left for it
+                if self.powerups.len() == food && !self.cleared {
+                    let position = self.powerups[index].0;
+                    self.events.push(Event::FoodSpawned { position });
+                }
             }
-            GameCell::Tail => None,
-            GameCell::Edge(_) => None,
-            GameCell::Head(_) => {
-                panic!("impossible behaviour");
+        }
+        if self.cleared && !was_cleared {
+            self.events.push(Event::LevelCompleted);
+        }
+        self.tick_events = self.events.len();
+        self.ticks += 1;
+        if self.is_over() {
+            None
+        } else {
+            Some(())
+        }
+    }
+}
+
+/// What the first snake did over a game, gathered tick by tick for the stats screen. It is
+/// kept with saved games so their stats carry on where they stopped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tally {
+    /// Times the snake changed heading
+    pub turns: usize,
+    #[serde(default)]
+    pub combo: Combo,
+    /// Time spent playing, pauses left out
+    pub played: Duration,
+}
+
+impl Observer for Tally {
+    fn observe(&mut self, game_state: &GameState, event: &Event) {
+        if let Event::Turned { player: 0, .. } = event {
+            self.turns += 1;
+        }
+        self.combo.observe(game_state, event);
+    }
+}
+
+/// How the first snake's game went, for the stats screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunStats {
+    /// The seed the game was started from, to play it again
+    pub seed: u64,
+    /// Food eaten, as there is only the one kind
+    pub score: usize,
+    /// Cells long, head included
+    pub length: usize,
+    pub ticks: u64,
+    pub turns: usize,
+    pub longest_combo: usize,
+    pub played: Duration,
+    /// What the snake died of; `None` if it was still alive when the game ended
+    pub cause: Option<Cause>,
+}
+
+impl RunStats {
+    /// How the game ended for the snake, such as "bit own tail"
+    pub fn ending(&self) -> String {
+        self.cause
+            .map_or(String::from("survived"), |cause| cause.to_string())
+    }
+
+    /// How long each piece of food took to get to, on average. `None` before the first bite.
+    pub fn ticks_per_food(&self) -> Option<f64> {
+        (self.score > 0).then(|| self.ticks as f64 / self.score as f64)
+    }
+}
+
+impl GameState {
+    pub fn run_stats(&self, tally: &Tally) -> RunStats {
+        let snake = &self.snakes[0];
+        RunStats {
+            seed: self.seed,
+            score: snake.score,
+            length: snake.tail.positions.len() + 1,
+            ticks: self.ticks,
+            turns: tally.turns,
+            longest_combo: tally.combo.longest(),
+            played: tally.played,
+            cause: snake.death.map(|death| death.cause),
+        }
+    }
+}
+
+/// How a local game came to an end
+#[cfg(feature = "terminal")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The game was played to the end
+    Over(RunStats),
+    Quit,
+    /// The player asked to save the game and carry on later
+    Save,
+    /// The player asked for a new game in place of this one
+    Restart,
+}
+
+/// Saves from the pause menu, where the game keys don't apply
+#[cfg(feature = "terminal")]
+const SAVE_KEY: Key = Key::Char('s');
+/// How many ticks a press of a boost key plays at double speed
+#[cfg(feature = "terminal")]
+const BOOST_TICKS: usize = 5;
+/// How many times the cell a snake ran into flashes before the snake dissolves
+#[cfg(feature = "terminal")]
+const DEATH_FLASHES: usize = 3;
+/// The most frames a dead snake takes to dissolve; long snakes lose several cells a frame
+#[cfg(feature = "terminal")]
+const DISSOLVE_FRAMES: usize = 12;
+#[cfg(feature = "terminal")]
+const DEATH_FRAME: Duration = Duration::from_millis(80);
+/// How long an achievement shows under the board once it's unlocked
+#[cfg(feature = "terminal")]
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// The line under the board while the game is paused, naming the first key for each option
+#[cfg(feature = "terminal")]
+fn pause_message(keys: &Bindings) -> String {
+    let first = |action| {
+        keys.keys(action)
+            .first()
+            .map_or(String::from("?"), Key::to_string)
+    };
+    format!(
+        "PAUSED: {} resume, {} save, {} quit",
+        first(Action::Pause),
+        SAVE_KEY,
+        first(Action::Quit)
+    )
+}
+
+/// Local games in the terminal, with the players on the first snakes
+#[cfg(feature = "terminal")]
+impl GameState {
+    /// Plays until the game is over or the player leaves through a key or the pause menu. The
+    /// last snakes are steered by `opponents` and the ones before them by players at the
+    /// keyboard, each with their own keys from `config`, which also sets the speed and theme.
+    /// The first snake's play is added to `tally`, and every turn to `replay` if there is one.
+    /// Achievements are followed by `achievements` and announced under the board as they are
+    /// unlocked.
+    pub fn run(
+        &mut self,
+        terminal: &mut impl Backend,
+        opponents: &mut [impl Controller],
+        config: &Config,
+        tally: &mut Tally,
+        achievements: &mut Tracker,
+        mut replay: Option<&mut Replay>,
+    ) -> Result<Outcome> {
+        terminal.clear(Clear::All)?;
+        terminal.move_to(0, 0)?;
+        terminal.set_cursor_visible(false)?;
+        terminal.flush()?;
+        let players = self.snakes.len().saturating_sub(opponents.len());
+        let mut boost = 0;
+        // The achievements being announced, and for how much longer
+        let mut toast: Option<(String, Duration)> = None;
+        // Play time is measured from frame to frame, so slow frames count for what they took
+        let mut frame_started = terminal.now();
+        loop {
+            // Only the last turn each player asked for this frame counts
+            let mut turns = vec![None; players];
+            while let Some(key) = terminal.poll_key()? {
+                match config.keys.action(key) {
+                    Some(Action::Quit) => return Ok(Outcome::Quit),
+                    Some(Action::Restart) => return Ok(Outcome::Restart),
+                    Some(Action::Pause) => {
+                        tally.played += terminal.now().duration_since(frame_started);
+                        if let Some(outcome) = self.pause(terminal, config)? {
+                            return Ok(outcome);
+                        }
+                        frame_started = terminal.now();
+                        turns.fill(None);
+                    }
+                    Some(Action::Turn(player, pressed)) if player < players => {
+                        let heading = self.snakes[player].player.heading;
+                        let steering = config.keys.steering(player);
+                        if let Some(direction) = steering.heading(heading, pressed) {
+                            turns[player] = Some(direction);
+                        }
+                    }
+                    Some(Action::Boost(player)) if player < players => boost = BOOST_TICKS,
+                    _ => (),
+                }
+            }
+            let mut recorded = Vec::new();
+            for (player, direction) in turns.into_iter().enumerate() {
+                if let Some(direction) = direction {
+                    self.steer(player, direction);
+                    recorded.push(Turn {
+                        player_index: player,
+                        direction,
+                    });
+                }
+            }
+            for (index, opponent) in opponents.iter_mut().enumerate() {
+                let player_index = players + index;
+                if self
+                    .snakes
+                    .get(player_index)
+                    .is_some_and(|snake| snake.alive)
+                {
+                    let direction = opponent.decide(&BoardView::new(self, player_index));
+                    self.steer(player_index, direction);
+                    recorded.push(Turn {
+                        player_index,
+                        direction,
+                    });
+                }
+            }
+            if let Some(replay) = replay.as_deref_mut() {
+                replay.record_tick(recorded);
+            }
+
+            let alive: Vec<bool> = self.snakes.iter().map(|snake| snake.alive).collect();
+            let earned = achievements.earned().len();
+            achievements.play(tally.played);
+            let running = self
+                .tick_observed(&mut |game_state: &GameState, event: &Event| {
+                    tally.observe(game_state, event);
+                    achievements.observe(game_state, event);
+                })
+                .is_some();
+            let unlocked = &achievements.earned()[earned..];
+            if !unlocked.is_empty() {
+                let names: Vec<String> = unlocked.iter().map(ToString::to_string).collect();
+                let message = format!("unlocked: {}", names.join(", ")).to_uppercase();
+                toast = Some((message, TOAST_DURATION));
+            }
+            if !running {
+                let dying: Vec<usize> = (0..alive.len())
+                    .filter(|&index| alive[index] && !self.snakes[index].alive)
+                    .collect();
+                self.animate_deaths(terminal, config.theme, &dying)?;
+                break;
+            }
+            self.render(terminal, config.theme)?;
+            let pause = if boost > 0 {
+                boost -= 1;
+                config.tick_duration() / 2
+            } else {
+                config.tick_duration()
+            };
+            if let Some((message, remaining)) = toast.take() {
+                terminal.move_to(0, self.game_area.height)?;
+                if remaining.is_zero() {
+                    terminal.clear(Clear::UntilNewline)?;
+                } else {
+                    terminal.write(&message)?;
+                    toast = Some((message, remaining.saturating_sub(pause)));
+                }
+                terminal.flush()?;
             }
+            terminal.sleep(pause);
+            let now = terminal.now();
+            tally.played += now.duration_since(frame_started);
+            frame_started = now;
         }
+        terminal.set_cursor_visible(true)?;
+        terminal.flush()?;
+        Ok(Outcome::Over(self.run_stats(tally)))
+    }
+
+    /// Flashes the cells the `dying` snakes ran into, then dissolves them from the tail up,
+    /// with what killed them under the board. Any key skips the rest.
+    fn animate_deaths(
+        &self,
+        terminal: &mut impl Backend,
+        theme: Theme,
+        dying: &[usize],
+    ) -> Result<()> {
+        terminal.move_to(0, self.game_area.height)?;
+        terminal.clear(Clear::UntilNewline)?;
+        terminal.write(&self.death_message(dying))?;
+        let longest = dying
+            .iter()
+            .map(|&index| self.snakes[index].body().count())
+            .max()
+            .unwrap_or(0);
+        let per_frame = longest.div_ceil(DISSOLVE_FRAMES).max(1);
+        let flashes = (0..DEATH_FLASHES * 2).map(|frame| (0, frame % 2 == 0));
+        let dissolving = (1..=longest.div_ceil(per_frame)).map(|step| (step * per_frame, false));
+        for (dissolved, flash) in flashes.chain(dissolving) {
+            if terminal.poll_key()?.is_some() {
+                break;
+            }
+            let board = self.death_frame(dying, dissolved, flash)?;
+            terminal.move_to(0, 0)?;
+            terminal.write(&board.render(theme, self.rules.wrap))?;
+            terminal.flush()?;
+            terminal.sleep(DEATH_FRAME);
+        }
+        Ok(())
+    }
+
+    /// The board with the `dying` snakes drawn back in, less the last `dissolved` cells of
+    /// each. With `flash` set their heads are drawn in the cells they ran into.
+    fn death_frame(&self, dying: &[usize], dissolved: usize, flash: bool) -> Result<GameBoard> {
+        let mut board = GameBoard::try_from(self)?;
+        let mut set = |position: Position, cell| {
+            let _ = board
+                .0
+                .set(position.row_number, position.column_number, cell);
+        };
+        for &index in dying {
+            let snake = &self.snakes[index];
+            let head = GameCell::Head(index, snake.player.heading);
+            let length = snake.body().count();
+            for (offset, position) in snake
+                .body()
+                .take(length.saturating_sub(dissolved))
+                .enumerate()
+            {
+                set(
+                    position,
+                    if offset == 0 {
+                        head
+                    } else {
+                        GameCell::Tail(index)
+                    },
+                );
+            }
+            if let Some(death) = snake.death.filter(|_| flash) {
+                set(death.position, head);
+            }
+        }
+        Ok(board)
+    }
+
+    /// What the `dying` snakes died of, for the line under the board
+    fn death_message(&self, dying: &[usize]) -> String {
+        let causes: Vec<String> = dying
+            .iter()
+            .filter_map(|&index| {
+                let cause = self.snakes[index].death?.cause;
+                Some(if self.snakes.len() == 1 {
+                    cause.to_string()
+                } else {
+                    format!("player {} {}", index + 1, cause)
+                })
+            })
+            .collect();
+        causes.join(", ").to_uppercase()
+    }
+
+    /// Shows the pause menu under the board until the player picks something. Returns `None`
+    /// to carry on playing.
+    fn pause(&self, terminal: &mut impl Backend, config: &Config) -> Result<Option<Outcome>> {
+        // A resumed game can be paused before its first frame
+        self.render(terminal, config.theme)?;
+        terminal.move_to(0, self.game_area.height)?;
+        terminal.write(&pause_message(&config.keys))?;
+        terminal.flush()?;
+        loop {
+            let key = terminal.read_key()?;
+            match config.keys.action(key) {
+                Some(Action::Pause) => break,
+                Some(Action::Quit) => return Ok(Some(Outcome::Quit)),
+                _ if key == SAVE_KEY => return Ok(Some(Outcome::Save)),
+                _ => (),
+            }
+        }
+        terminal.move_to(0, self.game_area.height)?;
+        terminal.clear(Clear::UntilNewline)?;
+        terminal.flush()?;
+        Ok(None)
+    }
+
+    fn render(&self, terminal: &mut impl Backend, theme: Theme) -> Result<()> {
+        let game_board = GameBoard::try_from(self)?;
+        terminal.move_to(0, 0)?;
+        terminal.write(&game_board.render(theme, self.rules.wrap))?;
+        terminal.flush()?;
+        Ok(())
     }
 }
 
@@ -219,5 +967,478 @@ impl TryFrom<&GameState> for GameBoard {
     }
 }
 
+impl GameBoard {
+    /// Crops the board to at most `columns` x `rows` cells, keeping `center` as close to the
+    /// middle as the edges of the board allow
+    pub fn viewport(&self, center: Position, columns: usize, rows: usize) -> Result<GameBoard> {
+        let columns = columns.min(self.0.num_columns());
+        let rows = rows.min(self.0.num_rows());
+        let first_column = center
+            .column_number
+            .saturating_sub(columns / 2)
+            .min(self.0.num_columns() - columns);
+        let first_row = center
+            .row_number
+            .saturating_sub(rows / 2)
+            .min(self.0.num_rows() - rows);
+        let cropped: Vec<Vec<GameCell>> = (first_row..first_row + rows)
+            .map(|row_index| {
+                (first_column..first_column + columns)
+                    .map(|column_index| self.0[(row_index, column_index)])
+                    .collect()
+            })
+            .collect();
+        let two_dimensional_array =
+            Array2D::from_rows(&cropped).map_err(|_| eyre!("unable to construct"))?;
+        Ok(GameBoard(two_dimensional_array))
+    }
+}
+
+/// Stores the random number generator as its seed and position in the stream, so a saved or
+/// streamed game carries on producing exactly the same food positions
+mod rng_state {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct RngState {
+        seed: [u8; 32],
+        stream: u64,
+        word_pos: u64,
+    }
+
+    pub fn serialize<S: Serializer>(rng: &ChaCha8Rng, serializer: S) -> Result<S::Ok, S::Error> {
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: u64::try_from(rng.get_word_pos()).map_err(S::Error::custom)?,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ChaCha8Rng, D::Error> {
+        let state = RngState::deserialize(deserializer)?;
+        let mut rng = ChaCha8Rng::from_seed(state.seed);
+        rng.set_stream(state.stream);
+        rng.set_word_pos(u128::from(state.word_pos));
+        Ok(rng)
+    }
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::ai::{BoardView, Difficulty};
+    use proptest::prelude::*;
+    use proptest::sample::select;
+    use std::collections::{HashSet, VecDeque};
+
+    /// A 10 x 8 game with the snake's head at (5, 4), its tail at (4, 4) and the food out of
+    /// the way at (2, 2)
+    fn small_game() -> GameState {
+        let mut game_state = GameState::seeded(10, 8, 1, 0);
+        game_state.powerups[0] = Powerup::new(2, 2);
+        game_state
+    }
+
+    #[test]
+    fn determine_game_cell_labels_the_walls() {
+        let game_state = small_game();
+        let cell = |column, row| determine_game_cell(&game_state, column, row);
+        assert_eq!(cell(0, 0), GameCell::Edge(Wall::TopLeft));
+        assert_eq!(cell(9, 0), GameCell::Edge(Wall::TopRight));
+        assert_eq!(cell(0, 7), GameCell::Edge(Wall::BottomLeft));
+        assert_eq!(cell(9, 7), GameCell::Edge(Wall::BottomRight));
+        assert_eq!(cell(0, 3), GameCell::Edge(Wall::Vertical));
+        assert_eq!(cell(9, 3), GameCell::Edge(Wall::Vertical));
+        assert_eq!(cell(4, 0), GameCell::Edge(Wall::Horizontal));
+        assert_eq!(cell(4, 7), GameCell::Edge(Wall::Horizontal));
+        // Anything past the board counts as wall too
+        assert_eq!(cell(20, 3), GameCell::Edge(Wall::Horizontal));
+        assert_eq!(cell(3, 20), GameCell::Edge(Wall::Horizontal));
+    }
+
+    #[test]
+    fn determine_game_cell_finds_the_snake_and_the_food() {
+        let game_state = small_game();
+        let cell = |column, row| determine_game_cell(&game_state, column, row);
+        assert_eq!(cell(5, 4), GameCell::Head(0, Direction::Right));
+        assert_eq!(cell(4, 4), GameCell::Tail(0));
+        assert_eq!(cell(2, 2), GameCell::Powerup);
+        assert_eq!(cell(7, 6), GameCell::Empty);
+    }
+
+    #[test]
+    fn determine_game_cell_skips_dead_snakes() {
+        let mut game_state = small_game();
+        game_state.kill(0);
+        assert_eq!(determine_game_cell(&game_state, 5, 4), GameCell::Empty);
+        assert_eq!(determine_game_cell(&game_state, 4, 4), GameCell::Empty);
+    }
+
+    #[test]
+    fn tick_moves_the_head_and_drags_the_tail() {
+        let mut game_state = small_game();
+        assert_eq!(game_state.tick(), Some(()));
+        let snake = &game_state.snakes()[0];
+        assert_eq!(snake.player.head_position, Position::new(6, 4));
+        assert_eq!(snake.tail.positions, VecDeque::from([Position::new(5, 4)]));
+        assert_eq!(snake.score, 0);
+    }
+
+    #[test]
+    fn eating_the_food_grows_the_snake_and_moves_the_food() {
+        let mut game_state = small_game();
+        game_state.powerups[0] = Powerup::new(6, 4);
+        assert_eq!(game_state.tick(), Some(()));
+        let snake = &game_state.snakes()[0];
+        assert_eq!(snake.score, 1);
+        assert_eq!(
+            snake.tail.positions,
+            VecDeque::from([Position::new(5, 4), Position::new(4, 4)])
+        );
+        let food = game_state.powerup_position();
+        assert_ne!(food, Position::new(6, 4));
+        assert_eq!(
+            determine_game_cell(&game_state, food.column_number, food.row_number),
+            GameCell::Powerup
+        );
+    }
+
+    #[test]
+    fn ticks_note_what_happened_as_events() {
+        let mut game_state = small_game();
+        game_state.powerups[0] = Powerup::new(6, 4);
+        game_state.tick();
+        let food = game_state.powerups[0].0;
+        assert_eq!(
+            game_state.events(),
+            [
+                Event::Moved {
+                    player: 0,
+                    position: Position::new(6, 4),
+                    heading: Direction::Right
+                },
+                Event::Ate {
+                    player: 0,
+                    position: Position::new(6, 4)
+                },
+                Event::Grew {
+                    player: 0,
+                    length: 3
+                },
+                Event::FoodSpawned { position: food },
+            ]
+        );
+        // Turns made between ticks are kept for the next one
+        game_state.steer(0, Direction::Up);
+        game_state.steer(0, Direction::Up);
+        game_state.tick();
+        assert_eq!(
+            game_state.events(),
+            [
+                Event::Turned {
+                    player: 0,
+                    from: Direction::Right,
+                    to: Direction::Up
+                },
+                Event::Moved {
+                    player: 0,
+                    position: Position::new(6, 3),
+                    heading: Direction::Up
+                },
+            ]
+        );
+        while game_state.tick().is_some() {
+            assert_eq!(game_state.events().len(), 1);
+        }
+        assert_eq!(
+            game_state.events(),
+            [Event::Died {
+                player: 0,
+                cause: Cause::Wall
+            }]
+        );
+    }
+
+    #[test]
+    fn observers_see_each_tick_and_what_came_before_it() {
+        let mut game_state = GameState::seeded(20, 10, 2, 0);
+        let mut seen = Vec::new();
+        let mut observer = |game_state: &GameState, event: &Event| {
+            seen.push((game_state.ticks(), *event));
+        };
+        game_state.kill(1);
+        game_state.tick_observed(&mut observer);
+        assert_eq!(
+            seen[0],
+            (
+                1,
+                Event::Died {
+                    player: 1,
+                    cause: Cause::Left
+                }
+            )
+        );
+        assert!(matches!(seen[1], (1, Event::Moved { player: 0, .. })));
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn steering_takes_effect_on_the_next_tick() {
+        let mut game_state = small_game();
+        game_state.steer(0, Direction::Left);
+        game_state.steer(0, Direction::Down);
+        game_state.tick();
+        let snake = &game_state.snakes()[0];
+        assert_eq!(snake.player.heading, Direction::Down);
+        assert_eq!(snake.player.head_position, Position::new(5, 5));
+        // Unknown players are ignored
+        game_state.steer(3, Direction::Up);
+    }
+
+    #[test]
+    fn hitting_a_wall_ends_a_single_player_game() {
+        let mut game_state = small_game();
+        game_state.steer(0, Direction::Up);
+        let mut ticks = 0;
+        while game_state.tick().is_some() {
+            ticks += 1;
+        }
+        // Rows 3, 2 and 1 are free, row 0 is the wall
+        assert_eq!(ticks, 3);
+        let snake = &game_state.snakes()[0];
+        assert!(!snake.alive);
+        assert_eq!(snake.player.head_position, Position::new(5, 1));
+        let death = snake.death.unwrap();
+        assert_eq!(death.cause, Cause::Wall);
+        assert_eq!(death.position, Position::new(5, 0));
+        assert_eq!(game_state.ticks(), 4);
+        assert!(game_state.is_over());
+    }
+
+    #[test]
+    fn open_walls_take_the_snake_to_the_other_side() {
+        let mut game_state = small_game().with_rules(Rules {
+            wrap: true,
+            food: 1,
+        });
+        game_state.steer(0, Direction::Up);
+        for _ in 0..4 {
+            assert_eq!(game_state.tick(), Some(()));
+        }
+        // Through the top wall from row 1 and out just above the bottom one
+        assert_eq!(
+            game_state.snakes()[0].player.head_position,
+            Position::new(5, 6)
+        );
+    }
+
+    #[test]
+    fn every_piece_of_food_is_replaced_once_eaten() {
+        let mut game_state = small_game().with_rules(Rules {
+            wrap: false,
+            food: 3,
+        });
+        assert_eq!(game_state.powerup_positions().count(), 3);
+        game_state.powerups[1] = Powerup::new(6, 4);
+        game_state.tick();
+        assert_eq!(game_state.score(), 1);
+        let food: HashSet<Position> = game_state.powerup_positions().collect();
+        assert_eq!(food.len(), 3);
+        assert!(!food.contains(&Position::new(6, 4)));
+        assert!(food.contains(&Position::new(2, 2)));
+    }
+
+    #[test]
+    fn food_that_does_not_fit_is_left_out() {
+        // A 4 x 4 board has four cells inside the walls, two of them taken by the snake
+        let game_state = GameState::seeded(4, 4, 1, 0).with_rules(Rules {
+            wrap: false,
+            food: MAX_FOOD,
+        });
+        assert_eq!(game_state.powerup_positions().count(), 2);
+        assert!(!game_state.is_over());
+    }
+
+    #[test]
+    fn every_snake_starts_inside_the_walls_on_cells_of_its_own() {
+        for height in MIN_BOARD_SIZE..40 {
+            for players in 1..=GameArea::max_players(height) {
+                let game_state = GameState::seeded(MIN_BOARD_SIZE, height, players, 0);
+                let mut cells = HashSet::new();
+                for position in game_state.snakes().iter().flat_map(Snake::body) {
+                    assert!(position.column_number > 0 && position.column_number < 3);
+                    assert!(position.row_number > 0 && position.row_number < height - 1);
+                    assert!(cells.insert(position), "{:?} is taken twice", position);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn boards_only_take_as_many_players_as_they_have_rows_for() {
+        assert!(GameArea::check_players(4, 2).is_ok());
+        assert_eq!(
+            GameArea::check_players(4, 3).unwrap_err().to_string(),
+            "a board 4 rows high has room for at most 2 players"
+        );
+        assert!(GameArea::check_players(20, 0).is_err());
+    }
+
+    #[test]
+    fn running_into_its_own_tail_kills_the_snake() {
+        let mut game_state = small_game();
+        game_state.snakes[0].tail.positions = VecDeque::from([
+            Position::new(4, 4),
+            Position::new(4, 3),
+            Position::new(5, 3),
+            Position::new(6, 3),
+        ]);
+        game_state.steer(0, Direction::Up);
+        assert_eq!(game_state.tick(), None);
+        assert!(!game_state.snakes()[0].alive);
+        assert_eq!(game_state.snakes()[0].death.unwrap().cause, Cause::OwnTail);
+    }
+
+    #[test]
+    fn the_end_of_the_tail_has_not_moved_away_yet() {
+        let mut game_state = small_game();
+        game_state.snakes[0].tail.positions = VecDeque::from([
+            Position::new(4, 4),
+            Position::new(4, 3),
+            Position::new(5, 3),
+        ]);
+        game_state.steer(0, Direction::Up);
+        assert_eq!(game_state.tick(), None);
+    }
+
+    #[test]
+    fn snakes_moving_into_the_same_cell_both_die() {
+        // Heads start at rows 3 and 7, so both reach row 5 on the second tick
+        let mut game_state = GameState::seeded(12, 11, 2, 0);
+        game_state.powerups[0] = Powerup::new(2, 2);
+        game_state.steer(0, Direction::Down);
+        game_state.steer(1, Direction::Up);
+        assert_eq!(game_state.tick(), Some(()));
+        assert_eq!(game_state.tick(), None);
+        assert!(game_state.snakes().iter().all(|snake| !snake.alive));
+        let causes: Vec<Cause> = game_state
+            .snakes()
+            .iter()
+            .filter_map(|snake| snake.death.map(|death| death.cause))
+            .collect();
+        assert_eq!(causes, [Cause::Snake(1), Cause::Snake(0)]);
+    }
+
+    #[test]
+    fn multiplayer_games_last_until_one_snake_is_left() {
+        let mut game_state = GameState::seeded(20, 12, 3, 0);
+        game_state.kill(1);
+        assert_eq!(game_state.tick(), Some(()));
+        game_state.kill(2);
+        assert_eq!(game_state.tick(), None);
+        assert!(game_state.snakes()[0].alive);
+        assert_eq!(game_state.snakes()[2].death.unwrap().cause, Cause::Left);
+    }
+
+    /// The invariants that hold after every tick
+    fn check_invariants(game_state: &GameState) -> Result<(), TestCaseError> {
+        let GameArea { width, height } = game_state.game_area;
+        for snake in game_state.snakes() {
+            prop_assert_eq!(snake.tail.positions.len() + 1, snake.score + 2);
+            prop_assert_eq!(snake.alive, snake.death.is_none());
+        }
+        let living: Vec<&Snake> = game_state
+            .snakes()
+            .iter()
+            .filter(|snake| snake.alive)
+            .collect();
+        for snake in &living {
+            let mut cells: HashSet<Position> = snake.tail.positions.iter().copied().collect();
+            cells.insert(snake.player.head_position);
+            prop_assert_eq!(cells.len(), snake.tail.positions.len() + 1);
+        }
+        // Once the board is cleared the food is left where it was eaten
+        if !game_state.is_over() {
+            for food in game_state.powerup_positions() {
+                prop_assert!((1..width - 1).contains(&food.column_number));
+                prop_assert!((1..height - 1).contains(&food.row_number));
+                for snake in &living {
+                    prop_assert_ne!(snake.player.head_position, food);
+                    prop_assert!(!snake.tail.positions.contains(&food));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Steers, ticks once and checks that nobody's heading was reversed
+    fn step(
+        game_state: &mut GameState,
+        turns: &[(usize, Direction)],
+    ) -> Result<bool, TestCaseError> {
+        let headings: Vec<Direction> = game_state
+            .snakes()
+            .iter()
+            .map(|snake| snake.player.heading)
+            .collect();
+        for &(player, direction) in turns {
+            game_state.steer(player, direction);
+        }
+        let running = game_state.tick().is_some();
+        for (before, snake) in headings.into_iter().zip(game_state.snakes()) {
+            prop_assert_ne!(snake.player.heading, before.opposite());
+        }
+        check_invariants(game_state)?;
+        Ok(running)
+    }
+
+    proptest! {
+        #[test]
+        fn rules_hold_for_any_turns(
+            width in 5usize..14,
+            height in 6usize..14,
+            players in 1usize..=3,
+            seed in any::<u64>(),
+            wrap in any::<bool>(),
+            food in 1..=MAX_FOOD,
+            turns in prop::collection::vec((0usize..3, select(Direction::ALL.to_vec())), 0..400),
+        ) {
+            let rules = Rules { wrap, food };
+            let mut game_state = GameState::seeded(width, height, players, seed).with_rules(rules);
+            check_invariants(&game_state)?;
+            for (player, direction) in turns {
+                if !step(&mut game_state, &[(player % players, direction)])? {
+                    break;
+                }
+            }
+        }
+
+        #[test]
+        fn rules_hold_while_bots_play(
+            width in 5usize..12,
+            height in 6usize..12,
+            players in 1usize..=2,
+            seed in any::<u64>(),
+            difficulty in select(vec![Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]),
+        ) {
+            let mut game_state = GameState::seeded(width, height, players, seed);
+            let mut controllers: Vec<_> = (0..players).map(|_| difficulty.controller()).collect();
+            for _ in 0..2_000 {
+                let turns: Vec<(usize, Direction)> = controllers
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(index, controller)| {
+                        (index, controller.decide(&BoardView::new(&game_state, index)))
+                    })
+                    .collect();
+                if !step(&mut game_state, &turns)? {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Plays games between controllers without a terminal, as fast as the controllers allow.
+
+use crate::ai::{BoardView, Controller};
+use crate::game::GameState;
+use serde::Serialize;
+use std::ops::ControlFlow;
+
+/// The board and limits for a headless game
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchConfig {
+    pub width: usize,
+    pub height: usize,
+    /// Seed for the food placement; a random one is picked when missing
+    pub seed: Option<u64>,
+    pub max_ticks: Option<u64>,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            height: 25,
+            seed: None,
+            max_ticks: None,
+        }
+    }
+}
+
+impl MatchConfig {
+    pub fn game_state(&self, players: usize) -> GameState {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        GameState::seeded(self.width, self.height, players, seed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchResult {
+    pub scores: Vec<usize>,
+    pub alive: Vec<bool>,
+    /// Number of ticks each snake stayed alive for
+    pub survival: Vec<u64>,
+    /// Number of ticks played before the game ended or hit the tick limit
+    pub ticks: u64,
+}
+
+impl MatchResult {
+    /// The last snake standing, if the game was played between several snakes
+    pub fn winner(&self) -> Option<usize> {
+        let mut survivors = self.alive.iter().enumerate().filter(|(_, alive)| **alive);
+        match (survivors.next(), survivors.next()) {
+            (Some((index, _)), None) if self.alive.len() > 1 => Some(index),
+            _ => None,
+        }
+    }
+}
+
+/// Plays `game_state` to the end with one controller per snake. `on_tick` sees the game after
+/// every tick, which is where a caller can draw it, slow it down or stop it early.
+pub fn play_match(
+    game_state: &mut GameState,
+    controllers: &mut [impl Controller],
+    max_ticks: Option<u64>,
+    mut on_tick: impl FnMut(&GameState) -> ControlFlow<()>,
+) -> MatchResult {
+    let mut ticks = 0;
+    let mut survival = vec![0; game_state.snakes().len()];
+    while max_ticks.is_none_or(|max_ticks| ticks < max_ticks) {
+        for (player_index, controller) in controllers.iter_mut().enumerate() {
+            if game_state.snakes()[player_index].alive {
+                let direction = controller.decide(&BoardView::new(game_state, player_index));
+                game_state.steer(player_index, direction);
+            }
+        }
+        for (player_index, controller) in controllers.iter().enumerate() {
+            if controller.is_disqualified() {
+                game_state.kill(player_index);
+            }
+        }
+        ticks += 1;
+        let running = game_state.tick().is_some();
+        for (survived, snake) in survival.iter_mut().zip(game_state.snakes()) {
+            if snake.alive {
+                *survived = ticks;
+            }
+        }
+        if on_tick(game_state).is_break() || !running {
+            break;
+        }
+    }
+    for (player_index, controller) in controllers.iter_mut().enumerate() {
+        controller.game_over(&BoardView::new(game_state, player_index));
+    }
+    MatchResult {
+        scores: game_state
+            .snakes()
+            .iter()
+            .map(|snake| snake.score)
+            .collect(),
+        alive: game_state
+            .snakes()
+            .iter()
+            .map(|snake| snake.alive)
+            .collect(),
+        survival,
+        ticks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+
+    #[test]
+    fn matches_stop_at_the_tick_limit() {
+        let mut game_state = GameState::seeded(30, 20, 1, 3);
+        let mut controllers = vec![Difficulty::Hard.controller()];
+        let mut observed = 0;
+        let result = play_match(&mut game_state, &mut controllers, Some(50), |_| {
+            observed += 1;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(result.ticks, 50);
+        assert_eq!(observed, 50);
+        assert_eq!(result.alive, vec![true]);
+        assert_eq!(result.survival, vec![50]);
+        assert_eq!(result.winner(), None);
+    }
+
+    #[test]
+    fn versus_matches_have_a_winner_unless_everyone_dies() {
+        let mut game_state = GameState::seeded(30, 20, 2, 3);
+        let mut controllers = vec![
+            Difficulty::Medium.controller(),
+            Difficulty::Easy.controller(),
+        ];
+        let result = play_match(&mut game_state, &mut controllers, Some(10_000), |_| {
+            ControlFlow::Continue(())
+        });
+        assert!(result
+            .survival
+            .iter()
+            .all(|&survived| survived <= result.ticks));
+        match result.winner() {
+            Some(winner) => assert!(result.alive[winner]),
+            None => assert_eq!(result.alive[0], result.alive[1]),
+        }
+    }
+}
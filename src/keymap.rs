@@ -0,0 +1,382 @@
+//! Which keys do what in a game. Keys are written in the config the way they are labelled on the
+//! keyboard: a single character, `ctrl-` and a character, or one of up, down, left, right,
+//! space, enter, tab, esc and backspace.
+
+use crate::player::{Direction, Steering};
+use color_eyre::eyre::{eyre, ErrReport, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The most players that can share a keyboard
+pub const MAX_LOCAL_PLAYERS: usize = 2;
+
+/// A key press, independent of the backend that read it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Key {
+    /// A printable character; tab is `'\t'` and enter is `'\n'`
+    Char(char),
+    Ctrl(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Backspace,
+    Esc,
+    /// Anything the game has no use for
+    Other,
+}
+
+impl Key {
+    /// q and Ctrl-C leave whichever menu is showing
+    pub fn is_quit(self) -> bool {
+        matches!(self, Key::Char('q') | Key::Ctrl('c'))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(' ') => write!(f, "space"),
+            Key::Char('\n') => write!(f, "enter"),
+            Key::Char('\t') => write!(f, "tab"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Ctrl(c) => write!(f, "ctrl-{}", c),
+            Key::Up => write!(f, "up"),
+            Key::Down => write!(f, "down"),
+            Key::Left => write!(f, "left"),
+            Key::Right => write!(f, "right"),
+            Key::Backspace => write!(f, "backspace"),
+            Key::Esc => write!(f, "esc"),
+            Key::Other => write!(f, "unknown"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = ErrReport;
+
+    fn from_str(name: &str) -> Result<Self> {
+        let single = |text: &str| {
+            let mut chars = text.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        };
+        match name {
+            "space" => Ok(Key::Char(' ')),
+            "enter" => Ok(Key::Char('\n')),
+            "tab" => Ok(Key::Char('\t')),
+            "up" => Ok(Key::Up),
+            "down" => Ok(Key::Down),
+            "left" => Ok(Key::Left),
+            "right" => Ok(Key::Right),
+            "backspace" => Ok(Key::Backspace),
+            "esc" => Ok(Key::Esc),
+            name => match name.strip_prefix("ctrl-").map(single) {
+                Some(Some(c)) => Ok(Key::Ctrl(c)),
+                Some(None) => Err(eyre!("unknown key {:?}", name)),
+                None => single(name)
+                    .filter(|c| !c.is_control())
+                    .map(Key::Char)
+                    .ok_or_else(|| eyre!("unknown key {:?}", name)),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = ErrReport;
+
+    fn try_from(name: String) -> Result<Self> {
+        name.parse()
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+/// Something a key can be bound to. Players are counted from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Turn(usize, Direction),
+    /// Speeds the game up for a moment
+    Boost(usize),
+    Pause,
+    Quit,
+    /// Starts a new game straight away
+    Restart,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Turn(player, direction) => {
+                let direction = match direction {
+                    Direction::Up => "up",
+                    Direction::Right => "right",
+                    Direction::Down => "down",
+                    Direction::Left => "left",
+                };
+                write!(f, "player {} {}", player + 1, direction)
+            }
+            Action::Boost(player) => write!(f, "player {} boost", player + 1),
+            Action::Pause => write!(f, "pause"),
+            Action::Quit => write!(f, "quit"),
+            Action::Restart => write!(f, "restart"),
+        }
+    }
+}
+
+/// The keys for one snake. With relative steering only left and right are used, to turn the
+/// snake counter-clockwise and clockwise, and up and down can be left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerKeys {
+    #[serde(default)]
+    pub steering: Steering,
+    #[serde(default)]
+    pub up: Vec<Key>,
+    #[serde(default)]
+    pub down: Vec<Key>,
+    pub left: Vec<Key>,
+    pub right: Vec<Key>,
+    #[serde(default)]
+    pub boost: Vec<Key>,
+}
+
+impl PlayerKeys {
+    fn turn(&self, direction: Direction) -> &Vec<Key> {
+        match direction {
+            Direction::Up => &self.up,
+            Direction::Right => &self.right,
+            Direction::Down => &self.down,
+            Direction::Left => &self.left,
+        }
+    }
+
+    fn turn_mut(&mut self, direction: Direction) -> &mut Vec<Key> {
+        match direction {
+            Direction::Up => &mut self.up,
+            Direction::Right => &mut self.right,
+            Direction::Down => &mut self.down,
+            Direction::Left => &mut self.left,
+        }
+    }
+}
+
+/// Every key binding, with the first player's keys first in `players`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bindings {
+    pub pause: Vec<Key>,
+    pub quit: Vec<Key>,
+    pub restart: Vec<Key>,
+    pub players: Vec<PlayerKeys>,
+}
+
+impl Default for Bindings {
+    /// The arrows and hjkl for the first player and wasd for the second
+    fn default() -> Self {
+        Self {
+            pause: vec![Key::Char('p'), Key::Esc],
+            quit: vec![Key::Char('q'), Key::Ctrl('c')],
+            restart: vec![Key::Char('r')],
+            players: vec![
+                PlayerKeys {
+                    steering: Steering::Absolute,
+                    up: vec![Key::Up, Key::Char('k')],
+                    down: vec![Key::Down, Key::Char('j')],
+                    left: vec![Key::Left, Key::Char('h')],
+                    right: vec![Key::Right, Key::Char('l')],
+                    boost: vec![Key::Char(' ')],
+                },
+                PlayerKeys {
+                    steering: Steering::Absolute,
+                    up: vec![Key::Char('w')],
+                    down: vec![Key::Char('s')],
+                    left: vec![Key::Char('a')],
+                    right: vec![Key::Char('d')],
+                    boost: vec![Key::Char('\t')],
+                },
+            ],
+        }
+    }
+}
+
+impl Bindings {
+    /// Every action there are bindings for, each player's in turn and then the rest
+    pub fn actions(&self) -> Vec<Action> {
+        let mut actions: Vec<Action> = (0..self.players.len())
+            .flat_map(|player| {
+                Direction::ALL
+                    .map(|direction| Action::Turn(player, direction))
+                    .into_iter()
+                    .chain([Action::Boost(player)])
+            })
+            .collect();
+        actions.extend([Action::Pause, Action::Quit, Action::Restart]);
+        actions
+    }
+
+    /// The keys bound to `action`, none for players without bindings
+    pub fn keys(&self, action: Action) -> &[Key] {
+        match action {
+            Action::Turn(player, direction) => self
+                .players
+                .get(player)
+                .map_or(&[], |keys| keys.turn(direction)),
+            Action::Boost(player) => self.players.get(player).map_or(&[], |keys| &keys.boost),
+            Action::Pause => &self.pause,
+            Action::Quit => &self.quit,
+            Action::Restart => &self.restart,
+        }
+    }
+
+    /// Binds `keys` to `action` in place of its old keys. Players without bindings are ignored.
+    pub fn set(&mut self, action: Action, keys: Vec<Key>) {
+        let slot = match action {
+            Action::Turn(player, direction) => match self.players.get_mut(player) {
+                Some(player_keys) => player_keys.turn_mut(direction),
+                None => return,
+            },
+            Action::Boost(player) => match self.players.get_mut(player) {
+                Some(player_keys) => &mut player_keys.boost,
+                None => return,
+            },
+            Action::Pause => &mut self.pause,
+            Action::Quit => &mut self.quit,
+            Action::Restart => &mut self.restart,
+        };
+        *slot = keys;
+    }
+
+    /// What `key` does, if anything
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.actions()
+            .into_iter()
+            .find(|&action| self.keys(action).contains(&key))
+    }
+
+    /// Whether `action` must have keys: quitting, and every turn the player's steering uses
+    fn is_required(&self, action: Action) -> bool {
+        match action {
+            Action::Turn(player, direction) => self.players.get(player).is_some_and(|keys| {
+                keys.steering == Steering::Absolute
+                    || matches!(direction, Direction::Left | Direction::Right)
+            }),
+            Action::Quit => true,
+            _ => false,
+        }
+    }
+
+    /// The steering `player` uses, absolute for players without bindings
+    pub fn steering(&self, player: usize) -> Steering {
+        self.players
+            .get(player)
+            .map_or(Steering::Absolute, |keys| keys.steering)
+    }
+
+    /// Refuses bindings that leave a snake unsteerable or the game impossible to leave, and keys
+    /// bound to two things at once
+    pub fn check(&self) -> Result<()> {
+        if !(1..=MAX_LOCAL_PLAYERS).contains(&self.players.len()) {
+            return Err(eyre!(
+                "there must be keys for 1 to {} players",
+                MAX_LOCAL_PLAYERS
+            ));
+        }
+        let mut bound: HashMap<Key, Action> = HashMap::new();
+        for action in self.actions() {
+            let keys = self.keys(action);
+            if keys.is_empty() && self.is_required(action) {
+                return Err(eyre!("nothing is bound to {}", action));
+            }
+            for &key in keys {
+                match bound.insert(key, action) {
+                    Some(other) if other != action => {
+                        return Err(eyre!("{} is bound to both {} and {}", key, other, action))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_survive_a_round_trip() {
+        for key in [
+            Key::Char('a'),
+            Key::Char(' '),
+            Key::Char('\n'),
+            Key::Char('\t'),
+            Key::Char('é'),
+            Key::Ctrl('c'),
+            Key::Up,
+            Key::Backspace,
+            Key::Esc,
+        ] {
+            assert_eq!(key.to_string().parse::<Key>().unwrap(), key);
+        }
+        for name in ["", "ctrl-", "ctrl-ab", "shift-a", "unknown", "\u{7}"] {
+            assert!(name.parse::<Key>().is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn keys_are_looked_up_by_action_and_actions_by_key() {
+        let mut bindings = Bindings::default();
+        assert!(bindings.check().is_ok());
+        assert_eq!(
+            bindings.action(Key::Char('k')),
+            Some(Action::Turn(0, Direction::Up))
+        );
+        assert_eq!(
+            bindings.action(Key::Char('a')),
+            Some(Action::Turn(1, Direction::Left))
+        );
+        assert_eq!(bindings.action(Key::Esc), Some(Action::Pause));
+        assert_eq!(bindings.action(Key::Char('z')), None);
+
+        bindings.set(Action::Quit, vec![Key::Char('z')]);
+        assert_eq!(bindings.action(Key::Char('z')), Some(Action::Quit));
+        assert_eq!(bindings.action(Key::Char('q')), None);
+        // There is no third player to bind keys for
+        bindings.set(Action::Boost(2), vec![Key::Char('x')]);
+        assert_eq!(bindings.keys(Action::Boost(2)), &[]);
+        assert_eq!(bindings.actions().len(), 2 * 5 + 3);
+    }
+
+    #[test]
+    fn conflicting_or_missing_bindings_are_rejected() {
+        let mut bindings = Bindings::default();
+        bindings.set(Action::Restart, vec![Key::Char('w')]);
+        let error = bindings.check().unwrap_err().to_string();
+        assert_eq!(error, "w is bound to both player 2 up and restart");
+
+        let mut bindings = Bindings::default();
+        bindings.set(Action::Turn(0, Direction::Down), vec![]);
+        assert!(bindings.check().is_err());
+
+        let mut bindings = Bindings::default();
+        bindings.players.clear();
+        assert!(bindings.check().is_err());
+
+        // Relative steering only turns left and right
+        let mut bindings = Bindings::default();
+        bindings.players[1].steering = Steering::Relative;
+        bindings.set(Action::Turn(1, Direction::Up), vec![]);
+        assert!(bindings.check().is_ok());
+        bindings.set(Action::Turn(1, Direction::Left), vec![]);
+        assert!(bindings.check().is_err());
+    }
+}
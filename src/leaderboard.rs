@@ -0,0 +1,109 @@
+//! The best scores played on this machine, kept in a text file one score per line, best first.
+//! A file holding just the old single high score reads as a leaderboard of one.
+
+use color_eyre::eyre::Result;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Where the leaderboard is kept unless told otherwise
+pub const DEFAULT_LEADERBOARD_PATH: &str = "high_score.txt";
+
+/// How many scores the leaderboard keeps
+pub const MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Leaderboard {
+    scores: Vec<usize>,
+}
+
+impl Leaderboard {
+    /// The best of `scores`, best first. Games that scored nothing don't make the board.
+    pub fn from_scores(mut scores: Vec<usize>) -> Self {
+        scores.retain(|&score| score > 0);
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.truncate(MAX_ENTRIES);
+        Self { scores }
+    }
+
+    /// Reads one score per line. Lines that aren't scores are skipped, so a damaged file loses
+    /// what it can't read rather than stopping the game from starting.
+    pub fn parse(text: &str) -> Self {
+        Self::from_scores(
+            text.lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect(),
+        )
+    }
+
+    /// Reads the leaderboard at `path`, which starts out empty if there is no file yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let text: String = self
+            .scores
+            .iter()
+            .map(|score| format!("{}\n", score))
+            .collect();
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn scores(&self) -> &[usize] {
+        &self.scores
+    }
+
+    /// The best score so far, 0 when there isn't one
+    pub fn high_score(&self) -> usize {
+        self.scores.first().copied().unwrap_or(0)
+    }
+
+    /// Adds `score`, after any scores it ties with. Returns the place it took, counted from one,
+    /// or `None` if it didn't make the board.
+    pub fn insert(&mut self, score: usize) -> Option<usize> {
+        let index = self
+            .scores
+            .iter()
+            .take_while(|&&best| best >= score)
+            .count();
+        if score == 0 || index >= MAX_ENTRIES {
+            return None;
+        }
+        self.scores.insert(index, score);
+        self.scores.truncate(MAX_ENTRIES);
+        Some(index + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempPath;
+
+    #[test]
+    fn old_high_score_files_and_damaged_lines_are_read() {
+        assert_eq!(Leaderboard::parse("42\n").scores(), [42]);
+        assert_eq!(Leaderboard::parse("3\nlots\n\n 7 \n0\n").scores(), [7, 3]);
+        assert_eq!(Leaderboard::parse("").high_score(), 0);
+    }
+
+    #[test]
+    fn scores_take_their_place_and_fall_off_the_bottom() {
+        let mut leaderboard = Leaderboard::from_scores((1..=MAX_ENTRIES).collect());
+        assert_eq!(leaderboard.insert(5), Some(7));
+        assert_eq!(leaderboard.insert(100), Some(1));
+        assert_eq!(leaderboard.scores().len(), MAX_ENTRIES);
+        assert_eq!(leaderboard.high_score(), 100);
+        assert_eq!(leaderboard.insert(3), None);
+        assert_eq!(leaderboard.insert(0), None);
+        let path = TempPath::new("leaderboard.scores");
+        leaderboard.save(&path).unwrap();
+        assert_eq!(Leaderboard::load(&path).unwrap(), leaderboard);
+    }
+}
@@ -1,6 +1,41 @@
+pub mod achievement;
+pub mod ai;
+#[cfg(feature = "terminal")]
 pub mod app;
+#[cfg(feature = "terminal")]
+pub mod backend;
+pub mod batch;
+pub mod bench;
+pub mod cli;
+pub mod config;
 pub mod constants;
+pub mod environment;
+pub mod event;
+pub mod font;
 pub mod game;
+pub mod headless;
+pub mod keymap;
+pub mod leaderboard;
+pub mod net;
+#[cfg(feature = "terminal")]
+pub mod pages;
 pub mod player;
+pub mod profile;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod render;
+pub mod replay;
+#[cfg(feature = "terminal")]
+pub mod results;
+pub mod save;
+#[cfg(feature = "terminal")]
+pub mod settings;
+#[cfg(feature = "terminal")]
+pub mod spectator;
+#[cfg(test)]
+mod testing;
+pub mod tournament;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "terminal")]
 pub mod welcome;
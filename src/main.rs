@@ -1,55 +1,197 @@
-extern crate termion;
-
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
+use snake::ai::external::{BotSettings, ExternalBot};
+use snake::ai::Controller;
 use snake::app::App;
-use std::fs;
+use snake::backend::{Backend, Terminal};
+use snake::bench;
+use snake::cli::{parse_args, Command};
+use snake::config::{Config, DEFAULT_CONFIG_PATH};
+use snake::headless::{play_match, MatchConfig};
+use snake::leaderboard::{Leaderboard, DEFAULT_LEADERBOARD_PATH};
+use snake::net::{client, server::Server};
+use snake::profile::{Profile, Profiles, DEFAULT_PROFILES_PATH};
+use snake::replay::Replay;
+use snake::spectator::{watch_match, watch_replay};
+use snake::tournament;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, io, slice};
 
-const MIN_TERMINAL_COLUMNS: usize = 80;
-const MIN_TERMINAL_ROWS: usize = 30;
+/// Fails when the terminal is too small for a `width` x `height` board and the line under it,
+/// which is reported once the terminal has been restored
+fn check_terminal_size(terminal: &impl Backend, width: usize, height: usize) -> Result<()> {
+    let (terminal_columns, terminal_rows) = terminal.size()?;
+    if terminal_columns < width || terminal_rows <= height {
+        return Err(eyre!(
+            "This app required a terminal of {} x {}\ncurrent dimensions: {} x {}",
+            width,
+            height + 1,
+            terminal_columns,
+            terminal_rows
+        ));
+    }
+    Ok(())
+}
 
-const WIDTH: usize = 80;
-const HEIGHT: usize = 25;
-const HIGH_SCORE_FILE_PATH: &str = "high_score.txt";
+fn run_bot(
+    command: &str,
+    settings: BotSettings,
+    config: MatchConfig,
+    watch: Option<Duration>,
+) -> Result<()> {
+    let mut bot = ExternalBot::spawn(command, settings)?;
+    let mut game_state = config.game_state(1);
+    let seed = game_state.seed();
+    let result = match watch {
+        Some(tick_duration) => watch_match(
+            &mut Terminal::new()?,
+            &mut game_state,
+            slice::from_mut(&mut bot),
+            vec![String::from("bot")],
+            config.max_ticks,
+            tick_duration,
+        )?,
+        None => play_match(
+            &mut game_state,
+            slice::from_mut(&mut bot),
+            config.max_ticks,
+            |_| ControlFlow::Continue(()),
+        ),
+    };
+    let outcome = if bot.is_disqualified() {
+        "disqualified"
+    } else if result.alive[0] {
+        "alive"
+    } else {
+        "dead"
+    };
+    println!(
+        "seed {}: score {} after {} ticks ({}), {} timeouts, {} malformed answers",
+        seed, result.scores[0], result.ticks, outcome, bot.timeouts, bot.malformed
+    );
+    Ok(())
+}
 
-struct Config {
-    high_score: usize,
-    width: usize,
-    height: usize,
+fn run_tournament(
+    config: &tournament::TournamentConfig,
+    csv: Option<PathBuf>,
+    json: Option<PathBuf>,
+) -> Result<()> {
+    let results = tournament::run(config, |finished, total| {
+        eprint!("\rplayed {}/{} games", finished, total);
+        let _ = io::stderr().flush();
+    })?;
+    eprintln!();
+    println!("{}", tournament::table(&results.standings));
+    if let Some(path) = csv {
+        tournament::write_csv(&results.standings, &mut BufWriter::new(File::create(path)?))?;
+    }
+    if let Some(path) = json {
+        tournament::write_json(&results, &mut BufWriter::new(File::create(path)?))?;
+    }
+    Ok(())
 }
 
-impl Config {
-    fn build() -> Result<Self> {
-        let high_score: usize = fs::read_to_string(HIGH_SCORE_FILE_PATH)?
-            .trim()
-            .parse()
-            .unwrap_or(0);
-        Ok(Config {
-            high_score,
-            width: WIDTH,
-            height: HEIGHT,
-        })
+fn run_bench(duration: Duration) {
+    if cfg!(debug_assertions) {
+        eprintln!("this is a debug build, the numbers mean more with --release");
     }
+    println!("{}", bench::table(&bench::run(duration)));
+}
+
+/// Columns the stats charts take up
+const STATS_WIDTH: usize = 60;
+
+fn run_stats(name: Option<String>) -> Result<()> {
+    let profiles = Profiles::load(DEFAULT_PROFILES_PATH)?;
+    let shown: Vec<&Profile> = match &name {
+        Some(name) => vec![profiles
+            .find(name)
+            .ok_or_else(|| eyre!("there is no profile called {}", name))?],
+        None => profiles.profiles().iter().collect(),
+    };
+    for (index, profile) in shown.into_iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("{}", profile.name);
+        let mut lines = profile.stats.report('#', STATS_WIDTH);
+        lines.push(String::new());
+        lines.push(String::from("ACHIEVEMENTS"));
+        lines.extend(profile.achievement_lines());
+        for line in lines {
+            if line.is_empty() {
+                println!();
+            } else {
+                println!("    {}", line);
+            }
+        }
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let Config {
-        high_score,
-        width,
-        height,
-    } = Config::build()?;
-    let (terminal_columns, terminal_rows) =
-        termion::terminal_size().expect("unable to determine terminal size");
-    if usize::from(terminal_columns) < width || usize::from(terminal_rows) < height {
-        eprintln!(
-            "\
-            This app required a terminal of {} x {}\ncurrent dimensions: {} x {}",
-            MIN_TERMINAL_COLUMNS, MIN_TERMINAL_ROWS, terminal_columns, terminal_rows
-        );
-        panic!();
+    match parse_args(env::args().skip(1))? {
+        Command::Play => {
+            let leaderboard = Leaderboard::load(DEFAULT_LEADERBOARD_PATH)?;
+            let profiles = Profiles::load(DEFAULT_PROFILES_PATH)?;
+            let config = Config::load(DEFAULT_CONFIG_PATH)?;
+            let mut terminal = Terminal::new()?;
+            check_terminal_size(&terminal, config.width, config.height)?;
+            let mut app = App::new(0, config.width, config.height)
+                .with_leaderboard(leaderboard, DEFAULT_LEADERBOARD_PATH)
+                .with_profiles(profiles, DEFAULT_PROFILES_PATH)
+                .with_config(config)
+                .with_config_path(DEFAULT_CONFIG_PATH);
+            app.run(&mut terminal)?;
+        }
+        Command::Serve {
+            bind,
+            config,
+            replay,
+        } => {
+            let replay = replay.map(Replay::load).transpose()?;
+            let mut server =
+                Server::bind(bind, config)?.with_notices(|notice| println!("{}", notice));
+            match replay {
+                Some(replay) => server.broadcast(&replay)?,
+                None => server.run()?,
+            }
+        }
+        Command::Join {
+            addr,
+            name,
+            steering,
+        } => {
+            let config = Config::load(DEFAULT_CONFIG_PATH)?;
+            // The size of the board is only known once the server starts a round
+            let mut terminal = Terminal::new()?;
+            client::join(&mut terminal, &addr, &name, steering, &config.keys)?;
+        }
+        Command::Spectate { addr, name, follow } => {
+            client::spectate(&mut Terminal::new()?, &addr, &name, follow)?
+        }
+        Command::WatchReplay {
+            path,
+            follow,
+            tick_duration,
+        } => {
+            let replay = Replay::load(path)?;
+            watch_replay(&mut Terminal::new()?, &replay, follow, tick_duration)?
+        }
+        Command::Bot {
+            command,
+            settings,
+            config,
+            watch,
+        } => run_bot(&command, settings, config, watch)?,
+        Command::Tournament { config, csv, json } => run_tournament(&config, csv, json)?,
+        Command::Bench { duration } => run_bench(duration),
+        Command::Stats { profile } => run_stats(profile)?,
     }
-
-    let mut app = App::new(high_score, width, height);
-    app.run();
     Ok(())
 }
@@ -0,0 +1,386 @@
+use super::connection::Connection;
+use super::protocol::{ClientMessage, LobbyPlayer, PlayerResult, ServerMessage};
+use crate::backend::{Backend, Clear, Key};
+use crate::font::{self, GLYPH_HEIGHT};
+use crate::game::{GameBoard, GameState};
+use crate::keymap::{Action, Bindings};
+use crate::player::{Direction, Steering};
+use crate::render::{Theme, TAIL_CHARACTERS};
+use crate::spectator::{self, SpectatorView};
+use color_eyre::eyre::{eyre, Result};
+use std::time::{Duration, Instant};
+
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+enum Phase {
+    Lobby,
+    Playing,
+    Results,
+}
+
+/// The terminal front-end for a networked game
+struct Client {
+    connection: Connection,
+    /// The first player's keys steer; restart and boost also toggle ready and leave the results
+    keys: Bindings,
+    steering: Steering,
+    phase: Phase,
+    ready: bool,
+    lobby: Vec<LobbyPlayer>,
+    player_index: usize,
+    names: Vec<String>,
+    notice: Option<String>,
+    latency: Option<Duration>,
+    ping: Option<(u64, Instant)>,
+    last_ping: Instant,
+}
+
+/// Joins the server at `addr` and plays rounds with `keys` until the player quits
+pub fn join(
+    terminal: &mut impl Backend,
+    addr: &str,
+    name: &str,
+    steering: Steering,
+    keys: &Bindings,
+) -> Result<()> {
+    let connection = Connection::connect(addr, name, steering)?;
+    let mut client = Client {
+        connection,
+        keys: keys.clone(),
+        steering,
+        phase: Phase::Lobby,
+        ready: false,
+        lobby: Vec::new(),
+        player_index: 0,
+        names: Vec::new(),
+        notice: None,
+        latency: None,
+        ping: None,
+        last_ping: Instant::now() - PING_INTERVAL,
+    };
+    terminal.clear(Clear::All)?;
+    terminal.set_cursor_visible(false)?;
+    let outcome = client.run(terminal);
+    restore(terminal)?;
+    outcome
+}
+
+impl Client {
+    fn run(&mut self, terminal: &mut impl Backend) -> Result<()> {
+        self.draw_lobby(terminal)?;
+        loop {
+            while let Some(key) = terminal.poll_key()? {
+                match key {
+                    key if key.is_quit() || self.keys.action(key) == Some(Action::Quit) => {
+                        return Ok(())
+                    }
+                    key => self.handle_key(key, terminal)?,
+                }
+            }
+            if self.last_ping.elapsed() >= PING_INTERVAL {
+                self.send_ping()?;
+            }
+            while let Some(message) = self.connection.try_recv()? {
+                self.handle_message(message, terminal)?;
+            }
+            terminal.sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Whether `key` is one of the first player's restart or boost keys, which stand in for
+    /// carrying on between rounds
+    fn is_continue(&self, key: Key) -> bool {
+        matches!(
+            self.keys.action(key),
+            Some(Action::Restart | Action::Boost(0))
+        )
+    }
+
+    /// The keys `is_continue` takes, for the prompts
+    fn continue_keys(&self) -> String {
+        let keys = [Action::Restart, Action::Boost(0)].map(|action| self.keys.keys(action));
+        key_names(&keys.concat())
+    }
+
+    fn handle_key(&mut self, key: Key, terminal: &mut impl Backend) -> Result<()> {
+        match self.phase {
+            Phase::Lobby => {
+                if self.is_continue(key) {
+                    self.ready = !self.ready;
+                    self.connection
+                        .send(&ClientMessage::Ready { ready: self.ready })?;
+                }
+            }
+            Phase::Playing => {
+                // Network games use the first player's keys. Relative turns are resolved by the
+                // server, which knows where the snake is heading.
+                let message = match (self.keys.action(key), self.steering) {
+                    (Some(Action::Turn(0, direction)), Steering::Absolute) => {
+                        Some(ClientMessage::Turn { direction })
+                    }
+                    (Some(Action::Turn(0, Direction::Left)), Steering::Relative) => {
+                        Some(ClientMessage::Rotate { clockwise: false })
+                    }
+                    (Some(Action::Turn(0, Direction::Right)), Steering::Relative) => {
+                        Some(ClientMessage::Rotate { clockwise: true })
+                    }
+                    _ => None,
+                };
+                if let Some(message) = message {
+                    self.connection.send(&message)?;
+                }
+            }
+            Phase::Results => {
+                if self.is_continue(key) {
+                    self.phase = Phase::Lobby;
+                    self.draw_lobby(terminal)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn send_ping(&mut self) -> Result<()> {
+        let nonce = self.ping.map_or(0, |(nonce, _)| nonce + 1);
+        self.connection.send(&ClientMessage::Ping { nonce })?;
+        self.ping = Some((nonce, Instant::now()));
+        self.last_ping = Instant::now();
+        Ok(())
+    }
+
+    fn handle_message(
+        &mut self,
+        message: ServerMessage,
+        terminal: &mut impl Backend,
+    ) -> Result<()> {
+        match message {
+            ServerMessage::Welcome { .. } => (),
+            ServerMessage::Lobby { players } => {
+                self.lobby = players;
+                if let Phase::Lobby = self.phase {
+                    self.draw_lobby(terminal)?;
+                }
+            }
+            ServerMessage::Start {
+                player_index,
+                names,
+                width,
+                height,
+            } => {
+                check_board_fits(terminal, width, height)?;
+                self.phase = Phase::Playing;
+                self.ready = false;
+                self.player_index = player_index.unwrap_or_default();
+                self.names = names;
+                self.notice = None;
+                terminal.clear(Clear::All)?;
+            }
+            ServerMessage::Snapshot { state, .. } => self.draw_game(&state, terminal)?,
+            ServerMessage::Pong { nonce } => {
+                if let Some((sent_nonce, sent_at)) = self.ping {
+                    if sent_nonce == nonce {
+                        self.latency = Some(sent_at.elapsed());
+                    }
+                }
+            }
+            ServerMessage::PlayerLeft { name } => {
+                self.notice = Some(format!("{} disconnected", name));
+            }
+            ServerMessage::GameOver { results } => {
+                self.phase = Phase::Results;
+                let prompt = format!(
+                    "Press {} to return to the lobby, {} to quit",
+                    self.continue_keys(),
+                    key_names(self.keys.keys(Action::Quit))
+                );
+                draw_results(terminal, &results, &prompt)?;
+            }
+            ServerMessage::Rejected { reason } => {
+                return Err(eyre!("the server rejected the connection: {}", reason));
+            }
+        }
+        Ok(())
+    }
+
+    fn latency_text(&self) -> String {
+        match self.latency {
+            Some(latency) => format!("ping {} ms", latency.as_millis()),
+            None => String::from("ping -"),
+        }
+    }
+
+    fn draw_lobby(&self, terminal: &mut impl Backend) -> Result<()> {
+        terminal.clear(Clear::All)?;
+        terminal.move_to(0, 0)?;
+        terminal.write("LOBBY\n\r\n\r")?;
+        for player in &self.lobby {
+            let status = if player.ready { "ready" } else { "waiting" };
+            terminal.write(&format!("  {:<20} {}\n\r", player.name, status))?;
+        }
+        terminal.write(&format!(
+            "\n\rPress {} to toggle ready, {} to quit    {}\n\r",
+            self.continue_keys(),
+            key_names(self.keys.keys(Action::Quit)),
+            self.latency_text()
+        ))?;
+        terminal.flush()?;
+        Ok(())
+    }
+
+    fn draw_game(&self, state: &GameState, terminal: &mut impl Backend) -> Result<()> {
+        let game_board = GameBoard::try_from(state)?;
+        let scores: Vec<String> = self
+            .names
+            .iter()
+            .zip(state.snakes())
+            .enumerate()
+            .map(|(index, (name, snake))| {
+                let marker = if index == self.player_index { "*" } else { " " };
+                let tail = TAIL_CHARACTERS[index % TAIL_CHARACTERS.len()];
+                let status = if snake.alive { "" } else { " (dead)" };
+                format!("{}{} {} {}{}", marker, tail, name, snake.score, status)
+            })
+            .collect();
+        terminal.move_to(0, 0)?;
+        terminal.write(&format!(
+            "{}{}  {}  {}",
+            game_board,
+            scores.join("  "),
+            self.latency_text(),
+            self.notice.as_deref().unwrap_or(""),
+        ))?;
+        terminal.clear(Clear::UntilNewline)?;
+        terminal.flush()?;
+        Ok(())
+    }
+}
+
+/// Fails when the terminal is too small for a `width` x `height` board from the server and
+/// the row of scores under it
+fn check_board_fits(terminal: &impl Backend, width: usize, height: usize) -> Result<()> {
+    let (columns, rows) = terminal.size()?;
+    if columns < width || rows <= height {
+        return Err(eyre!(
+            "the server's board needs a terminal of {} x {}, but this one is {} x {}",
+            width,
+            height + 1,
+            columns,
+            rows
+        ));
+    }
+    Ok(())
+}
+
+fn draw_results(terminal: &mut impl Backend, results: &[PlayerResult], prompt: &str) -> Result<()> {
+    terminal.clear(Clear::All)?;
+    terminal.move_to(0, 0)?;
+    let (width, _) = terminal.size()?;
+    for line in font::banner("GAME OVER", Theme::Classic.fill(), width, GLYPH_HEIGHT) {
+        terminal.write(&format!("{}\n\r", line))?;
+    }
+    terminal.write("\n\r")?;
+    for result in results {
+        let status = if result.alive { "winner" } else { "" };
+        terminal.write(&format!(
+            "  {:<20} {:>5}  {}\n\r",
+            result.name, result.score, status
+        ))?;
+    }
+    terminal.write(&format!("\n\r{}\n\r", prompt))?;
+    terminal.flush()?;
+    Ok(())
+}
+
+/// `keys` as they are written in the config, such as "r or space"
+fn key_names(keys: &[Key]) -> String {
+    let names: Vec<String> = keys.iter().map(ToString::to_string).collect();
+    names.join(" or ")
+}
+
+/// Watches the games on the server at `addr` without taking part
+pub fn spectate(
+    terminal: &mut impl Backend,
+    addr: &str,
+    name: &str,
+    follow: Option<usize>,
+) -> Result<()> {
+    let connection = Connection::spectate(addr, name)?;
+    terminal.clear(Clear::All)?;
+    terminal.move_to(0, 0)?;
+    terminal.set_cursor_visible(false)?;
+    terminal.write("Waiting for the next game to start...")?;
+    terminal.flush()?;
+    let outcome = watch(&connection, follow, terminal);
+    restore(terminal)?;
+    outcome
+}
+
+/// Leaves an empty screen with the cursor back, ready for the shell
+fn restore(terminal: &mut impl Backend) -> Result<()> {
+    terminal.clear(Clear::All)?;
+    terminal.move_to(0, 0)?;
+    terminal.set_cursor_visible(true)?;
+    terminal.flush()
+}
+
+fn watch(
+    connection: &Connection,
+    follow: Option<usize>,
+    terminal: &mut impl Backend,
+) -> Result<()> {
+    let mut view = SpectatorView::new(Vec::new(), follow);
+    let mut latest: Option<GameState> = None;
+    loop {
+        let mut redraw = false;
+        while let Some(key) = terminal.poll_key()? {
+            match key {
+                key if key.is_quit() => return Ok(()),
+                key => view.handle_key(key),
+            }
+            redraw = true;
+        }
+        while let Some(message) = connection.try_recv()? {
+            match message {
+                ServerMessage::Start { names, .. } => {
+                    view = SpectatorView::new(names, view.follow.or(follow));
+                    terminal.clear(Clear::All)?;
+                }
+                ServerMessage::Snapshot { state, .. } => {
+                    latest = Some(*state);
+                    redraw = true;
+                }
+                ServerMessage::GameOver { results } => {
+                    latest = None;
+                    let prompt = "Waiting for the next game to start, q to quit";
+                    draw_results(terminal, &results, prompt)?;
+                }
+                ServerMessage::Rejected { reason } => {
+                    return Err(eyre!("the server rejected the connection: {}", reason));
+                }
+                _ => (),
+            }
+        }
+        if let (true, Some(game_state)) = (redraw, &latest) {
+            spectator::draw(terminal, &view, game_state)?;
+        }
+        terminal.sleep(Duration::from_millis(5));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::memory::MemoryBackend;
+
+    #[test]
+    fn boards_are_checked_against_the_terminal() {
+        let terminal = MemoryBackend::new(40, 16);
+        assert!(check_board_fits(&terminal, 40, 15).is_ok());
+        assert!(check_board_fits(&terminal, 20, 12).is_ok());
+        assert_eq!(
+            check_board_fits(&terminal, 80, 25).unwrap_err().to_string(),
+            "the server's board needs a terminal of 80 x 26, but this one is 40 x 16"
+        );
+        assert!(check_board_fits(&terminal, 40, 16).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use super::protocol::{
+    read_message, write_message, ClientMessage, ServerMessage, MAX_SERVER_LINE_LENGTH,
+};
+use crate::player::Steering;
+use color_eyre::eyre::{eyre, Result};
+use std::io::BufReader;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// A connection to a game server. Incoming messages are read on a background thread so the
+/// caller can poll for them between frames.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<Result<ServerMessage>>,
+}
+
+impl Connection {
+    /// Connects as a player steering with `steering`
+    pub fn connect(addr: impl ToSocketAddrs, name: &str, steering: Steering) -> Result<Self> {
+        let mut connection = Self::open(addr)?;
+        connection.send(&ClientMessage::Hello {
+            name: name.to_string(),
+            steering,
+        })?;
+        Ok(connection)
+    }
+
+    /// Connects as a read-only spectator
+    pub fn spectate(addr: impl ToSocketAddrs, name: &str) -> Result<Self> {
+        let mut connection = Self::open(addr)?;
+        connection.send(&ClientMessage::Spectate {
+            name: name.to_string(),
+        })?;
+        Ok(connection)
+    }
+
+    fn open(addr: impl ToSocketAddrs) -> Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = reader;
+            // Stops at the first message that can't be used, after passing on why
+            loop {
+                let message =
+                    match read_message::<ServerMessage>(&mut reader, MAX_SERVER_LINE_LENGTH) {
+                        Ok(Some(message)) => message.check().map(|_| message),
+                        Ok(None) => return,
+                        Err(error) => Err(error),
+                    };
+                let failed = message.is_err();
+                if sender.send(message).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Ok(Self { stream, incoming })
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> Result<()> {
+        write_message(&mut self.stream, message)
+    }
+
+    /// Returns the next message if one has arrived, or an error once the server has gone away
+    pub fn try_recv(&self) -> Result<Option<ServerMessage>> {
+        match self.incoming.try_recv() {
+            Ok(message) => message.map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(eyre!("the server closed the connection")),
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<ServerMessage>> {
+        match self.incoming.recv_timeout(timeout) {
+            Ok(message) => message.map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(eyre!("the server closed the connection")),
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Networked multiplayer. A server owns the simulation and streams snapshots to clients, which
+//! only ever send direction changes back.
+
+#[cfg(feature = "terminal")]
+pub mod client;
+pub mod connection;
+pub mod protocol;
+pub mod server;
+
+pub const DEFAULT_PORT: u16 = 7373;
@@ -0,0 +1,215 @@
+use crate::game::GameState;
+use crate::player::{Direction, Steering};
+use color_eyre::eyre::{eyre, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Read, Write};
+
+/// Longest message a client may send, newline included
+pub const MAX_CLIENT_LINE_LENGTH: u64 = 4096;
+
+/// Longest message the server may send, newline included: room for a snapshot of the largest
+/// board with every cell taken
+pub const MAX_SERVER_LINE_LENGTH: u64 = 64 << 20;
+
+/// Longest name a player or spectator can go by, in characters
+pub const MAX_NAME_LENGTH: usize = 32;
+
+/// Messages sent from a client to the server, one JSON object per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello {
+        name: String,
+        #[serde(default)]
+        steering: Steering,
+    },
+    Spectate {
+        name: String,
+    },
+    Ready {
+        ready: bool,
+    },
+    Turn {
+        direction: Direction,
+    },
+    /// A quarter turn from wherever the snake is heading when the server gets it, for players
+    /// with relative steering
+    Rotate {
+        clockwise: bool,
+    },
+    Ping {
+        nonce: u64,
+    },
+}
+
+/// Messages sent from the server to its clients, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        client_id: usize,
+    },
+    Lobby {
+        players: Vec<LobbyPlayer>,
+    },
+    /// Sent to everyone when a round starts, with the size of the board so clients can tell
+    /// whether it fits. Spectators have no `player_index`.
+    Start {
+        player_index: Option<usize>,
+        names: Vec<String>,
+        width: usize,
+        height: usize,
+    },
+    Snapshot {
+        tick: u64,
+        state: Box<GameState>,
+    },
+    Pong {
+        nonce: u64,
+    },
+    PlayerLeft {
+        name: String,
+    },
+    GameOver {
+        results: Vec<PlayerResult>,
+    },
+    Rejected {
+        reason: String,
+    },
+}
+
+impl ServerMessage {
+    /// Refuses snapshots that no client could draw: boards of an unplayable size, or with
+    /// snakes or food off them
+    pub fn check(&self) -> Result<()> {
+        if let ServerMessage::Snapshot { state, .. } = self {
+            state.check()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LobbyPlayer {
+    pub name: String,
+    pub ready: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerResult {
+    pub name: String,
+    pub score: usize,
+    pub alive: bool,
+}
+
+/// Writes a message as a single line of JSON
+pub fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the next message of at most `limit` bytes, newline included, returning `None` once
+/// the other side has hung up. A longer line is an error, as is anything that isn't a message.
+pub fn read_message<T: DeserializeOwned>(
+    reader: &mut impl BufRead,
+    limit: u64,
+) -> Result<Option<T>> {
+    let mut line = Vec::new();
+    if reader.take(limit).read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if line.len() as u64 == limit && !line.ends_with(b"\n") {
+        return Err(eyre!("a message was longer than {} bytes", limit));
+    }
+    Ok(Some(serde_json::from_slice(line.trim_ascii_end())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn messages_round_trip_as_lines() {
+        let mut buffer = Vec::new();
+        write_message(
+            &mut buffer,
+            &ClientMessage::Hello {
+                name: "ada".into(),
+                steering: Steering::Relative,
+            },
+        )
+        .unwrap();
+        write_message(
+            &mut buffer,
+            &ClientMessage::Turn {
+                direction: Direction::Up,
+            },
+        )
+        .unwrap();
+        assert_eq!(buffer.iter().filter(|&&byte| byte == b'\n').count(), 2);
+
+        let mut reader = Cursor::new(buffer);
+        let first: Option<ClientMessage> = read_message(&mut reader, 100).unwrap();
+        let second: Option<ClientMessage> = read_message(&mut reader, 100).unwrap();
+        let end: Option<ClientMessage> = read_message(&mut reader, 100).unwrap();
+        assert_eq!(
+            first,
+            Some(ClientMessage::Hello {
+                name: "ada".into(),
+                steering: Steering::Relative,
+            })
+        );
+        assert_eq!(
+            second,
+            Some(ClientMessage::Turn {
+                direction: Direction::Up
+            })
+        );
+        assert_eq!(end, None);
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        let mut reader = Cursor::new(b"{\"type\":\"fly\"}\n".to_vec());
+        let message: Result<Option<ClientMessage>> = read_message(&mut reader, 100);
+        assert!(message.is_err());
+    }
+
+    #[test]
+    fn lines_over_the_limit_are_errors() {
+        let line = b"{\"type\":\"ping\",\"nonce\":1}\n";
+        let read = |limit| {
+            let mut reader = Cursor::new(line.to_vec());
+            read_message::<ClientMessage>(&mut reader, limit)
+        };
+        assert!(read(line.len() as u64).unwrap().is_some());
+        assert_eq!(
+            read(line.len() as u64 - 1).unwrap_err().to_string(),
+            format!("a message was longer than {} bytes", line.len() - 1)
+        );
+    }
+
+    #[test]
+    fn snapshots_of_oversized_boards_fail_the_check() {
+        let snapshot = |width| {
+            let mut state = GameState::seeded(20, 10, 1, 0);
+            state.game_area.width = width;
+            ServerMessage::Snapshot {
+                tick: 0,
+                state: Box::new(state),
+            }
+        };
+        assert!(snapshot(20).check().is_ok());
+        assert!(snapshot(4_000_000_000).check().is_err());
+        // The snake starts in column 10
+        assert_eq!(
+            snapshot(8).check().unwrap_err().to_string(),
+            "the game has pieces off the board"
+        );
+    }
+}
@@ -0,0 +1,935 @@
+use super::protocol::{
+    read_message, write_message, ClientMessage, LobbyPlayer, PlayerResult, ServerMessage,
+    MAX_CLIENT_LINE_LENGTH, MAX_NAME_LENGTH,
+};
+use crate::ai::{BoardView, Controller, Difficulty};
+use crate::game::{GameArea, GameState};
+use crate::player::{Direction, Steering};
+use crate::replay::{Replay, Turn};
+use color_eyre::eyre::Result;
+use std::fmt;
+use std::io::BufReader;
+use std::mem;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a write to one client may block. Snapshots are written on the tick thread, so a
+/// client that stops reading is dropped after this rather than holding up everyone else.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub width: usize,
+    pub height: usize,
+    /// Number of connected and ready players needed before a round starts
+    pub min_players: usize,
+    pub tick_duration: Duration,
+    /// Where to save a replay of the most recent round
+    pub record_path: Option<PathBuf>,
+    /// Computer controlled snakes added to every round alongside the human players
+    pub bots: usize,
+    pub bot_difficulty: Difficulty,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            height: 25,
+            min_players: 2,
+            tick_duration: Duration::from_millis(75),
+            record_path: None,
+            bots: 0,
+            bot_difficulty: Difficulty::Medium,
+        }
+    }
+}
+
+enum Event {
+    Connected(usize, TcpStream),
+    Message(usize, ClientMessage),
+    Disconnected(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    /// Connected, but has not said whether it wants to play or watch
+    Pending,
+    Player,
+    Spectator,
+}
+
+struct Client {
+    id: usize,
+    name: String,
+    role: Role,
+    ready: bool,
+    stream: TcpStream,
+    player_index: Option<usize>,
+    steering: Steering,
+}
+
+/// The game being streamed to clients, either played live or read back from a replay
+struct Match {
+    names: Vec<String>,
+    game_state: GameState,
+    tick: u64,
+    /// Turns received since the last tick, recorded for the replay
+    turns: Vec<Turn>,
+    replay: Replay,
+}
+
+impl Match {
+    fn start_message(&self, player_index: Option<usize>) -> ServerMessage {
+        ServerMessage::Start {
+            player_index,
+            names: self.names.clone(),
+            width: self.game_state.game_area.width,
+            height: self.game_state.game_area.height,
+        }
+    }
+
+    /// Turns a snake and notes the turn for the replay
+    fn steer(&mut self, player_index: usize, direction: Direction) {
+        self.game_state.steer(player_index, direction);
+        self.turns.push(Turn {
+            player_index,
+            direction,
+        });
+    }
+}
+
+/// Something worth telling whoever runs the server, see [`Server::with_notices`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notice {
+    Listening(SocketAddr),
+    Broadcasting(SocketAddr),
+    Connected {
+        client_id: usize,
+    },
+    Disconnected {
+        name: String,
+    },
+    /// A player tried to join a replay broadcast
+    TurnedAway {
+        name: String,
+    },
+    Starting {
+        names: Vec<String>,
+    },
+    Finished {
+        results: Vec<PlayerResult>,
+    },
+}
+
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Notice::Listening(addr) => write!(f, "listening on {}", addr),
+            Notice::Broadcasting(addr) => write!(f, "broadcasting a replay on {}", addr),
+            Notice::Connected { client_id } => write!(f, "client {} connected", client_id),
+            Notice::Disconnected { name } => write!(f, "{} disconnected", name),
+            Notice::TurnedAway { name } => write!(f, "turned away {}", name),
+            Notice::Starting { names } => write!(f, "starting a game for {}", names.join(", ")),
+            Notice::Finished { results } => {
+                let lines: Vec<String> = results
+                    .iter()
+                    .map(|result| format!("{}: {}", result.name, result.score))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// An authoritative game server. Clients only send turns; the server owns the simulation and
+/// broadcasts a snapshot of the whole game after every tick to players and spectators alike.
+pub struct Server {
+    config: ServerConfig,
+    local_addr: SocketAddr,
+    events: Receiver<Event>,
+    clients: Vec<Client>,
+    notices: Box<dyn FnMut(Notice) + Send>,
+}
+
+impl Server {
+    pub fn bind(addr: impl ToSocketAddrs, config: ServerConfig) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || accept_connections(listener, sender));
+        Ok(Self {
+            config,
+            local_addr,
+            events,
+            clients: Vec::new(),
+            notices: Box::new(|_| ()),
+        })
+    }
+
+    /// Hands `notices` what happens on the server, such as clients coming and going and the
+    /// results of each round. Nothing is reported otherwise.
+    pub fn with_notices(mut self, notices: impl FnMut(Notice) + Send + 'static) -> Self {
+        self.notices = Box::new(notices);
+        self
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Plays rounds until the process is stopped
+    pub fn run(&mut self) -> Result<()> {
+        (self.notices)(Notice::Listening(self.local_addr));
+        loop {
+            let results = self.run_round()?;
+            (self.notices)(Notice::Finished { results });
+        }
+    }
+
+    /// Streams a recorded game to spectators, starting again each time it finishes
+    pub fn broadcast(&mut self, replay: &Replay) -> Result<()> {
+        (self.notices)(Notice::Broadcasting(self.local_addr));
+        loop {
+            self.broadcast_round(replay);
+        }
+    }
+
+    /// Waits in the lobby until enough players are ready, then plays a single game
+    pub fn run_round(&mut self) -> Result<Vec<PlayerResult>> {
+        self.wait_in_lobby(false);
+        let mut names: Vec<String> = self.players().map(|client| client.name.clone()).collect();
+        // Bots steer absolutely, like everyone past the end of the list
+        let steering: Vec<Steering> = self.players().map(|client| client.steering).collect();
+        let human_count = names.len();
+        names.extend(self.bot_names());
+        let mut bots: Vec<Box<dyn Controller + Send>> = (0..self.config.bots)
+            .map(|_| self.config.bot_difficulty.controller())
+            .collect();
+        let game_state =
+            GameState::with_players(self.config.width, self.config.height, names.len());
+        (self.notices)(Notice::Starting {
+            names: names.clone(),
+        });
+        let mut players: Vec<&mut Client> = self
+            .clients
+            .iter_mut()
+            .filter(|client| client.role == Role::Player)
+            .collect();
+        for (player_index, client) in players.iter_mut().enumerate() {
+            client.player_index = Some(player_index);
+        }
+        let mut game = Match {
+            replay: Replay::new(&game_state, names.clone()).with_steering(steering),
+            names,
+            game_state,
+            tick: 0,
+            turns: Vec::new(),
+        };
+        self.start(&game);
+
+        let mut next_tick = Instant::now() + self.config.tick_duration;
+        loop {
+            self.handle_events_until(next_tick, &mut game, false);
+            next_tick += self.config.tick_duration;
+            for (bot_index, bot) in bots.iter_mut().enumerate() {
+                let player_index = human_count + bot_index;
+                let direction = bot.decide(&BoardView::new(&game.game_state, player_index));
+                game.steer(player_index, direction);
+            }
+            let turns = mem::take(&mut game.turns);
+            game.replay.record_tick(turns);
+            let running = game.game_state.tick().is_some();
+            self.send_snapshot(&mut game);
+            if !running {
+                break;
+            }
+        }
+
+        if let Some(path) = &self.config.record_path {
+            game.replay.save(path)?;
+        }
+        Ok(self.finish(&game))
+    }
+
+    /// Waits for a spectator, then plays the replay back at the configured speed
+    pub fn broadcast_round(&mut self, replay: &Replay) -> Vec<PlayerResult> {
+        self.wait_in_lobby(true);
+        let mut game = Match {
+            names: replay.header.names.clone(),
+            game_state: replay.initial_state(),
+            tick: 0,
+            turns: Vec::new(),
+            replay: replay.clone(),
+        };
+        self.start(&game);
+
+        let mut next_tick = Instant::now() + self.config.tick_duration;
+        for game_state in replay.states() {
+            self.handle_events_until(next_tick, &mut game, true);
+            next_tick += self.config.tick_duration;
+            game.game_state = game_state;
+            self.send_snapshot(&mut game);
+        }
+        self.finish(&game)
+    }
+
+    fn bot_names(&self) -> Vec<String> {
+        (1..=self.config.bots)
+            .map(|number| format!("{} bot {}", self.config.bot_difficulty, number))
+            .collect()
+    }
+
+    fn players(&self) -> impl Iterator<Item = &Client> {
+        self.clients
+            .iter()
+            .filter(|client| client.role == Role::Player)
+    }
+
+    /// Waits until enough players are ready, or for a single spectator when broadcasting
+    fn wait_in_lobby(&mut self, broadcasting: bool) {
+        for client in self.clients.iter_mut() {
+            client.ready = false;
+            client.player_index = None;
+        }
+        self.broadcast_lobby();
+        loop {
+            if broadcasting {
+                let spectating = self
+                    .clients
+                    .iter()
+                    .any(|client| client.role == Role::Spectator);
+                if spectating {
+                    return;
+                }
+            } else {
+                let everyone_ready = self.players().all(|client| client.ready);
+                if everyone_ready && self.players().count() >= self.config.min_players {
+                    return;
+                }
+            }
+            match self.events.recv_timeout(Duration::from_millis(100)) {
+                Ok(event) => self.handle_lobby_event(event, broadcasting),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    fn handle_lobby_event(&mut self, event: Event, broadcasting: bool) {
+        match event {
+            Event::Connected(id, stream) => self.add_client(id, stream),
+            Event::Message(id, ClientMessage::Hello { .. } | ClientMessage::Spectate { .. })
+                if !self.is_pending(id) => {}
+            Event::Message(id, ClientMessage::Hello { name, .. }) if broadcasting => {
+                self.reject(id, "this server is broadcasting a replay");
+                (self.notices)(Notice::TurnedAway { name });
+            }
+            Event::Message(id, ClientMessage::Hello { .. })
+                if self.players().count() + self.config.bots
+                    >= GameArea::max_players(self.config.height) =>
+            {
+                self.reject(id, "the game is full");
+            }
+            Event::Message(id, ClientMessage::Hello { name, steering }) => {
+                if !self.identify(id, name, Role::Player) {
+                    return;
+                }
+                if let Some(client) = self.client_mut(id) {
+                    client.steering = steering;
+                }
+                self.broadcast_lobby();
+            }
+            Event::Message(id, ClientMessage::Spectate { name }) => {
+                if self.identify(id, name, Role::Spectator) {
+                    self.broadcast_lobby();
+                }
+            }
+            Event::Message(id, ClientMessage::Ready { ready }) => {
+                if let Some(client) = self.client_mut(id) {
+                    client.ready = ready;
+                }
+                self.broadcast_lobby();
+            }
+            Event::Message(id, ClientMessage::Ping { nonce }) => {
+                self.send(id, &ServerMessage::Pong { nonce });
+            }
+            Event::Message(_, ClientMessage::Turn { .. } | ClientMessage::Rotate { .. }) => (),
+            Event::Disconnected(id) => {
+                self.remove_client(id);
+                self.broadcast_lobby();
+            }
+        }
+    }
+
+    /// Handles messages until it is time for the next tick
+    fn handle_events_until(&mut self, deadline: Instant, game: &mut Match, broadcasting: bool) {
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            match self.events.recv_timeout(timeout) {
+                Ok(event) => self.handle_game_event(event, game, broadcasting),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(timeout);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_game_event(&mut self, event: Event, game: &mut Match, broadcasting: bool) {
+        match event {
+            Event::Connected(id, stream) => self.add_client(id, stream),
+            // Clients only introduce themselves once
+            Event::Message(id, ClientMessage::Hello { .. } | ClientMessage::Spectate { .. })
+                if !self.is_pending(id) => {}
+            Event::Message(id, ClientMessage::Hello { .. }) => {
+                let reason = if broadcasting {
+                    "this server is broadcasting a replay"
+                } else {
+                    "a game is already in progress"
+                };
+                self.reject(id, reason);
+            }
+            Event::Message(id, ClientMessage::Spectate { name }) => {
+                if self.identify(id, name, Role::Spectator) {
+                    let start = game.start_message(None);
+                    self.send(id, &start);
+                }
+            }
+            Event::Message(id, ClientMessage::Turn { direction }) => {
+                if let Some(player_index) = self.client_mut(id).and_then(|c| c.player_index) {
+                    game.steer(player_index, direction);
+                }
+            }
+            Event::Message(id, ClientMessage::Rotate { clockwise }) => {
+                if let Some(player_index) = self.client_mut(id).and_then(|c| c.player_index) {
+                    // Recorded as the heading it led to, like any other turn
+                    let heading = game.game_state.snakes()[player_index].player.heading;
+                    let pressed = if clockwise {
+                        Direction::Right
+                    } else {
+                        Direction::Left
+                    };
+                    if let Some(direction) = Steering::Relative.heading(heading, pressed) {
+                        game.steer(player_index, direction);
+                    }
+                }
+            }
+            Event::Message(id, ClientMessage::Ping { nonce }) => {
+                self.send(id, &ServerMessage::Pong { nonce });
+            }
+            Event::Message(_, ClientMessage::Ready { .. }) => (),
+            Event::Disconnected(id) => {
+                if let Some(client) = self.remove_client(id) {
+                    if let Some(player_index) = client.player_index {
+                        game.game_state.kill(player_index);
+                        self.broadcast_all(&ServerMessage::PlayerLeft { name: client.name });
+                    }
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, game: &Match) {
+        let starts: Vec<(usize, Option<usize>)> = self
+            .clients
+            .iter()
+            .filter(|client| client.role != Role::Pending)
+            .map(|client| (client.id, client.player_index))
+            .collect();
+        for (id, player_index) in starts {
+            let start = game.start_message(player_index);
+            self.send(id, &start);
+        }
+    }
+
+    fn send_snapshot(&mut self, game: &mut Match) {
+        game.tick += 1;
+        self.broadcast_all(&ServerMessage::Snapshot {
+            tick: game.tick,
+            state: Box::new(game.game_state.clone()),
+        });
+    }
+
+    fn finish(&mut self, game: &Match) -> Vec<PlayerResult> {
+        let results: Vec<PlayerResult> = game
+            .names
+            .iter()
+            .zip(game.game_state.snakes())
+            .map(|(name, snake)| PlayerResult {
+                name: name.clone(),
+                score: snake.score,
+                alive: snake.alive,
+            })
+            .collect();
+        self.broadcast_all(&ServerMessage::GameOver {
+            results: results.clone(),
+        });
+        results
+    }
+
+    fn add_client(&mut self, id: usize, stream: TcpStream) {
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        (self.notices)(Notice::Connected { client_id: id });
+        self.clients.push(Client {
+            id,
+            name: format!("player {}", id + 1),
+            role: Role::Pending,
+            ready: false,
+            stream,
+            player_index: None,
+            steering: Steering::Absolute,
+        });
+        self.send(id, &ServerMessage::Welcome { client_id: id });
+    }
+
+    /// Gives a pending client its name and role, or turns it away if the name is too long.
+    /// Returns whether the client was let in.
+    fn identify(&mut self, id: usize, name: String, role: Role) -> bool {
+        if name.chars().count() > MAX_NAME_LENGTH {
+            let reason = format!("names can be at most {} characters", MAX_NAME_LENGTH);
+            self.reject(id, &reason);
+            return false;
+        }
+        if let Some(client) = self.client_mut(id) {
+            if client.role == Role::Pending {
+                client.name = name;
+                client.role = role;
+            }
+        }
+        true
+    }
+
+    fn reject(&mut self, id: usize, reason: &str) {
+        let reason = reason.to_string();
+        self.send(id, &ServerMessage::Rejected { reason });
+        self.remove_client(id);
+    }
+
+    fn is_pending(&self, id: usize) -> bool {
+        self.clients
+            .iter()
+            .any(|client| client.id == id && client.role == Role::Pending)
+    }
+
+    fn client_mut(&mut self, id: usize) -> Option<&mut Client> {
+        self.clients.iter_mut().find(|client| client.id == id)
+    }
+
+    fn remove_client(&mut self, id: usize) -> Option<Client> {
+        let position = self.clients.iter().position(|client| client.id == id)?;
+        let client = self.clients.remove(position);
+        (self.notices)(Notice::Disconnected {
+            name: client.name.clone(),
+        });
+        let _ = client.stream.shutdown(Shutdown::Both);
+        Some(client)
+    }
+
+    /// Sends a message to one client. A failed or timed out write means the client has gone
+    /// away or stopped reading; either way the reader thread then reports a disconnect.
+    fn send(&mut self, id: usize, message: &ServerMessage) {
+        if let Some(client) = self.client_mut(id) {
+            if write_message(&mut client.stream, message).is_err() {
+                let _ = client.stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    /// Sends a message to every player and spectator
+    fn broadcast_all(&mut self, message: &ServerMessage) {
+        let recipients = self
+            .clients
+            .iter_mut()
+            .filter(|client| client.role != Role::Pending);
+        for client in recipients {
+            if write_message(&mut client.stream, message).is_err() {
+                let _ = client.stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    fn broadcast_lobby(&mut self) {
+        let bots = self
+            .bot_names()
+            .into_iter()
+            .map(|name| LobbyPlayer { name, ready: true });
+        let players = self
+            .players()
+            .map(|client| LobbyPlayer {
+                name: client.name.clone(),
+                ready: client.ready,
+            })
+            .chain(bots)
+            .collect();
+        self.broadcast_all(&ServerMessage::Lobby { players });
+    }
+}
+
+fn accept_connections(listener: TcpListener, events: Sender<Event>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let _ = stream.set_nodelay(true);
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        if events.send(Event::Connected(id, writer)).is_err() {
+            return;
+        }
+        let events = events.clone();
+        thread::spawn(move || read_client(id, stream, events));
+    }
+}
+
+fn read_client(id: usize, stream: TcpStream, events: Sender<Event>) {
+    let mut reader = BufReader::new(stream);
+    // A line that is too long or isn't a message ends the connection like hanging up does
+    while let Ok(Some(message)) = read_message(&mut reader, MAX_CLIENT_LINE_LENGTH) {
+        if events.send(Event::Message(id, message)).is_err() {
+            return;
+        }
+    }
+    let _ = events.send(Event::Disconnected(id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::connection::Connection;
+    use crate::player::Direction;
+    use crate::testing::TempPath;
+    use std::io::{Read, Write};
+
+    fn test_server(min_players: usize) -> Server {
+        let config = ServerConfig {
+            width: 20,
+            height: 12,
+            min_players,
+            tick_duration: Duration::from_millis(5),
+            record_path: None,
+            ..ServerConfig::default()
+        };
+        Server::bind("127.0.0.1:0", config).unwrap()
+    }
+
+    fn wait_for(connection: &Connection, matches: impl Fn(&ServerMessage) -> bool) {
+        loop {
+            let message = connection
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap()
+                .expect("timed out waiting for the server");
+            if matches(&message) {
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn single_player_round_ends_when_the_snake_hits_a_wall() {
+        let (sender, notices) = mpsc::channel();
+        let mut server = test_server(1).with_notices(move |notice| {
+            let _ = sender.send(notice);
+        });
+        let addr = server.local_addr();
+        let round = thread::spawn(move || server.run_round().unwrap());
+
+        let mut connection = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        connection
+            .send(&ClientMessage::Ready { ready: true })
+            .unwrap();
+        wait_for(&connection, |message| {
+            matches!(
+                message,
+                ServerMessage::Start {
+                    player_index: Some(0),
+                    ..
+                }
+            )
+        });
+        connection
+            .send(&ClientMessage::Turn {
+                direction: Direction::Up,
+            })
+            .unwrap();
+        wait_for(&connection, |message| {
+            matches!(message, ServerMessage::GameOver { .. })
+        });
+
+        let results = round.join().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "ada");
+        assert!(!results[0].alive);
+        let notices: Vec<String> = notices
+            .try_iter()
+            .map(|notice| notice.to_string())
+            .collect();
+        assert_eq!(notices, ["client 0 connected", "starting a game for ada"]);
+    }
+
+    #[test]
+    fn saying_hello_again_mid_game_changes_nothing() {
+        let mut server = test_server(1);
+        let addr = server.local_addr();
+        let round = thread::spawn(move || server.run_round().unwrap());
+
+        let mut connection = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        connection
+            .send(&ClientMessage::Ready { ready: true })
+            .unwrap();
+        wait_for(&connection, |message| {
+            matches!(message, ServerMessage::Start { .. })
+        });
+        connection
+            .send(&ClientMessage::Hello {
+                name: String::from("grace"),
+                steering: Steering::Absolute,
+            })
+            .unwrap();
+        connection
+            .send(&ClientMessage::Turn {
+                direction: Direction::Up,
+            })
+            .unwrap();
+        wait_for(&connection, |message| {
+            assert!(!matches!(message, ServerMessage::Rejected { .. }));
+            matches!(message, ServerMessage::GameOver { .. })
+        });
+
+        let results = round.join().unwrap();
+        assert_eq!(results[0].name, "ada");
+        assert!(!results[0].alive);
+    }
+
+    #[test]
+    fn overlong_lines_and_names_get_clients_dropped() {
+        let (sender, notices) = mpsc::channel();
+        let mut server = test_server(1).with_notices(move |notice| {
+            let _ = sender.send(notice);
+        });
+        let addr = server.local_addr();
+        let round = thread::spawn(move || server.run_round().unwrap());
+
+        let mut flooder = TcpStream::connect(addr).unwrap();
+        flooder
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let line = vec![b'x'; MAX_CLIENT_LINE_LENGTH as usize];
+        flooder.write_all(&line).unwrap();
+        // The server hangs up after its welcome rather than waiting for the rest of the line
+        let mut received = Vec::new();
+        flooder.read_to_end(&mut received).unwrap();
+        assert_eq!(received.iter().filter(|&&byte| byte == b'\n').count(), 1);
+
+        let long_name = "x".repeat(MAX_NAME_LENGTH + 1);
+        let rejected = Connection::connect(addr, &long_name, Steering::Absolute).unwrap();
+        wait_for(
+            &rejected,
+            |message| matches!(message, ServerMessage::Rejected { reason } if reason == "names can be at most 32 characters"),
+        );
+
+        let mut player = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        player.send(&ClientMessage::Ready { ready: true }).unwrap();
+        player
+            .send(&ClientMessage::Turn {
+                direction: Direction::Up,
+            })
+            .unwrap();
+        wait_for(&player, |message| {
+            matches!(message, ServerMessage::GameOver { .. })
+        });
+        let results = round.join().unwrap();
+        assert_eq!(results[0].name, "ada");
+        let notices: Vec<String> = notices
+            .try_iter()
+            .map(|notice| notice.to_string())
+            .collect();
+        assert!(notices.contains(&String::from("player 1 disconnected")));
+    }
+
+    #[test]
+    fn disconnecting_player_forfeits_the_round() {
+        let mut server = test_server(2);
+        let addr = server.local_addr();
+        let round = thread::spawn(move || server.run_round().unwrap());
+
+        let mut first = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        let mut second = Connection::connect(addr, "grace", Steering::Absolute).unwrap();
+        first.send(&ClientMessage::Ready { ready: true }).unwrap();
+        second.send(&ClientMessage::Ready { ready: true }).unwrap();
+        wait_for(&first, |message| {
+            matches!(message, ServerMessage::Start { .. })
+        });
+        wait_for(&second, |message| {
+            matches!(message, ServerMessage::Start { .. })
+        });
+        drop(second);
+
+        wait_for(
+            &first,
+            |message| matches!(message, ServerMessage::PlayerLeft { name } if name == "grace"),
+        );
+        let results = round.join().unwrap();
+        let survivor = results.iter().find(|result| result.alive).unwrap();
+        assert_eq!(survivor.name, "ada");
+    }
+
+    #[test]
+    fn spectators_watch_without_playing_and_rounds_are_recorded() {
+        let record_path = TempPath::new("server.replay");
+        let mut server = test_server(1);
+        server.config.record_path = Some(record_path.to_path_buf());
+        let addr = server.local_addr();
+        let round = thread::spawn(move || server.run_round().unwrap());
+
+        let spectator = Connection::spectate(addr, "watcher").unwrap();
+        let mut player = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        wait_for(
+            &spectator,
+            |message| matches!(message, ServerMessage::Lobby { players } if players.len() == 1),
+        );
+        player.send(&ClientMessage::Ready { ready: true }).unwrap();
+        wait_for(
+            &spectator,
+            |message| matches!(message, ServerMessage::Start { player_index: None, names, .. } if names == &["ada"]),
+        );
+        wait_for(&spectator, |message| {
+            matches!(message, ServerMessage::Snapshot { .. })
+        });
+        wait_for(&spectator, |message| {
+            matches!(message, ServerMessage::GameOver { .. })
+        });
+        let results = round.join().unwrap();
+
+        let replay = Replay::load(&record_path).unwrap();
+        assert_eq!(replay.header.names, vec![String::from("ada")]);
+        let final_state = replay.states().last().unwrap();
+        assert_eq!(final_state.score(), results[0].score);
+        assert!(final_state.is_over());
+    }
+
+    #[test]
+    fn relative_turns_are_resolved_and_recorded_as_headings() {
+        let record_path = TempPath::new("relative.replay");
+        let mut server = test_server(1);
+        server.config.record_path = Some(record_path.to_path_buf());
+        let addr = server.local_addr();
+        let round = thread::spawn(move || server.run_round().unwrap());
+
+        let mut connection = Connection::connect(addr, "ada", Steering::Relative).unwrap();
+        connection
+            .send(&ClientMessage::Ready { ready: true })
+            .unwrap();
+        wait_for(&connection, |message| {
+            matches!(message, ServerMessage::Start { .. })
+        });
+        connection
+            .send(&ClientMessage::Rotate { clockwise: true })
+            .unwrap();
+        wait_for(&connection, |message| {
+            matches!(message, ServerMessage::GameOver { .. })
+        });
+        round.join().unwrap();
+
+        let replay = Replay::load(&record_path).unwrap();
+        assert_eq!(replay.header.steering, vec![Steering::Relative]);
+        let heading = replay.initial_state().snakes()[0].player.heading;
+        let turn = replay.ticks.iter().flatten().next().unwrap();
+        assert_eq!(turn.direction, heading.clockwise());
+    }
+
+    #[test]
+    fn replays_are_broadcast_to_spectators_and_players_are_turned_away() {
+        let game_state = GameState::seeded(20, 12, 1, 5);
+        let mut replay = Replay::new(&game_state, vec![String::from("ada")]);
+        for _ in 0..20 {
+            replay.record_tick(Vec::new());
+        }
+        let mut server = test_server(1);
+        let addr = server.local_addr();
+        thread::spawn(move || server.broadcast(&replay));
+
+        let player = Connection::connect(addr, "grace", Steering::Absolute).unwrap();
+        wait_for(&player, |message| {
+            matches!(message, ServerMessage::Rejected { .. })
+        });
+        let spectator = Connection::spectate(addr, "watcher").unwrap();
+        wait_for(&spectator, |message| {
+            matches!(
+                message,
+                ServerMessage::Start {
+                    player_index: None,
+                    ..
+                }
+            )
+        });
+        wait_for(
+            &spectator,
+            |message| matches!(message, ServerMessage::GameOver { results } if !results[0].alive),
+        );
+    }
+
+    #[test]
+    fn bots_play_alongside_humans() {
+        let mut server = test_server(1);
+        server.config.bots = 2;
+        server.config.bot_difficulty = Difficulty::Easy;
+        let addr = server.local_addr();
+        let round = thread::spawn(move || server.run_round().unwrap());
+
+        let mut connection = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        wait_for(
+            &connection,
+            |message| matches!(message, ServerMessage::Lobby { players } if players.len() == 3),
+        );
+        connection
+            .send(&ClientMessage::Ready { ready: true })
+            .unwrap();
+        wait_for(
+            &connection,
+            |message| matches!(message, ServerMessage::Start { names, .. } if names.len() == 3),
+        );
+        drop(connection);
+
+        let results = round.join().unwrap();
+        assert_eq!(results[1].name, "easy bot 1");
+        assert_eq!(results[2].name, "easy bot 2");
+    }
+
+    #[test]
+    fn players_are_turned_away_once_the_board_is_full() {
+        let mut server = test_server(3);
+        server.config.height = 4;
+        let addr = server.local_addr();
+        thread::spawn(move || server.run_round());
+
+        let first = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        let _second = Connection::connect(addr, "grace", Steering::Absolute).unwrap();
+        wait_for(
+            &first,
+            |message| matches!(message, ServerMessage::Lobby { players } if players.len() == 2),
+        );
+        let third = Connection::connect(addr, "alan", Steering::Absolute).unwrap();
+        wait_for(
+            &third,
+            |message| matches!(message, ServerMessage::Rejected { reason } if reason == "the game is full"),
+        );
+    }
+
+    #[test]
+    fn server_answers_pings() {
+        let mut server = test_server(2);
+        let addr = server.local_addr();
+        thread::spawn(move || server.run_round());
+
+        let mut connection = Connection::connect(addr, "ada", Steering::Absolute).unwrap();
+        connection.send(&ClientMessage::Ping { nonce: 42 }).unwrap();
+        wait_for(&connection, |message| {
+            matches!(message, ServerMessage::Pong { nonce: 42 })
+        });
+    }
+}
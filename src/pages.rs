@@ -0,0 +1,160 @@
+//! The plain screens reached from the menu: a page of text, a list to pick from and a line of
+//! text to type. All are drawn in the same box frame as the welcome screen.
+
+use crate::backend::{Backend, Clear, Key};
+use crate::welcome::{framed_rows, get_inner_row};
+use color_eyre::eyre::Result;
+use std::ops::ControlFlow;
+
+/// Clears the screen and draws `rows` from the top left
+pub(crate) fn draw(terminal: &mut impl Backend, rows: &[String]) -> Result<()> {
+    terminal.clear(Clear::All)?;
+    terminal.move_to(0, 0)?;
+    terminal.write(&rows.join("\n\r"))?;
+    terminal.flush()
+}
+
+/// Shows `lines` until space, enter or Esc is pressed. Breaks if the player quit instead.
+pub fn show_page(
+    terminal: &mut impl Backend,
+    width: usize,
+    height: usize,
+    lines: &[&str],
+) -> Result<ControlFlow<()>> {
+    let mut content: Vec<String> = lines
+        .iter()
+        .map(|line| get_inner_row(Some(line), width))
+        .collect();
+    content.push(get_inner_row(None, width));
+    content.push(get_inner_row(Some("SPACE TO GO BACK"), width));
+    draw(terminal, &framed_rows(content, width, height))?;
+    loop {
+        match terminal.read_key()? {
+            key if key.is_quit() => return Ok(ControlFlow::Break(())),
+            Key::Char(' ') | Key::Char('\n') | Key::Esc => return Ok(ControlFlow::Continue(())),
+            _ => (),
+        }
+    }
+}
+
+/// Lets the player pick one of `entries` under `title` with the arrows and enter, or go back
+/// with Esc. Continues with the index picked, if any, and breaks if the player quit.
+pub fn pick(
+    terminal: &mut impl Backend,
+    width: usize,
+    height: usize,
+    title: &str,
+    entries: &[String],
+) -> Result<ControlFlow<(), Option<usize>>> {
+    let mut selected: usize = 0;
+    loop {
+        // The title and a blank row take two of the rows inside the frame
+        let shown = entries.len().min(height.saturating_sub(4).max(1));
+        let first = (selected + 1).saturating_sub(shown);
+        let mut content = vec![
+            get_inner_row(Some(title), width),
+            get_inner_row(None, width),
+        ];
+        content.extend(
+            entries
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(shown)
+                .map(|(index, entry)| {
+                    if index == selected {
+                        get_inner_row(Some(&format!("> {} <", entry)), width)
+                    } else {
+                        get_inner_row(Some(entry), width)
+                    }
+                }),
+        );
+        draw(terminal, &framed_rows(content, width, height))?;
+        match terminal.read_key()? {
+            key if key.is_quit() => return Ok(ControlFlow::Break(())),
+            Key::Esc => return Ok(ControlFlow::Continue(None)),
+            Key::Char('\n') | Key::Char(' ') if !entries.is_empty() => {
+                return Ok(ControlFlow::Continue(Some(selected)))
+            }
+            Key::Up | Key::Char('k') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => {
+                selected = (selected + 1).min(entries.len().saturating_sub(1))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Lets the player type up to `max_length` characters under `title`, which enter hands back
+/// and Esc drops. Only Ctrl-C quits, as q could be part of the text.
+pub fn prompt(
+    terminal: &mut impl Backend,
+    width: usize,
+    height: usize,
+    title: &str,
+    max_length: usize,
+) -> Result<ControlFlow<(), Option<String>>> {
+    let mut text = String::new();
+    loop {
+        let content = vec![
+            get_inner_row(Some(title), width),
+            get_inner_row(None, width),
+            get_inner_row(Some(&format!("{}_", text)), width),
+            get_inner_row(None, width),
+            get_inner_row(Some("ENTER TO SAVE, ESC TO GO BACK"), width),
+        ];
+        draw(terminal, &framed_rows(content, width, height))?;
+        match terminal.read_key()? {
+            Key::Ctrl('c') => return Ok(ControlFlow::Break(())),
+            Key::Esc => return Ok(ControlFlow::Continue(None)),
+            Key::Char('\n') => return Ok(ControlFlow::Continue(Some(text))),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Char(character)
+                if !character.is_control() && text.chars().count() < max_length =>
+            {
+                text.push(character)
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::memory::MemoryBackend;
+
+    #[test]
+    fn picking_scrolls_to_the_selection_and_esc_goes_back() {
+        let entries: Vec<String> = (1..=9).map(|n| format!("game {}", n)).collect();
+        let mut terminal = MemoryBackend::new(20, 8).with_script(
+            [Key::Down; 6]
+                .into_iter()
+                .chain([Key::Char('\n')])
+                .map(Into::into),
+        );
+        let picked = pick(&mut terminal, 20, 8, "REPLAYS", &entries).unwrap();
+        assert_eq!(picked, ControlFlow::Continue(Some(6)));
+        assert!(terminal.screen().contains("> game 7 <"));
+
+        let mut terminal = MemoryBackend::new(20, 8).with_script([Key::Esc.into()]);
+        let picked = pick(&mut terminal, 20, 8, "REPLAYS", &[]).unwrap();
+        assert_eq!(picked, ControlFlow::Continue(None));
+    }
+
+    #[test]
+    fn typing_stops_at_the_longest_text_and_backspace_takes_back() {
+        let mut terminal = MemoryBackend::new(40, 8).with_script(
+            "quick"
+                .chars()
+                .map(Key::Char)
+                .chain([Key::Backspace, Key::Char('\t'), Key::Char('\n')])
+                .map(Into::into),
+        );
+        let typed = prompt(&mut terminal, 40, 8, "NAME", 4).unwrap();
+        assert_eq!(typed, ControlFlow::Continue(Some(String::from("qui"))));
+        assert!(terminal.screen().contains("qui_"));
+    }
+}
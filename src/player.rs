@@ -1,12 +1,83 @@
+use serde::{Deserialize, Serialize};
 use std::{collections::VecDeque, fmt};
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Right,
     Down,
     Left,
 }
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    /// The direction a quarter turn to the right
+    pub fn clockwise(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// The direction a quarter turn to the left
+    pub fn counter_clockwise(self) -> Direction {
+        self.clockwise().opposite()
+    }
+}
+
+/// How a player's turn keys steer their snake
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Steering {
+    /// Each key points the snake one way across the board
+    #[default]
+    Absolute,
+    /// Two keys turn the snake a quarter turn left or right of where it is going, so it can be
+    /// played one-handed
+    Relative,
+}
+
+impl Steering {
+    pub const ALL: [Steering; 2] = [Steering::Absolute, Steering::Relative];
+
+    /// The heading asked for by pressing the key for `pressed` while going `heading`. Relative
+    /// steering only uses left and right, which turn counter-clockwise and clockwise.
+    pub fn heading(self, heading: Direction, pressed: Direction) -> Option<Direction> {
+        match (self, pressed) {
+            (Steering::Absolute, pressed) => Some(pressed),
+            (Steering::Relative, Direction::Left) => Some(heading.counter_clockwise()),
+            (Steering::Relative, Direction::Right) => Some(heading.clockwise()),
+            (Steering::Relative, Direction::Up | Direction::Down) => None,
+        }
+    }
+}
+
+impl fmt::Display for Steering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Steering::Absolute => write!(f, "absolute"),
+            Steering::Relative => write!(f, "relative"),
+        }
+    }
+}
+
 pub enum MoveType {
     Normal,
     PowerUp,
@@ -26,7 +97,7 @@ impl TryFrom<String> for Direction {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub column_number: usize,
     pub row_number: usize,
@@ -40,6 +111,27 @@ impl Position {
     }
 }
 
+impl Position {
+    /// The neighbouring position in `direction`, or `None` past the top or left of the grid
+    pub fn neighbour(self, direction: Direction) -> Option<Position> {
+        let Position {
+            column_number,
+            row_number,
+        } = self;
+        match direction {
+            Direction::Up => Some(Position::new(column_number, row_number.checked_sub(1)?)),
+            Direction::Right => Some(Position::new(column_number + 1, row_number)),
+            Direction::Down => Some(Position::new(column_number, row_number + 1)),
+            Direction::Left => Some(Position::new(column_number.checked_sub(1)?, row_number)),
+        }
+    }
+
+    pub fn manhattan_distance(self, other: Position) -> usize {
+        self.column_number.abs_diff(other.column_number)
+            + self.row_number.abs_diff(other.row_number)
+    }
+}
+
 impl fmt::Display for Position {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -49,7 +141,7 @@ impl fmt::Display for Position {
         )
     }
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tail {
     pub positions: VecDeque<Position>,
 }
@@ -61,15 +153,13 @@ impl Tail {
         }
     }
     pub fn check(&self, column_number: usize, row_number: usize) -> bool {
-        return self
-            .positions
+        self.positions
             .iter()
             .any(|pos| pos.column_number == column_number && pos.row_number == row_number)
-            .to_owned();
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Player {
     pub head_position: Position,
     pub heading: Direction,
@@ -91,18 +181,20 @@ impl Player {
         self.head_position = new_position;
         previous_position
     }
+    /// The cell the head moves into next. Past the top or left edge the head stays put, which is
+    /// wall all the same.
     pub fn calculate_new_position(&self) -> Position {
         match self.heading {
             Direction::Up => Position {
                 column_number: self.head_position.column_number,
-                row_number: self.head_position.row_number - 1,
+                row_number: self.head_position.row_number.saturating_sub(1),
             },
             Direction::Right => Position {
                 column_number: self.head_position.column_number + 1,
                 row_number: self.head_position.row_number,
             },
             Direction::Left => Position {
-                column_number: self.head_position.column_number - 1,
+                column_number: self.head_position.column_number.saturating_sub(1),
                 row_number: self.head_position.row_number,
             },
             Direction::Down => Position {
@@ -124,3 +216,183 @@ impl Player {
         }
     }
 }
+
+/// What a snake died of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cause {
+    Wall,
+    OwnTail,
+    /// Ran into the head or tail of the snake with this index
+    Snake(usize),
+    /// Its player left the game
+    Left,
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cause::Wall => write!(f, "hit wall"),
+            Cause::OwnTail => write!(f, "bit own tail"),
+            Cause::Snake(index) => write!(f, "collided with player {}", index + 1),
+            Cause::Left => write!(f, "left the game"),
+        }
+    }
+}
+
+/// How and where a snake died. The position is the cell it ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Death {
+    pub cause: Cause,
+    pub position: Position,
+}
+
+/// A single snake on the board: its head, its tail and how much it has eaten.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snake {
+    pub player: Player,
+    pub tail: Tail,
+    pub score: usize,
+    pub alive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub death: Option<Death>,
+}
+
+impl Snake {
+    /// Creates a snake heading right with a single tail segment behind its head
+    pub fn new(column_number: usize, row_number: usize) -> Self {
+        Self {
+            player: Player::new(column_number, row_number),
+            tail: Tail::new(column_number - 1, row_number),
+            score: 0,
+            alive: true,
+            death: None,
+        }
+    }
+
+    /// Takes the snake out of play, remembering why
+    pub fn die(&mut self, cause: Cause, position: Position) {
+        self.alive = false;
+        self.death = Some(Death { cause, position });
+    }
+
+    /// Every cell of the snake, head first
+    pub fn body(&self) -> impl Iterator<Item = Position> + '_ {
+        std::iter::once(self.player.head_position).chain(self.tail.positions.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player_heading(heading: Direction) -> Player {
+        let mut player = Player::new(5, 5);
+        player.heading = heading;
+        player
+    }
+
+    #[test]
+    fn change_heading_turns_sideways() {
+        for heading in Direction::ALL {
+            for turn in [heading.clockwise(), heading.counter_clockwise()] {
+                let mut player = player_heading(heading);
+                player.change_heading(turn);
+                assert_eq!(player.heading, turn);
+            }
+        }
+    }
+
+    #[test]
+    fn change_heading_ignores_reversing_and_the_current_heading() {
+        for heading in Direction::ALL {
+            for direction in [heading, heading.opposite()] {
+                let mut player = player_heading(heading);
+                player.change_heading(direction);
+                assert_eq!(player.heading, heading);
+            }
+        }
+    }
+
+    #[test]
+    fn relative_steering_turns_from_the_current_heading() {
+        for heading in Direction::ALL {
+            let steer = |pressed| Steering::Relative.heading(heading, pressed);
+            assert_eq!(steer(Direction::Right), Some(heading.clockwise()));
+            assert_eq!(steer(Direction::Left), Some(heading.counter_clockwise()));
+            assert_eq!(steer(Direction::Up), None);
+            assert_eq!(steer(Direction::Down), None);
+            assert_eq!(
+                Steering::Absolute.heading(heading, Direction::Up),
+                Some(Direction::Up)
+            );
+        }
+    }
+
+    #[test]
+    fn calculate_new_position_is_one_cell_ahead() {
+        let expected = [
+            (Direction::Up, Position::new(5, 4)),
+            (Direction::Right, Position::new(6, 5)),
+            (Direction::Down, Position::new(5, 6)),
+            (Direction::Left, Position::new(4, 5)),
+        ];
+        for (heading, position) in expected {
+            let player = player_heading(heading);
+            assert_eq!(player.calculate_new_position(), position);
+            assert_eq!(player.head_position, Position::new(5, 5));
+        }
+    }
+
+    #[test]
+    fn calculate_new_position_agrees_with_neighbour() {
+        for heading in Direction::ALL {
+            let player = player_heading(heading);
+            assert_eq!(
+                Some(player.calculate_new_position()),
+                player.head_position.neighbour(heading)
+            );
+        }
+    }
+
+    #[test]
+    fn calculate_new_position_stops_at_the_top_and_left_edges() {
+        let mut player = Player::new(0, 0);
+        player.change_heading(Direction::Up);
+        assert_eq!(player.calculate_new_position(), Position::new(0, 0));
+        player.change_heading(Direction::Left);
+        assert_eq!(player.calculate_new_position(), Position::new(0, 0));
+    }
+
+    #[test]
+    fn move_player_returns_the_previous_position() {
+        let mut player = Player::new(5, 5);
+        assert_eq!(player.move_player(Position::new(6, 5)), Position::new(5, 5));
+        assert_eq!(player.head_position, Position::new(6, 5));
+    }
+
+    #[test]
+    fn neighbours_stop_at_the_top_and_left_of_the_grid() {
+        let corner = Position::new(0, 0);
+        assert_eq!(corner.neighbour(Direction::Up), None);
+        assert_eq!(corner.neighbour(Direction::Left), None);
+        assert_eq!(corner.neighbour(Direction::Down), Some(Position::new(0, 1)));
+    }
+
+    #[test]
+    fn quarter_turns_undo_each_other() {
+        for direction in Direction::ALL {
+            assert_eq!(direction.clockwise().counter_clockwise(), direction);
+            assert_eq!(direction.clockwise().clockwise(), direction.opposite());
+        }
+    }
+
+    #[test]
+    fn new_snakes_have_one_tail_segment_behind_the_head() {
+        let snake = Snake::new(5, 5);
+        assert_eq!(snake.player.heading, Direction::Right);
+        assert!(snake.tail.check(4, 5));
+        assert_eq!(snake.tail.positions.len(), 1);
+        assert_eq!(snake.score, 0);
+    }
+}
@@ -0,0 +1,397 @@
+//! The people who play on this machine, each with stats kept over every game they have played.
+//! Profiles are kept in a TOML file next to the config, along with which one was picked last.
+
+use crate::achievement::Achievement;
+use crate::game::{Mode, RunStats};
+use crate::player::Cause;
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+
+/// Where the profiles are kept unless told otherwise
+pub const DEFAULT_PROFILES_PATH: &str = "profiles.toml";
+
+/// Who plays until someone makes a profile of their own
+pub const DEFAULT_PROFILE: &str = "guest";
+
+/// The longest name that still fits on the menu of a small board
+pub const MAX_NAME_LENGTH: usize = 12;
+
+/// How games have ended, in the order the stats list them
+const ENDINGS: [&str; 5] = [
+    "hit wall",
+    "bit own tail",
+    "hit a snake",
+    "left",
+    "survived",
+];
+
+/// The entry of `ENDINGS` a game that ended with `cause` is counted under
+fn ending(cause: Option<Cause>) -> &'static str {
+    match cause {
+        Some(Cause::Wall) => ENDINGS[0],
+        Some(Cause::OwnTail) => ENDINGS[1],
+        Some(Cause::Snake(_)) => ENDINGS[2],
+        Some(Cause::Left) => ENDINGS[3],
+        None => ENDINGS[4],
+    }
+}
+
+/// Totals over every game a profile has played
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Lifetime {
+    pub games: u64,
+    /// Food eaten, which is also the total of every score
+    pub food: u64,
+    pub played_ms: u64,
+    /// The best score in each mode, by the mode's name
+    pub best: BTreeMap<String, usize>,
+    /// How many games ended each way, by the names in `ENDINGS`
+    pub endings: BTreeMap<String, u64>,
+}
+
+impl Lifetime {
+    pub fn record(&mut self, mode: Mode, stats: &RunStats) {
+        self.games = self.games.saturating_add(1);
+        self.food = self.food.saturating_add(stats.score as u64);
+        self.played_ms = self
+            .played_ms
+            .saturating_add(stats.played.as_millis() as u64);
+        let best = self.best.entry(mode.name().to_string()).or_default();
+        *best = (*best).max(stats.score);
+        let count = self
+            .endings
+            .entry(ending(stats.cause).to_string())
+            .or_default();
+        *count = count.saturating_add(1);
+    }
+
+    pub fn played(&self) -> Duration {
+        Duration::from_millis(self.played_ms)
+    }
+
+    /// `None` until the first game
+    pub fn average_score(&self) -> Option<f64> {
+        (self.games > 0).then(|| self.food as f64 / self.games as f64)
+    }
+
+    pub fn best(&self, mode: Mode) -> usize {
+        self.best.get(mode.name()).copied().unwrap_or(0)
+    }
+
+    /// Every stat with charts of the best scores and the endings, as lines at most `width`
+    /// columns wide. The bars are drawn with `fill`.
+    pub fn report(&self, fill: char, width: usize) -> Vec<String> {
+        let average = self
+            .average_score()
+            .map_or(String::from("-"), |average| format!("{:.1}", average));
+        let mut lines = vec![
+            format!("GAMES PLAYED: {}", self.games),
+            format!("FOOD EATEN: {}", self.food),
+            format!("PLAY TIME: {}", format_duration(self.played())),
+            format!("AVERAGE SCORE: {}", average),
+            String::new(),
+            String::from("BEST SCORE BY MODE"),
+        ];
+        let best: Vec<(String, u64)> = Mode::ALL
+            .iter()
+            .map(|&mode| (mode.name().to_uppercase(), self.best(mode) as u64))
+            .collect();
+        lines.extend(bar_chart(&best, fill, width));
+        lines.push(String::new());
+        lines.push(String::from("HOW GAMES ENDED"));
+        let endings: Vec<(String, u64)> = ENDINGS
+            .iter()
+            .map(|&ending| {
+                let count = self.endings.get(ending).copied().unwrap_or(0);
+                (ending.to_uppercase(), count)
+            })
+            .collect();
+        lines.extend(bar_chart(&endings, fill, width));
+        lines
+    }
+}
+
+/// Rounded to the largest units that matter, such as "1H 05M" or "42S"
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, seconds) => format!("{}S", seconds),
+        (0, minutes, seconds) => format!("{}M {:02}S", minutes, seconds),
+        (hours, minutes, _) => format!("{}H {:02}M", hours, minutes),
+    }
+}
+
+/// A row per entry: the label, a bar as long as the value allows in `width` columns and the
+/// value. Bars are scaled to the largest value, and every row is padded to the same width so
+/// the chart stays in one piece when centered.
+pub fn bar_chart(entries: &[(String, u64)], fill: char, width: usize) -> Vec<String> {
+    let label_width = entries
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let value_width = entries
+        .iter()
+        .map(|(_, value)| value.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let most = entries.iter().map(|&(_, value)| value).max().unwrap_or(0);
+    let bar_width = width.saturating_sub(label_width + value_width + 2).max(1);
+    entries
+        .iter()
+        .map(|(label, value)| {
+            let length = if most == 0 {
+                0
+            } else {
+                // Rounded up, so anything above zero shows. Worked out in u128 so that no
+                // count a hand-edited file holds can overflow.
+                (u128::from(*value) * bar_width as u128).div_ceil(u128::from(most)) as usize
+            };
+            format!(
+                "{:<label_width$} {:<bar_width$} {:>value_width$}",
+                label,
+                fill.to_string().repeat(length),
+                value,
+            )
+        })
+        .collect()
+}
+
+/// A player, their lifetime stats and the achievements they have unlocked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub stats: Lifetime,
+    #[serde(default)]
+    pub achievements: BTreeSet<Achievement>,
+}
+
+impl Profile {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            stats: Lifetime::default(),
+            achievements: BTreeSet::new(),
+        }
+    }
+
+    /// A line per achievement, marked when it has been unlocked
+    pub fn achievement_lines(&self) -> Vec<String> {
+        Achievement::ALL
+            .iter()
+            .map(|achievement| {
+                let mark = if self.achievements.contains(achievement) {
+                    'X'
+                } else {
+                    ' '
+                };
+                format!("[{}] {}: {}", mark, achievement, achievement.description()).to_uppercase()
+            })
+            .collect()
+    }
+}
+
+/// Everyone's profiles, and the one the next game is played as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profiles {
+    #[serde(default)]
+    selected: usize,
+    profiles: Vec<Profile>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            selected: 0,
+            profiles: vec![Profile::new(DEFAULT_PROFILE)],
+        }
+    }
+}
+
+impl Profiles {
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles
+            .iter()
+            .map(|profile| profile.name.clone())
+            .collect()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn current(&self) -> &Profile {
+        &self.profiles[self.selected]
+    }
+
+    /// Plays the next games as the profile at `index`, if there is one
+    pub fn select(&mut self, index: usize) {
+        if index < self.profiles.len() {
+            self.selected = index;
+        }
+    }
+
+    /// The profile called `name`, whatever its case
+    pub fn find(&self, name: &str) -> Option<&Profile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Adds a profile called `name` and plays as it. Names are trimmed, and have to be short
+    /// and different from the others.
+    pub fn add(&mut self, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(eyre!("names are 1 to {} characters long", MAX_NAME_LENGTH));
+        }
+        if self.find(name).is_some() {
+            return Err(eyre!("{} is already taken", name));
+        }
+        self.profiles.push(Profile::new(name));
+        self.selected = self.profiles.len() - 1;
+        Ok(())
+    }
+
+    /// Adds a finished game to the current profile's stats
+    pub fn record(&mut self, mode: Mode, stats: &RunStats) {
+        self.profiles[self.selected].stats.record(mode, stats);
+    }
+
+    /// Unlocks `achievements` for the current profile. Returns whether any were new to it.
+    pub fn unlock(&mut self, achievements: &[Achievement]) -> bool {
+        let unlocked = &mut self.profiles[self.selected].achievements;
+        let before = unlocked.len();
+        unlocked.extend(achievements);
+        unlocked.len() > before
+    }
+
+    pub fn read_from(text: &str) -> Result<Self> {
+        let profiles: Profiles =
+            toml::from_str(text).map_err(|error| eyre!("invalid profiles: {}", error))?;
+        if profiles.profiles.is_empty() || profiles.selected >= profiles.profiles.len() {
+            return Err(eyre!("invalid profiles: the selected profile is missing"));
+        }
+        Ok(profiles)
+    }
+
+    /// Reads the profiles at `path`, or just the guest's if there is no file there yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::read_from(&text),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(score: usize, cause: Option<Cause>) -> RunStats {
+        RunStats {
+            seed: 0,
+            score,
+            length: score + 2,
+            ticks: 100,
+            turns: 10,
+            longest_combo: 1,
+            played: Duration::from_secs(75),
+            cause,
+        }
+    }
+
+    #[test]
+    fn games_add_up_per_profile_and_survive_a_round_trip() {
+        let mut profiles = Profiles::default();
+        profiles.add(" ada ").unwrap();
+        assert!(profiles.add("ADA").is_err());
+        assert!(profiles.add("").is_err());
+        assert!(profiles.add("a name that is far too long").is_err());
+        profiles.record(Mode::Solo, &game(4, Some(Cause::Wall)));
+        profiles.record(Mode::Solo, &game(2, Some(Cause::Snake(1))));
+        profiles.record(Mode::Versus, &game(7, None));
+        assert!(profiles.unlock(&[Achievement::FirstBite]));
+        assert!(!profiles.unlock(&[Achievement::FirstBite]));
+        profiles.select(0);
+        profiles.record(Mode::Solo, &game(1, Some(Cause::OwnTail)));
+
+        let ada = &profiles.find("ada").unwrap().stats;
+        assert_eq!((ada.games, ada.food), (3, 13));
+        assert_eq!(ada.best(Mode::Solo), 4);
+        assert_eq!(ada.best(Mode::TwoPlayer), 0);
+        assert_eq!(ada.endings["hit a snake"], 1);
+        assert_eq!(format_duration(ada.played()), "3M 45S");
+        assert_eq!(profiles.current().stats.games, 1);
+        assert!(profiles.current().achievements.is_empty());
+        let lines = profiles.find("ada").unwrap().achievement_lines();
+        assert_eq!(lines[0], "[X] FIRST BITE: EAT FOR THE FIRST TIME");
+        assert!(lines[1].starts_with("[ ] HAT TRICK"));
+
+        let text = toml::to_string(&profiles).unwrap();
+        assert_eq!(Profiles::read_from(&text).unwrap(), profiles);
+        assert!(Profiles::read_from("selected = 1\n[[profiles]]\nname = \"ada\"").is_err());
+        assert!(Profiles::read_from("profiles = []").is_err());
+    }
+
+    #[test]
+    fn charts_scale_their_bars_to_the_largest_value() {
+        let entries = [
+            (String::from("WALL"), 10),
+            (String::from("OWN TAIL"), 1),
+            (String::from("LEFT"), 0),
+        ];
+        let chart = bar_chart(&entries, '#', 22);
+        assert_eq!(
+            chart,
+            [
+                "WALL     ########## 10",
+                "OWN TAIL #           1",
+                "LEFT                 0",
+            ]
+        );
+        let mut stats = Lifetime::default();
+        stats.record(Mode::Solo, &game(3, Some(Cause::Wall)));
+        let report = stats.report('#', 30);
+        assert!(report.contains(&String::from("AVERAGE SCORE: 3.0")));
+        assert!(report.iter().all(|line| line.chars().count() <= 30));
+    }
+
+    #[test]
+    fn huge_counts_from_a_hand_edited_file_are_charted_and_added_to() {
+        let text = format!(
+            "[[profiles]]\nname = \"ada\"\n[profiles.stats]\ngames = {max}\n\
+             [profiles.stats.endings]\n\"hit wall\" = {max}\n\"bit own tail\" = 1",
+            max = i64::MAX
+        );
+        let mut profiles = Profiles::read_from(&text).unwrap();
+        profiles.record(Mode::Solo, &game(3, Some(Cause::Wall)));
+        profiles.record(Mode::Solo, &game(3, Some(Cause::Wall)));
+        let stats = &profiles.current().stats;
+        assert_eq!(stats.endings["hit wall"], i64::MAX as u64 + 2);
+        let report = stats.report('#', 40);
+        assert!(report.iter().all(|line| line.chars().count() <= 40));
+        let own_tail = report
+            .iter()
+            .find(|line| line.starts_with("BIT OWN TAIL"))
+            .unwrap();
+        assert_eq!(own_tail.matches('#').count(), 1);
+    }
+}
@@ -0,0 +1,398 @@
+//! The headless engine as a Python extension module, built with the `python` feature.
+//!
+//! `python/build.sh` compiles the module and copies it next to the type stubs in `python/`,
+//! after which `import snake` works from that directory and `python3 -m unittest discover
+//! python/tests` runs the Python tests.
+
+use crate::environment::{Encoding, Environment, EnvironmentConfig, Rewards};
+use crate::game::{GameArea, GameState};
+use crate::player::Direction;
+use crate::replay::{Replay, Turn};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Directions are given as `"up"`, `"right"`, `"down"` and `"left"` (or their first letters), or
+/// as an index into that list
+fn parse_direction(direction: &Bound<'_, PyAny>) -> PyResult<Direction> {
+    if let Ok(index) = direction.extract::<usize>() {
+        return Direction::ALL.get(index).copied().ok_or_else(|| {
+            PyValueError::new_err(format!("{} is not an action from 0 to 3", index))
+        });
+    }
+    let name: String = direction.extract()?;
+    match name.to_lowercase().as_str() {
+        "up" | "u" => Ok(Direction::Up),
+        "right" | "r" => Ok(Direction::Right),
+        "down" | "d" => Ok(Direction::Down),
+        "left" | "l" => Ok(Direction::Left),
+        _ => Err(PyValueError::new_err(format!(
+            "{:?} is not a direction",
+            name
+        ))),
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Right => "right",
+        Direction::Down => "down",
+        Direction::Left => "left",
+    }
+}
+
+fn parse_encoding(encoding: &str, radius: usize) -> PyResult<Encoding> {
+    let encoding = match encoding {
+        "grid" => Encoding::Grid,
+        "egocentric" => Encoding::Egocentric { radius },
+        "features" => Encoding::Features,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown encoding {:?}, expected grid, egocentric or features",
+                encoding
+            )))
+        }
+    };
+    encoding.check().map_err(value_error)?;
+    Ok(encoding)
+}
+
+fn check_size(width: usize, height: usize) -> PyResult<()> {
+    GameArea::check(width, height).map_err(value_error)
+}
+
+fn value_error(error: color_eyre::Report) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+/// A game that records every turn, so it can be saved as a replay
+#[pyclass(name = "Game", module = "snake")]
+pub struct PyGame {
+    game_state: GameState,
+    replay: Replay,
+    turns: Vec<Turn>,
+}
+
+impl PyGame {
+    fn from_state(game_state: GameState, names: Vec<String>) -> Self {
+        Self {
+            replay: Replay::new(&game_state, names),
+            game_state,
+            turns: Vec::new(),
+        }
+    }
+
+    fn check_player(&self, player: usize) -> PyResult<()> {
+        if player >= self.game_state.snakes().len() {
+            return Err(PyIndexError::new_err(format!(
+                "there is no player {}",
+                player
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    #[pyo3(signature = (width = 20, height = 20, players = 1, seed = None))]
+    fn new(width: usize, height: usize, players: usize, seed: Option<u64>) -> PyResult<Self> {
+        check_size(width, height)?;
+        GameArea::check_players(height, players).map_err(value_error)?;
+        let seed = seed.unwrap_or_else(rand::random);
+        let names = (1..=players)
+            .map(|index| format!("player {}", index))
+            .collect();
+        Ok(Self::from_state(
+            GameState::seeded(width, height, players, seed),
+            names,
+        ))
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.game_state.game_area.width
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.game_state.game_area.height
+    }
+
+    #[getter]
+    fn seed(&self) -> u64 {
+        self.game_state.seed()
+    }
+
+    /// Position of the food nearest the first snake's head as (column, row)
+    #[getter]
+    fn food(&self) -> (usize, usize) {
+        let head = self.game_state.snakes()[0].player.head_position;
+        let food = self.game_state.nearest_food(head);
+        (food.column_number, food.row_number)
+    }
+
+    /// Position of every food as (column, row)
+    #[getter]
+    fn foods(&self) -> Vec<(usize, usize)> {
+        self.game_state
+            .powerup_positions()
+            .map(|food| (food.column_number, food.row_number))
+            .collect()
+    }
+
+    #[getter]
+    fn ticks(&self) -> usize {
+        self.replay.ticks.len()
+    }
+
+    fn is_over(&self) -> bool {
+        self.game_state.is_over()
+    }
+
+    #[pyo3(signature = (player = 0))]
+    fn score(&self, player: usize) -> PyResult<usize> {
+        self.check_player(player)?;
+        Ok(self.game_state.snakes()[player].score)
+    }
+
+    /// Every snake as a dict of `head`, `heading`, `body`, `score` and `alive`
+    fn snakes<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.game_state
+            .snakes()
+            .iter()
+            .map(|snake| {
+                let dict = PyDict::new(py);
+                let head = snake.player.head_position;
+                dict.set_item("head", (head.column_number, head.row_number))?;
+                dict.set_item("heading", direction_name(snake.player.heading))?;
+                let body: Vec<(usize, usize)> = snake
+                    .tail
+                    .positions
+                    .iter()
+                    .map(|position| (position.column_number, position.row_number))
+                    .collect();
+                dict.set_item("body", body)?;
+                dict.set_item("score", snake.score)?;
+                dict.set_item("alive", snake.alive)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Turns a snake before the next tick; reversing is ignored
+    fn steer(&mut self, player: usize, direction: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.check_player(player)?;
+        let direction = parse_direction(direction)?;
+        self.game_state.steer(player, direction);
+        self.turns.push(Turn {
+            player_index: player,
+            direction,
+        });
+        Ok(())
+    }
+
+    /// Advances the game by one tick, returning whether it is still running
+    fn tick(&mut self) -> bool {
+        if self.game_state.is_over() {
+            return false;
+        }
+        self.replay.record_tick(std::mem::take(&mut self.turns));
+        self.game_state.tick().is_some()
+    }
+
+    /// The first snake's view of the board as a flat list; see `observation_shape`
+    #[pyo3(signature = (encoding = "features", radius = 5))]
+    fn observation(&self, encoding: &str, radius: usize) -> PyResult<Vec<f32>> {
+        let encoding = parse_encoding(encoding, radius)?;
+        let (width, height) = (self.width(), self.height());
+        let mut buffer = vec![0.0; encoding.len(width, height)];
+        crate::environment::encode(&self.game_state, encoding, &mut buffer);
+        Ok(buffer)
+    }
+
+    #[pyo3(signature = (encoding = "features", radius = 5))]
+    fn observation_shape(&self, encoding: &str, radius: usize) -> PyResult<Vec<usize>> {
+        Ok(parse_encoding(encoding, radius)?.shape(self.width(), self.height()))
+    }
+
+    /// Saves the ticks played so far, which `snake replay` can play back
+    fn save_replay(&self, path: &str) -> PyResult<()> {
+        self.replay
+            .save(path)
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+}
+
+#[pyclass(name = "Replay", module = "snake")]
+pub struct PyReplay {
+    replay: Replay,
+}
+
+#[pymethods]
+impl PyReplay {
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let replay =
+            Replay::load(path).map_err(|error| PyValueError::new_err(error.to_string()))?;
+        Ok(Self { replay })
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.replay
+            .save(path)
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    #[getter]
+    fn seed(&self) -> u64 {
+        self.replay.header.seed
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.replay.header.width
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.replay.header.height
+    }
+
+    #[getter]
+    fn names(&self) -> Vec<String> {
+        self.replay.header.names.clone()
+    }
+
+    #[getter]
+    fn ticks(&self) -> usize {
+        self.replay.ticks.len()
+    }
+
+    /// The recorded turns, one list of (player, direction) pairs per tick
+    fn turns(&self) -> Vec<Vec<(usize, &'static str)>> {
+        self.replay
+            .ticks
+            .iter()
+            .map(|turns| {
+                turns
+                    .iter()
+                    .map(|turn| (turn.player_index, direction_name(turn.direction)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Plays the recording back as a game, stopping after `ticks` ticks if given
+    #[pyo3(signature = (ticks = None))]
+    fn play(&self, ticks: Option<usize>) -> PyGame {
+        let mut game = PyGame::from_state(
+            self.replay.initial_state(),
+            self.replay.header.names.clone(),
+        );
+        let ticks = ticks.unwrap_or(self.replay.ticks.len());
+        for turns in self.replay.ticks.iter().take(ticks) {
+            for turn in turns {
+                game.game_state.steer(turn.player_index, turn.direction);
+            }
+            game.replay.record_tick(turns.clone());
+            if game.game_state.tick().is_none() {
+                break;
+            }
+        }
+        game
+    }
+}
+
+/// The gym-style training environment, see `src/environment.rs`
+#[pyclass(name = "Env", module = "snake")]
+pub struct PyEnv {
+    environment: Environment,
+}
+
+#[pymethods]
+impl PyEnv {
+    #[new]
+    #[pyo3(signature = (
+        width = 20,
+        height = 20,
+        encoding = "features",
+        radius = 5,
+        max_steps = Some(10_000),
+        food_reward = 1.0,
+        death_reward = -1.0,
+        step_reward = 0.0,
+        approach_reward = 0.0,
+        clear_reward = 10.0,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        width: usize,
+        height: usize,
+        encoding: &str,
+        radius: usize,
+        max_steps: Option<u64>,
+        food_reward: f32,
+        death_reward: f32,
+        step_reward: f32,
+        approach_reward: f32,
+        clear_reward: f32,
+    ) -> PyResult<Self> {
+        check_size(width, height)?;
+        let config = EnvironmentConfig {
+            width,
+            height,
+            encoding: parse_encoding(encoding, radius)?,
+            rewards: Rewards {
+                food: food_reward,
+                death: death_reward,
+                step: step_reward,
+                approach: approach_reward,
+                clear: clear_reward,
+            },
+            max_steps,
+        };
+        let environment = Environment::new(config).map_err(value_error)?;
+        Ok(Self { environment })
+    }
+
+    #[getter]
+    fn observation_shape(&self) -> Vec<usize> {
+        let config = self.environment.config();
+        config.encoding.shape(config.width, config.height)
+    }
+
+    #[pyo3(signature = (seed = None))]
+    fn reset(&mut self, seed: Option<u64>) -> Vec<f32> {
+        self.environment
+            .reset(seed.unwrap_or_else(rand::random))
+            .data
+    }
+
+    /// Returns `(observation, reward, done, info)`
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        action: &Bound<'_, PyAny>,
+    ) -> PyResult<(Vec<f32>, f32, bool, Bound<'py, PyDict>)> {
+        let (observation, reward, done, info) = self.environment.step(parse_direction(action)?);
+        let dict = PyDict::new(py);
+        dict.set_item("score", info.score)?;
+        dict.set_item("length", info.length)?;
+        dict.set_item("steps", info.steps)?;
+        dict.set_item("truncated", info.truncated)?;
+        Ok((observation.data, reward, done, dict))
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "snake")]
+fn snake_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    module.add_class::<PyReplay>()?;
+    module.add_class::<PyEnv>()?;
+    module.add("DIRECTIONS", ["up", "right", "down", "left"])?;
+    Ok(())
+}
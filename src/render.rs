@@ -1,35 +1,130 @@
-use std::fmt::Display;
+use std::fmt::{self, Display};
 
 use crate::game::{GameBoard, GameCell, Wall};
+use crate::player::Direction;
+use serde::{Deserialize, Serialize};
 
-impl Display for GameBoard {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let output: String = self.0.rows_iter().fold(String::new(), |mut acc, row| {
-            row.for_each(|tile| {
-                let tile_char = match tile {
-                    GameCell::Tail => 'O',
-                    GameCell::Powerup => '*',
-                    GameCell::Empty => ' ',
-                    GameCell::Head(head_direction) => match head_direction {
-                        crate::player::Direction::Up => 'Ʌ',
-                        crate::player::Direction::Right => '>',
-                        crate::player::Direction::Down => 'V',
-                        crate::player::Direction::Left => '<',
-                    },
-                    GameCell::Edge(wall) => match wall {
-                        Wall::Horizontal => '═',
-                        Wall::Vertical => '║',
-                        Wall::TopLeft => '╔',
-                        Wall::TopRight => '╗',
-                        Wall::BottomLeft => '╚',
-                        Wall::BottomRight => '╝',
-                    },
+/// Tail characters for each snake, so players can tell their snakes apart
+pub const TAIL_CHARACTERS: [char; 6] = ['O', '@', '#', '%', '&', '$'];
+const BLOCK_TAIL_CHARACTERS: [char; 6] = ['▓', '▒', '░', '█', '▚', '▞'];
+
+/// The sets of characters the board can be drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Classic,
+    /// Plain ASCII, for terminals without box drawing characters
+    Ascii,
+    Blocks,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Ascii, Theme::Blocks];
+
+    /// The character the big letters of titles are drawn with
+    pub fn fill(self) -> char {
+        match self {
+            Theme::Ascii => '#',
+            Theme::Classic | Theme::Blocks => '█',
+        }
+    }
+
+    /// The character for `cell`. Open walls, which snakes pass through, are drawn dashed.
+    pub fn glyph(self, cell: GameCell, open_walls: bool) -> char {
+        match (self, cell) {
+            (_, GameCell::Empty) => ' ',
+            (Theme::Blocks, GameCell::Tail(index)) => {
+                BLOCK_TAIL_CHARACTERS[index % BLOCK_TAIL_CHARACTERS.len()]
+            }
+            (_, GameCell::Tail(index)) => TAIL_CHARACTERS[index % TAIL_CHARACTERS.len()],
+            (Theme::Blocks, GameCell::Powerup) => '●',
+            (_, GameCell::Powerup) => '*',
+            (theme, GameCell::Head(_, heading)) => {
+                let heads = match theme {
+                    Theme::Classic => ['Ʌ', '>', 'V', '<'],
+                    Theme::Ascii => ['^', '>', 'v', '<'],
+                    Theme::Blocks => ['▲', '▶', '▼', '◀'],
+                };
+                match heading {
+                    Direction::Up => heads[0],
+                    Direction::Right => heads[1],
+                    Direction::Down => heads[2],
+                    Direction::Left => heads[3],
+                }
+            }
+            (theme, GameCell::Edge(wall)) => {
+                // Horizontal, vertical, then the corners clockwise from the top left
+                let walls = match (theme, open_walls) {
+                    (Theme::Classic, false) => ['═', '║', '╔', '╗', '╝', '╚'],
+                    (Theme::Classic, true) => ['┄', '┆', '┌', '┐', '┘', '└'],
+                    (Theme::Ascii, false) => ['-', '|', '+', '+', '+', '+'],
+                    (Theme::Ascii, true) => ['.', ':', '.', '.', '.', '.'],
+                    (Theme::Blocks, false) => ['█'; 6],
+                    (Theme::Blocks, true) => ['░'; 6],
                 };
-                acc.push(tile_char);
-            });
+                match wall {
+                    Wall::Horizontal => walls[0],
+                    Wall::Vertical => walls[1],
+                    Wall::TopLeft => walls[2],
+                    Wall::TopRight => walls[3],
+                    Wall::BottomRight => walls[4],
+                    Wall::BottomLeft => walls[5],
+                }
+            }
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Theme::Classic => "classic",
+            Theme::Ascii => "ascii",
+            Theme::Blocks => "blocks",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl GameBoard {
+    /// Draws the board one row per line, each ending in `"\n\r"`
+    pub fn render(&self, theme: Theme, open_walls: bool) -> String {
+        self.0.rows_iter().fold(String::new(), |mut acc, row| {
+            row.for_each(|tile| acc.push(theme.glyph(*tile, open_walls)));
             acc.push_str("\n\r");
             acc
-        });
-        write!(f, "{}", output)
+        })
+    }
+}
+
+impl Display for GameBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(Theme::Classic, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn every_theme_draws_every_cell_as_one_character() {
+        let game_state = GameState::seeded(8, 5, 2, 3);
+        let board = GameBoard::try_from(&game_state).unwrap();
+        for theme in Theme::ALL {
+            for open_walls in [false, true] {
+                let rows: Vec<String> = board
+                    .render(theme, open_walls)
+                    .split_terminator("\n\r")
+                    .map(String::from)
+                    .collect();
+                assert_eq!(rows.len(), 5);
+                assert!(rows.iter().all(|row| row.chars().count() == 8));
+            }
+        }
+        assert_eq!(board.render(Theme::Classic, false), board.to_string());
+        assert!(board.render(Theme::Ascii, false).is_ascii());
     }
 }
@@ -0,0 +1,284 @@
+//! Recorded games. A replay stores the seed and every turn made, which is enough to play the
+//! whole game back tick for tick because the simulation is deterministic.
+
+use crate::game::{GameArea, GameState, Rules};
+use crate::player::{Direction, Steering};
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Version 2 added the rules. Version 1 replays are still read, and were all played by the
+/// classic ones; builds that only know version 1 refuse version 2 instead of misplaying it.
+pub const REPLAY_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    pub names: Vec<String>,
+    /// How each player steered, in the order of `names`. Players past the end of the list, and
+    /// everyone in replays from before it was recorded, steered absolutely.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steering: Vec<Steering>,
+    /// Replays from before the rules were recorded were all played by the classic ones
+    #[serde(default, skip_serializing_if = "Rules::is_classic")]
+    pub rules: Rules,
+}
+
+impl ReplayHeader {
+    pub fn steering(&self, player_index: usize) -> Steering {
+        self.steering.get(player_index).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Turn {
+    pub player_index: usize,
+    pub direction: Direction,
+}
+
+/// A replay is stored as JSON lines: the header first, then one array of turns per tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    /// The turns applied before each tick, in the order they were made
+    pub ticks: Vec<Vec<Turn>>,
+}
+
+impl Replay {
+    /// Starts an empty recording of a freshly created game
+    pub fn new(game_state: &GameState, names: Vec<String>) -> Self {
+        Self {
+            header: ReplayHeader {
+                version: REPLAY_VERSION,
+                seed: game_state.seed(),
+                width: game_state.game_area.width,
+                height: game_state.game_area.height,
+                names,
+                steering: Vec::new(),
+                rules: game_state.rules(),
+            },
+            ticks: Vec::new(),
+        }
+    }
+
+    /// Records how each player steers. Turns are always stored as the heading they led to, so
+    /// playback doesn't depend on it.
+    pub fn with_steering(mut self, steering: Vec<Steering>) -> Self {
+        self.header.steering = steering;
+        self
+    }
+
+    pub fn record_tick(&mut self, turns: Vec<Turn>) {
+        self.ticks.push(turns);
+    }
+
+    /// The game as it was before the first tick
+    pub fn initial_state(&self) -> GameState {
+        GameState::seeded(
+            self.header.width,
+            self.header.height,
+            self.header.names.len(),
+            self.header.seed,
+        )
+        .with_rules(self.header.rules)
+    }
+
+    /// Plays the recording back, yielding the state after every tick
+    pub fn states(&self) -> impl Iterator<Item = GameState> + '_ {
+        let mut game_state = self.initial_state();
+        let mut running = true;
+        self.ticks.iter().map_while(move |turns| {
+            if !running {
+                return None;
+            }
+            for turn in turns {
+                game_state.steer(turn.player_index, turn.direction);
+            }
+            running = game_state.tick().is_some();
+            Some(game_state.clone())
+        })
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        serde_json::to_writer(&mut *writer, &self.header)?;
+        writeln!(writer)?;
+        for turns in &self.ticks {
+            serde_json::to_writer(&mut *writer, turns)?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn read_from(reader: impl BufRead) -> Result<Self> {
+        let mut lines = reader.lines();
+        let header_line = lines.next().ok_or_else(|| eyre!("the replay is empty"))??;
+        let header: ReplayHeader = serde_json::from_str(&header_line)
+            .map_err(|error| eyre!("invalid replay header: {}", error))?;
+        if !(1..=REPLAY_VERSION).contains(&header.version) {
+            return Err(eyre!(
+                "unsupported replay version {} (expected 1 to {})",
+                header.version,
+                REPLAY_VERSION
+            ));
+        }
+        if header.names.is_empty() || header.steering.len() > header.names.len() {
+            return Err(eyre!("the replay header describes an unplayable game"));
+        }
+        GameArea::check(header.width, header.height)
+            .and_then(|()| GameArea::check_players(header.height, header.names.len()))
+            .map_err(|error| eyre!("the replay header describes an unplayable game: {}", error))?;
+        let ticks = lines
+            .enumerate()
+            .map(|(index, line)| {
+                let turns: Vec<Turn> = serde_json::from_str(&line?)
+                    .map_err(|error| eyre!("invalid replay tick {}: {}", index + 1, error))?;
+                Ok(turns)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { header, ticks })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded_game() -> (Replay, Vec<GameState>) {
+        let mut game_state = GameState::seeded(30, 15, 1, 7);
+        let mut replay = Replay::new(&game_state, vec![String::from("ada")]);
+        let mut states = Vec::new();
+        let script = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ];
+        for step in 0.. {
+            let mut turns = Vec::new();
+            if step % 3 == 0 {
+                let direction = script[(step / 3) % script.len()];
+                game_state.steer(0, direction);
+                turns.push(Turn {
+                    player_index: 0,
+                    direction,
+                });
+            }
+            replay.record_tick(turns);
+            let running = game_state.tick().is_some();
+            states.push(game_state.clone());
+            if !running || step > 200 {
+                break;
+            }
+        }
+        (replay, states)
+    }
+
+    #[test]
+    fn playback_reproduces_the_recorded_game() {
+        let (replay, states) = recorded_game();
+        let played_back: Vec<GameState> = replay.states().collect();
+        assert_eq!(played_back.len(), states.len());
+        for (recorded, replayed) in states.iter().zip(&played_back) {
+            assert_eq!(
+                recorded.snakes()[0].player.head_position,
+                replayed.snakes()[0].player.head_position
+            );
+            assert_eq!(recorded.powerup_position(), replayed.powerup_position());
+        }
+    }
+
+    #[test]
+    fn replays_survive_a_round_trip_through_text() {
+        let (replay, _) = recorded_game();
+        let mut buffer = Vec::new();
+        replay.write_to(&mut buffer).unwrap();
+        let loaded = Replay::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(loaded, replay);
+    }
+
+    #[test]
+    fn steering_is_recorded_per_player() {
+        let game_state = GameState::seeded(30, 15, 2, 7);
+        let names = vec![String::from("ada"), String::from("grace")];
+        let replay = Replay::new(&game_state, names).with_steering(vec![Steering::Relative]);
+        let mut buffer = Vec::new();
+        replay.write_to(&mut buffer).unwrap();
+        let loaded = Replay::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(loaded.header.steering(0), Steering::Relative);
+        assert_eq!(loaded.header.steering(1), Steering::Absolute);
+
+        // Replays from before steering was recorded are all absolute
+        let text = r#"{"version":1,"seed":1,"width":20,"height":20,"names":["ada"]}"#;
+        let old = Replay::read_from(text.as_bytes()).unwrap();
+        assert_eq!(old.header.steering(0), Steering::Absolute);
+        let text = r#"{"version":1,"seed":1,"width":20,"height":20,"names":["ada"],"steering":["relative","relative"]}"#;
+        assert!(Replay::read_from(text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn version_1_replays_are_played_by_the_classic_rules() {
+        let game_state = GameState::seeded(20, 20, 1, 1).with_rules(Rules {
+            wrap: true,
+            food: 3,
+        });
+        let mut buffer = Vec::new();
+        Replay::new(&game_state, vec![String::from("ada")])
+            .write_to(&mut buffer)
+            .unwrap();
+        assert!(buffer.starts_with(br#"{"version":2,"#));
+        assert_eq!(
+            Replay::read_from(buffer.as_slice()).unwrap().header.rules,
+            game_state.rules()
+        );
+
+        let text = "{\"version\":1,\"seed\":1,\"width\":20,\"height\":20,\"names\":[\"ada\"]}\n[]";
+        let old = Replay::read_from(text.as_bytes()).unwrap();
+        assert!(old.header.rules.is_classic());
+        assert_eq!(old.states().count(), 1);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let text = r#"{"version":99,"seed":1,"width":20,"height":20,"names":["ada"]}"#;
+        let error = Replay::read_from(text.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("version 99"));
+    }
+
+    #[test]
+    fn boards_too_small_or_too_large_are_rejected() {
+        for (width, height) in [(3, 20), (20, 2), (20, usize::MAX / 2)] {
+            let text = format!(
+                r#"{{"version":1,"seed":1,"width":{},"height":{},"names":["ada"]}}"#,
+                width, height
+            );
+            let error = Replay::read_from(text.as_bytes()).unwrap_err();
+            assert!(error.to_string().contains("unplayable"));
+        }
+    }
+
+    #[test]
+    fn more_players_than_the_board_has_room_for_are_rejected() {
+        let names = [r#""ada""#; 5].join(",");
+        let text = format!(
+            r#"{{"version":1,"seed":1,"width":20,"height":6,"names":[{}]}}"#,
+            names
+        );
+        let error = Replay::read_from(text.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("at most 4 players"));
+    }
+}
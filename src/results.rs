@@ -0,0 +1,238 @@
+//! The screen after a local game: how it went, where it placed, and what to do next. It is
+//! drawn in the same box frame as the welcome screen.
+
+use crate::backend::{Backend, Key};
+use crate::config::Config;
+use crate::game::RunStats;
+use crate::keymap::Action;
+use crate::pages::draw;
+use crate::render::Theme;
+use crate::replay::Replay;
+use crate::welcome::{banner_rows, framed_rows, get_inner_row};
+use color_eyre::eyre::Result;
+use std::path::Path;
+
+/// What the player picked after a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Next {
+    /// The same game again, from the same seed
+    Retry,
+    /// A game of the same kind with the next seed, from the restart key
+    NewGame,
+    Menu,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    SaveReplay,
+    Retry,
+    Menu,
+}
+
+/// A finished game and where it placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Results {
+    pub stats: RunStats,
+    /// The best score before this game
+    pub previous_best: usize,
+    /// The game's place on the leaderboard, if it made it
+    pub rank: Option<usize>,
+}
+
+impl Results {
+    fn title(&self) -> &'static str {
+        if self.stats.score > self.previous_best {
+            "NEW HIGH SCORE"
+        } else {
+            "GAME OVER"
+        }
+    }
+
+    /// The stats, most important first, as the last ones are left out when the board is small
+    fn lines(&self) -> Vec<String> {
+        let stats = &self.stats;
+        let best = if stats.score > self.previous_best {
+            format!("PREVIOUS BEST: {}", self.previous_best)
+        } else {
+            format!("HIGH SCORE: {}", self.previous_best)
+        };
+        let rank = self
+            .rank
+            .map_or(String::from("NOT ON THE LEADERBOARD"), |rank| {
+                format!("LEADERBOARD RANK: {}", rank)
+            });
+        let ticks_per_food = stats
+            .ticks_per_food()
+            .map_or(String::from("-"), |ticks| format!("{:.1}", ticks));
+        vec![
+            format!("SCORE: {}", stats.score),
+            best,
+            stats.ending().to_uppercase(),
+            rank,
+            format!("LENGTH: {}", stats.length),
+            format!("DURATION: {:.1}S", stats.played.as_secs_f64()),
+            format!("TICKS SURVIVED: {}", stats.ticks),
+            format!("FOOD EATEN: {}", stats.score),
+            format!("LONGEST COMBO: {}", stats.longest_combo),
+            format!("TURNS MADE: {}", stats.turns),
+            format!("TICKS PER FOOD: {}", ticks_per_food),
+        ]
+    }
+
+    /// The title, as many stats as fit and the `options` under them, inside the box frame
+    fn rows(
+        &self,
+        options: &[String],
+        selected: usize,
+        theme: Theme,
+        width: usize,
+        height: usize,
+    ) -> Vec<String> {
+        let inner_rows = height - 2;
+        // The options always show, under a blank row
+        let room = inner_rows.saturating_sub(options.len() + 1);
+        let mut content: Vec<String> = self
+            .lines()
+            .iter()
+            .take(room)
+            .map(|line| get_inner_row(Some(line), width))
+            .collect();
+        content.push(get_inner_row(None, width));
+        content.extend(options.iter().enumerate().map(|(index, option)| {
+            if index == selected {
+                get_inner_row(Some(&format!("> {} <", option)), width)
+            } else {
+                get_inner_row(Some(option), width)
+            }
+        }));
+        // The title and a blank row go above, in big letters if there's room
+        let spare_rows = inner_rows.saturating_sub(content.len());
+        if spare_rows >= 2 {
+            let mut title = banner_rows(self.title(), theme, width, spare_rows - 1);
+            title.push(get_inner_row(None, width));
+            content.splice(0..0, title);
+        }
+        framed_rows(content, width, height)
+    }
+}
+
+/// Shows `results` until the player picks what to do next. `replay`, the recording of the whole
+/// game if there is one, can be saved to `replay_dir` from here.
+pub fn show_results(
+    terminal: &mut impl Backend,
+    config: &Config,
+    results: &Results,
+    replay: Option<&Replay>,
+    replay_dir: &Path,
+) -> Result<Next> {
+    let mut items = vec![Item::Retry, Item::Menu];
+    if replay.is_some() {
+        items.insert(0, Item::SaveReplay);
+    }
+    // Space carries on to the menu, as it always has
+    let mut selected = items.len() - 1;
+    let mut saved_as = None;
+    loop {
+        let options: Vec<String> = items
+            .iter()
+            .map(|item| match (item, &saved_as) {
+                (Item::SaveReplay, None) => String::from("SAVE REPLAY"),
+                (Item::SaveReplay, Some(name)) => format!("SAVED AS {}", name),
+                (Item::Retry, _) => String::from("RETRY SAME SEED"),
+                (Item::Menu, _) => String::from("MAIN MENU"),
+            })
+            .collect();
+        let rows = results.rows(
+            &options,
+            selected,
+            config.theme,
+            config.width,
+            config.height,
+        );
+        draw(terminal, &rows)?;
+        let key = terminal.read_key()?;
+        match config.keys.action(key) {
+            Some(Action::Quit) => return Ok(Next::Quit),
+            Some(Action::Restart) => return Ok(Next::NewGame),
+            _ => (),
+        }
+        match key {
+            Key::Esc => return Ok(Next::Menu),
+            Key::Up | Key::Char('k') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => selected = (selected + 1).min(items.len() - 1),
+            Key::Char('\n') | Key::Char(' ') => match items[selected] {
+                Item::SaveReplay => {
+                    if let (Some(replay), None) = (replay, &saved_as) {
+                        let name = format!("snake-{}.replay", results.stats.seed);
+                        replay.save(replay_dir.join(&name))?;
+                        saved_as = Some(name);
+                    }
+                }
+                Item::Retry => return Ok(Next::Retry),
+                Item::Menu => return Ok(Next::Menu),
+            },
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Cause;
+    use std::time::Duration;
+
+    fn results() -> Results {
+        Results {
+            stats: RunStats {
+                seed: 7,
+                score: 4,
+                length: 6,
+                ticks: 90,
+                turns: 12,
+                longest_combo: 2,
+                played: Duration::from_millis(6750),
+                cause: Some(Cause::OwnTail),
+            },
+            previous_best: 3,
+            rank: Some(1),
+        }
+    }
+
+    #[test]
+    fn every_stat_shows_on_a_big_board() {
+        let options = [String::from("RETRY SAME SEED"), String::from("MAIN MENU")];
+        let rows = results().rows(&options, 1, Theme::Ascii, 80, 25);
+        let screen = rows.join("\n");
+        for line in [
+            "SCORE: 4",
+            "PREVIOUS BEST: 3",
+            "BIT OWN TAIL",
+            "LEADERBOARD RANK: 1",
+            "DURATION: 6.8S",
+            "TICKS PER FOOD: 22.5",
+            "> MAIN MENU <",
+        ] {
+            assert!(
+                screen.contains(line),
+                "{} is missing from\n{}",
+                line,
+                screen
+            );
+        }
+        // The title is in big letters
+        assert!(screen.contains("#  # ### #   #"));
+        assert!(!screen.contains("NEW HIGH SCORE"));
+    }
+
+    #[test]
+    fn small_boards_keep_the_options_and_drop_the_last_stats() {
+        let options = [String::from("RETRY SAME SEED"), String::from("MAIN MENU")];
+        let rows = results().rows(&options, 0, Theme::Classic, 30, 12);
+        assert_eq!(rows.len(), 12);
+        assert!(rows[1].contains("SCORE: 4"));
+        assert!(rows[9].contains("> RETRY SAME SEED <"));
+        assert!(!rows.iter().any(|row| row.contains("TURNS MADE")));
+    }
+}
@@ -0,0 +1,205 @@
+//! Saved games. A save holds the whole `GameState`, random number generator included, so a
+//! resumed game carries on exactly where it stopped.
+
+use crate::achievement::Tracker;
+use crate::ai::Difficulty;
+use crate::game::{GameState, Tally};
+use crate::keymap::MAX_LOCAL_PLAYERS;
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+pub const SAVE_VERSION: u32 = 2;
+
+/// Where the game is saved unless told otherwise
+pub const DEFAULT_SAVE_PATH: &str = "snake.save";
+
+/// A save is a single JSON object with the version next to the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u32,
+    pub game_state: GameState,
+    /// The computer snake playing against the player, if there is one
+    #[serde(default)]
+    pub opponent: Option<Difficulty>,
+    /// How many people were playing at the keyboard, on the first snakes
+    #[serde(default = "one_player")]
+    pub players: usize,
+    /// The first player's stats so far
+    #[serde(default)]
+    pub tally: Tally,
+    /// The first player's achievements so far
+    #[serde(default)]
+    pub achievements: Tracker,
+}
+
+fn one_player() -> usize {
+    1
+}
+
+/// Just enough of a save to tell which version wrote it, whatever else it holds
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+impl SavedGame {
+    pub fn new(game_state: &GameState, opponent: Option<Difficulty>) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            game_state: game_state.clone(),
+            opponent,
+            players: 1,
+            tally: Tally::default(),
+            achievements: Tracker::default(),
+        }
+    }
+
+    pub fn with_players(mut self, players: usize) -> Self {
+        self.players = players;
+        self
+    }
+
+    pub fn with_tally(mut self, tally: Tally) -> Self {
+        self.tally = tally;
+        self
+    }
+
+    pub fn with_achievements(mut self, achievements: Tracker) -> Self {
+        self.achievements = achievements;
+        self
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        serde_json::to_writer(&mut *writer, self)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn read_from(mut reader: impl Read) -> Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let SaveVersion { version } =
+            serde_json::from_str(&text).map_err(|error| eyre!("invalid saved game: {}", error))?;
+        if version != SAVE_VERSION {
+            return Err(eyre!(
+                "unsupported saved game version {} (expected {})",
+                version,
+                SAVE_VERSION
+            ));
+        }
+        let saved_game: SavedGame =
+            serde_json::from_str(&text).map_err(|error| eyre!("invalid saved game: {}", error))?;
+        saved_game
+            .game_state
+            .check()
+            .map_err(|error| eyre!("the saved game is unplayable: {}", error))?;
+        let snakes = saved_game.players + usize::from(saved_game.opponent.is_some());
+        if !(1..=MAX_LOCAL_PLAYERS).contains(&saved_game.players)
+            || saved_game.game_state.snakes().len() != snakes
+        {
+            return Err(eyre!(
+                "the saved game is unplayable: the number of snakes doesn't match the players"
+            ));
+        }
+        Ok(saved_game)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Direction;
+
+    fn game_in_progress() -> GameState {
+        let mut game_state = GameState::seeded(30, 15, 1, 7);
+        for direction in [Direction::Up, Direction::Left, Direction::Down] {
+            game_state.steer(0, direction);
+            for _ in 0..4 {
+                game_state.tick();
+            }
+        }
+        game_state
+    }
+
+    #[test]
+    fn resumed_games_play_on_exactly_like_the_original() {
+        let mut original = game_in_progress();
+        let mut buffer = Vec::new();
+        SavedGame::new(&original, None)
+            .write_to(&mut buffer)
+            .unwrap();
+        let mut resumed = SavedGame::read_from(buffer.as_slice()).unwrap().game_state;
+        for _ in 0..100 {
+            original.steer(0, Direction::Right);
+            resumed.steer(0, Direction::Right);
+            assert_eq!(original.tick(), resumed.tick());
+            assert_eq!(
+                original.snakes()[0].player.head_position,
+                resumed.snakes()[0].player.head_position
+            );
+            assert_eq!(original.snakes()[0].score, resumed.snakes()[0].score);
+            assert_eq!(original.powerup_position(), resumed.powerup_position());
+        }
+    }
+
+    #[test]
+    fn other_versions_are_rejected_before_anything_else_is_read() {
+        let error =
+            SavedGame::read_from(r#"{"version":99,"game_state":"from the future"}"#.as_bytes())
+                .unwrap_err();
+        assert!(error.to_string().contains("version 99"));
+        let error = SavedGame::read_from("not a save".as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("invalid saved game"));
+    }
+
+    #[test]
+    fn saves_with_pieces_off_the_board_are_rejected() {
+        let mut buffer = Vec::new();
+        SavedGame::new(&game_in_progress(), None)
+            .write_to(&mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let shrunk = text.replacen(r#""width":30"#, r#""width":6"#, 1);
+        assert_ne!(shrunk, text);
+        let error = SavedGame::read_from(shrunk.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("unplayable"));
+    }
+
+    #[test]
+    fn opponents_are_saved_along_with_their_snakes() {
+        let game_state = GameState::seeded(30, 15, 2, 7);
+        let mut buffer = Vec::new();
+        SavedGame::new(&game_state, Some(Difficulty::Hard))
+            .write_to(&mut buffer)
+            .unwrap();
+        let loaded = SavedGame::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(loaded.opponent, Some(Difficulty::Hard));
+
+        buffer.clear();
+        SavedGame::new(&game_state, None)
+            .write_to(&mut buffer)
+            .unwrap();
+        let error = SavedGame::read_from(buffer.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("number of snakes"));
+
+        buffer.clear();
+        SavedGame::new(&game_state, None)
+            .with_players(2)
+            .write_to(&mut buffer)
+            .unwrap();
+        assert_eq!(SavedGame::read_from(buffer.as_slice()).unwrap().players, 2);
+    }
+}
@@ -0,0 +1,535 @@
+//! The settings screen: the options from the config in a list, changed in place with left and
+//! right, above a miniature game that plays by them as they change.
+
+use crate::backend::{Backend, Clear, Key};
+use crate::config::{Config, TICK_MS};
+use crate::game::{MAX_FOOD, MIN_BOARD_SIZE};
+use crate::keymap::{Action, Bindings};
+use crate::pages::draw;
+use crate::player::Steering;
+use crate::render::Theme;
+use crate::welcome::{cycle, framed_rows, get_inner_row, Demo};
+use color_eyre::eyre::Result;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+/// How often the screen is redrawn. The preview moves on every few frames, at the speed set.
+const FRAME_MS: u64 = 20;
+const SPEED_STEP_MS: u64 = 5;
+const HINT: &str = "ENTER SAVES, ESC CANCELS";
+const BINDING_HINT: &str = "ENTER BINDS, BACKSPACE RESETS, ESC GOES BACK";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Width,
+    Height,
+    Speed,
+    Theme,
+    Keys,
+    /// How the player with this index steers
+    Steering(usize),
+    Wrap,
+    Food,
+    Done,
+}
+
+impl Setting {
+    const ALL: [Setting; 10] = [
+        Setting::Width,
+        Setting::Height,
+        Setting::Speed,
+        Setting::Theme,
+        Setting::Keys,
+        Setting::Steering(0),
+        Setting::Steering(1),
+        Setting::Wrap,
+        Setting::Food,
+        Setting::Done,
+    ];
+}
+
+/// `value` moved one step up or down, kept between `min` and `max`
+fn step(value: usize, forwards: bool, min: usize, max: usize) -> usize {
+    let value = if forwards {
+        value.saturating_add(1)
+    } else {
+        value.saturating_sub(1)
+    };
+    value.min(max).max(min)
+}
+
+/// The config being edited. Boards can't be made bigger than the terminal has room for.
+struct Editor {
+    config: Config,
+    selected: usize,
+    max_width: usize,
+    max_height: usize,
+}
+
+impl Editor {
+    fn label(&self, setting: Setting) -> String {
+        let config = &self.config;
+        match setting {
+            Setting::Width => format!("BOARD WIDTH: {}", config.width),
+            Setting::Height => format!("BOARD HEIGHT: {}", config.height),
+            Setting::Speed => format!("SPEED: {} MS PER TICK", config.tick_ms),
+            Setting::Theme => format!("THEME: {}", config.theme).to_uppercase(),
+            Setting::Keys => String::from("KEY BINDINGS"),
+            Setting::Steering(player) => format!(
+                "PLAYER {} STEERING: {}",
+                player + 1,
+                config.keys.steering(player)
+            )
+            .to_uppercase(),
+            Setting::Wrap if config.wrap => String::from("WALLS: WRAP AROUND"),
+            Setting::Wrap => String::from("WALLS: SOLID"),
+            Setting::Food => format!("FOOD: {}", config.food),
+            Setting::Done => String::from("DONE"),
+        }
+    }
+
+    /// Moves the selection or changes the selected setting. Returns the setting picked, if it
+    /// is one that leaves the list: Done or the key bindings.
+    fn handle_key(&mut self, key: Key) -> Option<Setting> {
+        let count = Setting::ALL.len();
+        match key {
+            Key::Up | Key::Char('k') => self.selected = (self.selected + count - 1) % count,
+            Key::Down | Key::Char('j') | Key::Char('\t') => {
+                self.selected = (self.selected + 1) % count
+            }
+            Key::Left | Key::Char('h') => self.change(false),
+            Key::Right | Key::Char('l') => self.change(true),
+            Key::Char('\n') | Key::Char(' ') => match Setting::ALL[self.selected] {
+                setting @ (Setting::Done | Setting::Keys) => return Some(setting),
+                _ => self.change(true),
+            },
+            _ => (),
+        }
+        None
+    }
+
+    fn change(&mut self, forwards: bool) {
+        let config = &mut self.config;
+        match Setting::ALL[self.selected] {
+            Setting::Width => {
+                config.width = step(config.width, forwards, MIN_BOARD_SIZE, self.max_width)
+            }
+            Setting::Height => {
+                config.height = step(config.height, forwards, MIN_BOARD_SIZE, self.max_height)
+            }
+            Setting::Speed => {
+                let tick_ms = if forwards {
+                    config.tick_ms.saturating_add(SPEED_STEP_MS)
+                } else {
+                    config.tick_ms.saturating_sub(SPEED_STEP_MS)
+                };
+                config.tick_ms = tick_ms.clamp(*TICK_MS.start(), *TICK_MS.end());
+            }
+            Setting::Theme => config.theme = cycle(&Theme::ALL, config.theme, forwards),
+            Setting::Steering(player) => {
+                if let Some(keys) = config.keys.players.get_mut(player) {
+                    keys.steering = cycle(&Steering::ALL, keys.steering, forwards);
+                }
+                // Absolute steering needs up and down keys, which a hand-written config may
+                // have left out
+                if config.keys.check().is_err() {
+                    if let Some(keys) = config.keys.players.get_mut(player) {
+                        keys.steering = cycle(&Steering::ALL, keys.steering, !forwards);
+                    }
+                }
+            }
+            Setting::Wrap => config.wrap = !config.wrap,
+            Setting::Food => config.food = step(config.food, forwards, 1, MAX_FOOD),
+            Setting::Keys | Setting::Done => (),
+        }
+    }
+
+    /// The size of the preview: the board being set, shrunk by the smallest whole factor that
+    /// fits it in `columns` x `rows`. `None` when not even the smallest board fits.
+    fn preview_size(&self, columns: usize, rows: usize) -> Option<(usize, usize)> {
+        let Config { width, height, .. } = self.config;
+        (1..=width.max(height))
+            .map(|scale| {
+                (
+                    width.div_ceil(scale).max(MIN_BOARD_SIZE),
+                    height.div_ceil(scale).max(MIN_BOARD_SIZE),
+                )
+            })
+            .find(|&(preview_width, preview_height)| {
+                preview_width <= columns && preview_height <= rows
+            })
+    }
+
+    /// The settings around the selection, with the title, the preview and the hint added when
+    /// there is room for them
+    fn rows(&self, preview: Option<&str>, width: usize, height: usize) -> Vec<String> {
+        let inner_rows = height - 2;
+        let shown = Setting::ALL.len().min(inner_rows);
+        let first = (self.selected + 1).saturating_sub(shown);
+        let mut content: Vec<String> = Setting::ALL[first..first + shown]
+            .iter()
+            .enumerate()
+            .map(|(offset, &setting)| {
+                let label = self.label(setting);
+                if first + offset == self.selected {
+                    get_inner_row(Some(&format!("> {} <", label)), width)
+                } else {
+                    get_inner_row(Some(&label), width)
+                }
+            })
+            .collect();
+        if inner_rows >= content.len() + 2 {
+            content.insert(0, get_inner_row(Some("SETTINGS"), width));
+            content.insert(1, get_inner_row(None, width));
+        }
+        if inner_rows >= content.len() + 2 {
+            content.push(get_inner_row(None, width));
+            content.push(get_inner_row(Some(HINT), width));
+        }
+        if let Some(preview) = preview {
+            // Between the settings and the hint
+            let index = content.len() - 2;
+            let mut preview_rows = vec![get_inner_row(None, width)];
+            preview_rows.extend(
+                preview
+                    .split_terminator("\n\r")
+                    .map(|row| get_inner_row(Some(row), width)),
+            );
+            content.splice(index..index, preview_rows);
+        }
+        framed_rows(content, width, height)
+    }
+}
+
+/// Lets the player change the settings on a screen the size of the board in `config`, with the
+/// preview started from `seed`. Boards are kept within `max_size`, the room in the terminal.
+/// Continues with the new settings once Done is picked, or with `None` if Esc is pressed, and
+/// breaks if the player quit.
+pub fn edit_settings(
+    terminal: &mut impl Backend,
+    config: &Config,
+    seed: u64,
+    max_size: (usize, usize),
+) -> Result<ControlFlow<(), Option<Config>>> {
+    let (width, height) = (config.width, config.height);
+    let mut editor = Editor {
+        config: config.clone(),
+        selected: 0,
+        max_width: max_size.0,
+        max_height: max_size.1,
+    };
+    // The settings, the title and the hint take up twelve rows, and a blank row goes above the
+    // preview
+    let preview_room = (
+        width.saturating_sub(4),
+        height.saturating_sub(2 + Setting::ALL.len() + 4 + 1),
+    );
+    let mut demo: Option<Demo> = None;
+    let mut shown = None;
+    let mut elapsed_ms = 0;
+    terminal.clear(Clear::All)?;
+    terminal.set_cursor_visible(false)?;
+    let edited = 'screen: loop {
+        while let Some(key) = terminal.poll_key()? {
+            if key.is_quit() {
+                terminal.set_cursor_visible(true)?;
+                terminal.flush()?;
+                return Ok(ControlFlow::Break(()));
+            }
+            match key {
+                Key::Esc => break 'screen None,
+                key => match editor.handle_key(key) {
+                    Some(Setting::Done) => break 'screen Some(editor.config),
+                    Some(Setting::Keys) => {
+                        match bind_keys(terminal, width, height, &editor.config.keys)? {
+                            ControlFlow::Break(()) => {
+                                terminal.set_cursor_visible(true)?;
+                                terminal.flush()?;
+                                return Ok(ControlFlow::Break(()));
+                            }
+                            ControlFlow::Continue(keys) => editor.config.keys = keys,
+                        }
+                        terminal.clear(Clear::All)?;
+                    }
+                    _ => (),
+                },
+            }
+        }
+        // The preview starts over whenever the board or the rules change
+        let wanted = (
+            editor.preview_size(preview_room.0, preview_room.1),
+            editor.config.rules(),
+        );
+        if shown != Some(wanted) {
+            demo = wanted.0.map(|(preview_width, preview_height)| {
+                Demo::new(preview_width, preview_height, seed, wanted.1)
+            });
+            shown = Some(wanted);
+            elapsed_ms = 0;
+        }
+        let board = demo.as_ref().map(|demo| demo.board(editor.config.theme));
+        let rows = editor.rows(board.as_deref(), width, height);
+        terminal.move_to(0, 0)?;
+        terminal.write(&rows.join("\n\r"))?;
+        terminal.flush()?;
+        terminal.sleep(Duration::from_millis(FRAME_MS));
+        elapsed_ms += FRAME_MS;
+        if elapsed_ms >= editor.config.tick_ms {
+            elapsed_ms = 0;
+            if let Some(demo) = &mut demo {
+                demo.advance();
+            }
+        }
+    };
+    terminal.clear(Clear::All)?;
+    terminal.move_to(0, 0)?;
+    terminal.set_cursor_visible(true)?;
+    terminal.flush()?;
+    Ok(ControlFlow::Continue(edited))
+}
+
+fn binding_label(bindings: &Bindings, action: Action) -> String {
+    let keys: Vec<String> = bindings
+        .keys(action)
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    format!("{}: {}", action.to_string().to_uppercase(), keys.join(", "))
+}
+
+/// Lets the player rebind the keys one action at a time: enter waits for a key to bind to the
+/// selected action and backspace puts back its default keys. Keys already bound to something
+/// else are refused. Continues with the new bindings once Esc is pressed, and breaks if the
+/// player quit.
+fn bind_keys(
+    terminal: &mut impl Backend,
+    width: usize,
+    height: usize,
+    bindings: &Bindings,
+) -> Result<ControlFlow<(), Bindings>> {
+    let mut bindings = bindings.clone();
+    let actions = bindings.actions();
+    let mut selected: usize = 0;
+    let mut status = String::from(BINDING_HINT);
+    let mut waiting = false;
+    loop {
+        // The title, the status and a blank row after each take four of the rows inside the frame
+        let shown = actions.len().min(height.saturating_sub(6).max(1));
+        let first = (selected + 1).saturating_sub(shown);
+        let mut content = vec![
+            get_inner_row(Some("KEY BINDINGS"), width),
+            get_inner_row(None, width),
+        ];
+        content.extend(actions.iter().enumerate().skip(first).take(shown).map(
+            |(index, &action)| {
+                let label = binding_label(&bindings, action);
+                if index == selected {
+                    get_inner_row(Some(&format!("> {} <", label)), width)
+                } else {
+                    get_inner_row(Some(&label), width)
+                }
+            },
+        ));
+        content.push(get_inner_row(None, width));
+        content.push(get_inner_row(Some(&status), width));
+        draw(terminal, &framed_rows(content, width, height))?;
+        let key = terminal.read_key()?;
+        let action = actions[selected];
+        if waiting {
+            waiting = false;
+            status = match (key, bindings.action(key)) {
+                (Key::Esc, _) => String::from(BINDING_HINT),
+                (Key::Other, _) => String::from("THAT KEY CAN'T BE BOUND"),
+                (key, Some(other)) if other != action => {
+                    format!("{} IS ALREADY {}", key, other).to_uppercase()
+                }
+                (key, _) => {
+                    bindings.set(action, vec![key]);
+                    String::from(BINDING_HINT)
+                }
+            };
+            continue;
+        }
+        match key {
+            key if key.is_quit() => return Ok(ControlFlow::Break(())),
+            Key::Esc => return Ok(ControlFlow::Continue(bindings)),
+            Key::Up | Key::Char('k') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => selected = (selected + 1).min(actions.len() - 1),
+            Key::Char('\n') => {
+                waiting = true;
+                status = format!("PRESS A KEY FOR {}", action).to_uppercase();
+            }
+            Key::Backspace => {
+                let defaults = Bindings::default();
+                let keys = defaults.keys(action).to_vec();
+                // The defaults may be taken by now
+                match keys.iter().find_map(|&key| {
+                    bindings
+                        .action(key)
+                        .filter(|&other| other != action)
+                        .map(|other| (key, other))
+                }) {
+                    Some((key, other)) => {
+                        status = format!("{} IS ALREADY {}", key, other).to_uppercase()
+                    }
+                    None => bindings.set(action, keys),
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::memory::{Input, MemoryBackend};
+    use crate::player::Direction;
+
+    fn editor() -> Editor {
+        Editor {
+            config: Config {
+                width: 30,
+                height: 12,
+                ..Config::default()
+            },
+            selected: 0,
+            max_width: 32,
+            max_height: 12,
+        }
+    }
+
+    #[test]
+    fn settings_stay_within_what_can_be_played() {
+        let mut editor = editor();
+        for _ in 0..5 {
+            editor.handle_key(Key::Right);
+        }
+        assert_eq!(editor.config.width, 32);
+        editor.handle_key(Key::Down);
+        editor.handle_key(Key::Right);
+        assert_eq!(editor.config.height, 12);
+        editor.handle_key(Key::Down);
+        for _ in 0..100 {
+            editor.handle_key(Key::Left);
+        }
+        assert_eq!(editor.config.tick_ms, *TICK_MS.start());
+        editor.handle_key(Key::Down);
+        editor.handle_key(Key::Left);
+        assert_eq!(editor.config.theme, Theme::Blocks);
+        editor.handle_key(Key::Up);
+        editor.handle_key(Key::Up);
+        editor.handle_key(Key::Up);
+        assert_eq!(editor.handle_key(Key::Up), None);
+        assert_eq!(editor.handle_key(Key::Char('\n')), Some(Setting::Done));
+        assert!(editor.config.check().is_ok());
+    }
+
+    #[test]
+    fn steering_is_chosen_per_player() {
+        let mut editor = editor();
+        for _ in 0..6 {
+            editor.handle_key(Key::Down);
+        }
+        assert_eq!(Setting::ALL[editor.selected], Setting::Steering(1));
+        editor.handle_key(Key::Right);
+        assert_eq!(editor.config.keys.steering(0), Steering::Absolute);
+        assert_eq!(editor.config.keys.steering(1), Steering::Relative);
+        assert_eq!(
+            editor.label(Setting::Steering(1)),
+            "PLAYER 2 STEERING: RELATIVE"
+        );
+        editor.handle_key(Key::Right);
+        assert_eq!(editor.config.keys.steering(1), Steering::Absolute);
+    }
+
+    #[test]
+    fn the_preview_is_the_board_scaled_down_to_fit() {
+        let editor = editor();
+        assert_eq!(editor.preview_size(30, 12), Some((30, 12)));
+        assert_eq!(editor.preview_size(20, 8), Some((15, 6)));
+        assert_eq!(editor.preview_size(3, 10), None);
+    }
+
+    #[test]
+    fn done_hands_back_the_changed_settings_and_esc_drops_them() {
+        let config = Config {
+            width: 40,
+            height: 30,
+            ..Config::default()
+        };
+        let keys = [Key::Down, Key::Down, Key::Down, Key::Right, Key::Up];
+        let mut terminal = MemoryBackend::new(40, 31).with_script(
+            keys.into_iter()
+                .map(Input::from)
+                .chain([Input::Wait(5), Key::Char('\n').into(), Input::Wait(1)])
+                .chain([Key::Up, Key::Up, Key::Up, Key::Char('\n')].map(Input::from)),
+        );
+        let edited = edit_settings(&mut terminal, &config, 3, (40, 30)).unwrap();
+        let ControlFlow::Continue(Some(edited)) = edited else {
+            panic!("the settings were not saved");
+        };
+        assert_eq!(edited.theme, Theme::Ascii);
+        assert_eq!(edited.tick_ms, config.tick_ms + SPEED_STEP_MS);
+        let preview = terminal
+            .frames()
+            .iter()
+            .find(|frame| frame.contains("THEME: ASCII"))
+            .unwrap();
+        assert!(preview.contains("+---"));
+
+        let mut terminal = MemoryBackend::new(40, 31).with_script([
+            Key::Right.into(),
+            Input::Wait(1),
+            Key::Esc.into(),
+        ]);
+        let edited = edit_settings(&mut terminal, &config, 3, (40, 30)).unwrap();
+        assert_eq!(edited, ControlFlow::Continue(None));
+    }
+
+    #[test]
+    fn keys_are_bound_one_at_a_time_and_conflicts_refused() {
+        let script = [
+            // Player 1 up becomes x
+            Key::Char('\n'),
+            Key::Char('x'),
+            // Player 1 right can't take w from player 2
+            Key::Down,
+            Key::Char('\n'),
+            Key::Char('w'),
+            // Player 1 up goes back to its defaults
+            Key::Up,
+            Key::Backspace,
+            Key::Down,
+            Key::Down,
+            Key::Char('\n'),
+            Key::Char('q'),
+            Key::Esc,
+        ];
+        let mut terminal = MemoryBackend::new(50, 25).with_script(script.map(Input::from));
+        let bindings = Bindings::default();
+        let bound = bind_keys(&mut terminal, 50, 25, &bindings).unwrap();
+        let ControlFlow::Continue(bound) = bound else {
+            panic!("the bindings were dropped");
+        };
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.contains("W IS ALREADY PLAYER 2 UP")));
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.contains("PLAYER 1 UP: x")));
+        assert_eq!(
+            bound.keys(Action::Turn(0, Direction::Up)),
+            &[Key::Up, Key::Char('k')]
+        );
+        assert_eq!(
+            bound.keys(Action::Turn(0, Direction::Down)),
+            &[Key::Down, Key::Char('j')]
+        );
+        // q was taken by quit
+        assert!(terminal.screen().contains("Q IS ALREADY QUIT"));
+        assert_eq!(bound, bindings);
+    }
+}
@@ -0,0 +1,239 @@
+//! A read-only view of a game, used to watch networked games and recorded replays.
+
+use crate::ai::Controller;
+use crate::backend::{Backend, Clear, Key};
+use crate::game::{GameBoard, GameState};
+use crate::headless::{play_match, MatchResult};
+use crate::render::TAIL_CHARACTERS;
+use crate::replay::Replay;
+use color_eyre::eyre::Result;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+pub struct SpectatorView {
+    pub names: Vec<String>,
+    /// The snake the camera stays on when the board is larger than the terminal
+    pub follow: Option<usize>,
+}
+
+impl SpectatorView {
+    pub fn new(names: Vec<String>, follow: Option<usize>) -> Self {
+        let follow = follow.filter(|&index| index < names.len());
+        Self { names, follow }
+    }
+
+    /// Tab/n and p cycle through the snakes, 1-9 pick one directly and 0 stops following
+    pub fn handle_key(&mut self, key: Key) {
+        let player_count = self.names.len();
+        if player_count == 0 {
+            return;
+        }
+        self.follow = match key {
+            Key::Char('\t') | Key::Char('n') => {
+                Some(self.follow.map_or(0, |index| (index + 1) % player_count))
+            }
+            Key::Char('p') => Some(self.follow.map_or(player_count - 1, |index| {
+                (index + player_count - 1) % player_count
+            })),
+            Key::Char('0') => None,
+            Key::Char(digit @ '1'..='9') => {
+                let index = digit as usize - '1' as usize;
+                if index < player_count {
+                    Some(index)
+                } else {
+                    self.follow
+                }
+            }
+            _ => self.follow,
+        };
+    }
+
+    /// Draws the board, cropped around the followed snake if needed, above a scoreboard
+    pub fn render(&self, game_state: &GameState, columns: usize, rows: usize) -> Result<String> {
+        let scoreboard = self.scoreboard(game_state);
+        let board = GameBoard::try_from(game_state)?;
+        let board_rows = rows.saturating_sub(scoreboard.len()).max(1);
+        let followed = self
+            .follow
+            .and_then(|index| game_state.snakes().get(index))
+            .or_else(|| game_state.snakes().iter().find(|snake| snake.alive));
+        let center = match followed {
+            Some(snake) => snake.player.head_position,
+            None => crate::player::Position::new(
+                game_state.game_area.width / 2,
+                game_state.game_area.height / 2,
+            ),
+        };
+        let board = board.viewport(center, columns, board_rows)?;
+        let mut output = board.to_string();
+        output.push_str(&scoreboard.join("\n\r"));
+        Ok(output)
+    }
+
+    fn scoreboard(&self, game_state: &GameState) -> Vec<String> {
+        let mut standings: Vec<(usize, &String, &crate::player::Snake)> = self
+            .names
+            .iter()
+            .zip(game_state.snakes())
+            .enumerate()
+            .map(|(index, (name, snake))| (index, name, snake))
+            .collect();
+        standings.sort_by(|a, b| b.2.score.cmp(&a.2.score).then(a.0.cmp(&b.0)));
+        let mut lines: Vec<String> = standings
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (index, name, snake))| {
+                let marker = if self.follow == Some(index) { ">" } else { " " };
+                let tail = TAIL_CHARACTERS[index % TAIL_CHARACTERS.len()];
+                let status = if snake.alive { "" } else { "dead" };
+                format!(
+                    "{}{}. {} {:<16} score {:>4}  length {:>4}  {}",
+                    marker,
+                    rank + 1,
+                    tail,
+                    name,
+                    snake.score,
+                    snake.tail.positions.len() + 1,
+                    status
+                )
+            })
+            .collect();
+        lines.push(String::from(
+            "tab/n/p or 1-9 to follow a snake, 0 to stop following, q to quit",
+        ));
+        lines
+    }
+}
+
+pub(crate) fn draw(
+    terminal: &mut impl Backend,
+    view: &SpectatorView,
+    game_state: &GameState,
+) -> Result<()> {
+    let (columns, rows) = terminal.size()?;
+    let frame = view.render(game_state, columns, rows)?;
+    terminal.move_to(0, 0)?;
+    for (index, line) in frame.split("\n\r").enumerate() {
+        if index > 0 {
+            terminal.clear(Clear::UntilNewline)?;
+            terminal.write("\n\r")?;
+        }
+        terminal.write(line)?;
+    }
+    terminal.clear(Clear::AfterCursor)?;
+    terminal.flush()?;
+    Ok(())
+}
+
+/// Plays a replay in the terminal as if it were being watched live
+pub fn watch_replay(
+    terminal: &mut impl Backend,
+    replay: &Replay,
+    follow: Option<usize>,
+    tick_duration: Duration,
+) -> Result<()> {
+    let mut view = SpectatorView::new(replay.header.names.clone(), follow);
+    terminal.clear(Clear::All)?;
+    terminal.set_cursor_visible(false)?;
+    let mut game_state = replay.initial_state();
+    let mut states = replay.states();
+    let mut next_tick = Instant::now();
+    'playback: loop {
+        while let Some(key) = terminal.poll_key()? {
+            match key {
+                key if key.is_quit() => break 'playback,
+                key => view.handle_key(key),
+            }
+        }
+        if Instant::now() >= next_tick {
+            match states.next() {
+                Some(state) => game_state = state,
+                None => break,
+            }
+            next_tick += tick_duration;
+        }
+        draw(terminal, &view, &game_state)?;
+        terminal.sleep(Duration::from_millis(10));
+    }
+    terminal.set_cursor_visible(true)?;
+    terminal.flush()?;
+    Ok(())
+}
+
+/// Plays a game between controllers in the terminal, one tick every `tick_duration`
+pub fn watch_match(
+    terminal: &mut impl Backend,
+    game_state: &mut GameState,
+    controllers: &mut [impl Controller],
+    names: Vec<String>,
+    max_ticks: Option<u64>,
+    tick_duration: Duration,
+) -> Result<MatchResult> {
+    let mut view = SpectatorView::new(names, Some(0));
+    terminal.clear(Clear::All)?;
+    terminal.set_cursor_visible(false)?;
+    draw(terminal, &view, game_state)?;
+    let mut failure = None;
+    let result = play_match(game_state, controllers, max_ticks, |game_state| {
+        loop {
+            match terminal.poll_key() {
+                Ok(Some(key)) if key.is_quit() => return ControlFlow::Break(()),
+                Ok(Some(key)) => view.handle_key(key),
+                Ok(None) => break,
+                Err(error) => {
+                    failure = Some(error);
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+        if let Err(error) = draw(terminal, &view, game_state) {
+            failure = Some(error);
+            return ControlFlow::Break(());
+        }
+        terminal.sleep(tick_duration);
+        ControlFlow::Continue(())
+    });
+    terminal.set_cursor_visible(true)?;
+    terminal.flush()?;
+    match failure {
+        Some(error) => Err(error),
+        None => Ok(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view() -> SpectatorView {
+        SpectatorView::new(vec![String::from("ada"), String::from("grace")], None)
+    }
+
+    #[test]
+    fn follow_keys_cycle_through_snakes() {
+        let mut view = view();
+        view.handle_key(Key::Char('\t'));
+        assert_eq!(view.follow, Some(0));
+        view.handle_key(Key::Char('n'));
+        assert_eq!(view.follow, Some(1));
+        view.handle_key(Key::Char('n'));
+        assert_eq!(view.follow, Some(0));
+        view.handle_key(Key::Char('p'));
+        assert_eq!(view.follow, Some(1));
+        view.handle_key(Key::Char('9'));
+        assert_eq!(view.follow, Some(1));
+        view.handle_key(Key::Char('0'));
+        assert_eq!(view.follow, None);
+    }
+
+    #[test]
+    fn small_terminals_get_a_cropped_board_and_a_scoreboard() {
+        let game_state = GameState::seeded(80, 25, 2, 3);
+        let frame = view().render(&game_state, 40, 15).unwrap();
+        let lines: Vec<&str> = frame.split("\n\r").collect();
+        assert_eq!(lines.len(), 15);
+        assert_eq!(lines[0].chars().count(), 40);
+        assert!(lines[12].contains("ada"));
+        assert!(lines[13].contains("grace"));
+    }
+}
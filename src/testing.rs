@@ -0,0 +1,54 @@
+//! Helpers shared by the unit tests
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::{env, process};
+
+/// A file or directory in the temp directory, named after the test process so parallel runs
+/// don't collide. Whatever is there is removed on creation and again on drop, so a failing
+/// test doesn't leave it behind.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("snake-{}-{}", process::id(), name));
+        let temp_path = Self(path);
+        temp_path.remove();
+        temp_path
+    }
+
+    fn remove(&self) {
+        if self.0.is_dir() {
+            let _ = fs::remove_dir_all(&self.0);
+        } else {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl From<&TempPath> for PathBuf {
+    fn from(temp_path: &TempPath) -> Self {
+        temp_path.0.clone()
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
@@ -0,0 +1,513 @@
+//! Runs a matrix of headless games between bots and summarises how each bot did, so that
+//! bot versions can be compared on the same seeds and boards.
+
+use crate::ai::external::{BotSettings, ExternalBot};
+use crate::ai::{Controller, Difficulty};
+use crate::game::GameState;
+use crate::headless::{play_match, MatchResult};
+use color_eyre::eyre::{eyre, Report, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// A bot taking part in a tournament
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entrant {
+    Builtin(Difficulty),
+    /// A program speaking the protocol described in [`crate::ai::external`]
+    External(String),
+}
+
+impl Entrant {
+    /// Difficulty names pick a built-in bot, anything else is run as a command
+    pub fn parse(value: &str) -> Self {
+        value
+            .parse()
+            .map_or_else(|_| Entrant::External(value.to_string()), Entrant::Builtin)
+    }
+
+    fn controller(&self, settings: BotSettings) -> Result<Box<dyn Controller + Send>> {
+        match self {
+            Entrant::Builtin(difficulty) => Ok(difficulty.controller()),
+            Entrant::External(command) => Ok(Box::new(ExternalBot::spawn(command, settings)?)),
+        }
+    }
+}
+
+impl fmt::Display for Entrant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entrant::Builtin(difficulty) => write!(f, "{}", difficulty),
+            Entrant::External(command) => write!(f, "{}", command),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// Every bot plays alone
+    Solo,
+    /// Every pair of bots plays on the same board, once from each starting position
+    Versus,
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            MatchKind::Solo => "solo",
+            MatchKind::Versus => "versus",
+        })
+    }
+}
+
+impl FromStr for MatchKind {
+    type Err = Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "solo" => Ok(MatchKind::Solo),
+            "versus" => Ok(MatchKind::Versus),
+            _ => Err(eyre!("unknown mode {:?}, expected solo or versus", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentConfig {
+    pub entrants: Vec<Entrant>,
+    pub seeds: Vec<u64>,
+    /// Board sizes as (width, height)
+    pub sizes: Vec<(usize, usize)>,
+    pub modes: Vec<MatchKind>,
+    pub max_ticks: u64,
+    /// Number of games played at the same time
+    pub jobs: usize,
+    pub bot_settings: BotSettings,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        Self {
+            entrants: Difficulty::ALL.into_iter().map(Entrant::Builtin).collect(),
+            seeds: (0..10).collect(),
+            sizes: vec![(40, 20)],
+            modes: vec![MatchKind::Solo],
+            max_ticks: 5_000,
+            jobs: 1,
+            bot_settings: BotSettings {
+                show_stderr: false,
+                ..BotSettings::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Fixture {
+    mode: MatchKind,
+    width: usize,
+    height: usize,
+    seed: u64,
+    /// Indices into the entrants, one per snake
+    players: Vec<usize>,
+}
+
+impl TournamentConfig {
+    fn fixtures(&self) -> Vec<Fixture> {
+        let mut fixtures = Vec::new();
+        for &mode in &self.modes {
+            let seatings: Vec<Vec<usize>> = match mode {
+                MatchKind::Solo => (0..self.entrants.len()).map(|index| vec![index]).collect(),
+                MatchKind::Versus => (0..self.entrants.len())
+                    .flat_map(|first| {
+                        (0..self.entrants.len())
+                            .filter(move |&second| second != first)
+                            .map(move |second| vec![first, second])
+                    })
+                    .collect(),
+            };
+            for &(width, height) in &self.sizes {
+                for &seed in &self.seeds {
+                    for players in &seatings {
+                        fixtures.push(Fixture {
+                            mode,
+                            width,
+                            height,
+                            seed,
+                            players: players.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        fixtures
+    }
+
+    fn play(&self, fixture: &Fixture) -> Result<GameRecord> {
+        let mut controllers = fixture
+            .players
+            .iter()
+            .map(|&index| self.entrants[index].controller(self.bot_settings))
+            .collect::<Result<Vec<_>>>()?;
+        let mut game_state = GameState::seeded(
+            fixture.width,
+            fixture.height,
+            fixture.players.len(),
+            fixture.seed,
+        );
+        let result = play_match(
+            &mut game_state,
+            &mut controllers,
+            Some(self.max_ticks),
+            |_| ControlFlow::Continue(()),
+        );
+        Ok(GameRecord {
+            mode: fixture.mode,
+            width: fixture.width,
+            height: fixture.height,
+            seed: fixture.seed,
+            players: fixture
+                .players
+                .iter()
+                .map(|&index| self.entrants[index].to_string())
+                .collect(),
+            winner: result.winner(),
+            result,
+            entrants: fixture.players.clone(),
+        })
+    }
+}
+
+/// One game of the tournament
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameRecord {
+    pub mode: MatchKind,
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    pub players: Vec<String>,
+    #[serde(flatten)]
+    pub result: MatchResult,
+    /// Seat of the last snake standing in versus games
+    pub winner: Option<usize>,
+    #[serde(skip)]
+    entrants: Vec<usize>,
+}
+
+/// How one bot did in one mode on one board size, across all seeds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Standing {
+    pub entrant: String,
+    pub mode: MatchKind,
+    pub width: usize,
+    pub height: usize,
+    pub games: usize,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub max_score: usize,
+    pub mean_survival_ticks: f64,
+    /// Only counted in versus games
+    pub wins: Option<usize>,
+    pub win_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TournamentResults {
+    pub standings: Vec<Standing>,
+    pub games: Vec<GameRecord>,
+}
+
+/// Plays every game of the tournament, calling `progress` with the number of finished games
+pub fn run(
+    config: &TournamentConfig,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<TournamentResults> {
+    let fixtures = config.fixtures();
+    if fixtures.is_empty() {
+        return Err(eyre!("the tournament has no games to play"));
+    }
+    let next_fixture = AtomicUsize::new(0);
+    let mut games: Vec<Option<GameRecord>> = vec![None; fixtures.len()];
+    thread::scope(|scope| -> Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..config.jobs.clamp(1, fixtures.len()) {
+            let sender = sender.clone();
+            let next_fixture = &next_fixture;
+            let fixtures = &fixtures;
+            scope.spawn(move || loop {
+                let index = next_fixture.fetch_add(1, Ordering::Relaxed);
+                let Some(fixture) = fixtures.get(index) else {
+                    return;
+                };
+                if sender.send((index, config.play(fixture))).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(sender);
+        for (finished, (index, record)) in receiver.into_iter().enumerate() {
+            match record {
+                Ok(record) => games[index] = Some(record),
+                Err(error) => {
+                    // Stop handing out games so the workers wind down
+                    next_fixture.store(fixtures.len(), Ordering::Relaxed);
+                    return Err(error);
+                }
+            }
+            progress(finished + 1, fixtures.len());
+        }
+        Ok(())
+    })?;
+    let games: Vec<GameRecord> = games.into_iter().flatten().collect();
+    Ok(TournamentResults {
+        standings: standings(config, &games),
+        games,
+    })
+}
+
+#[derive(Default)]
+struct Totals {
+    scores: Vec<usize>,
+    survival: Vec<u64>,
+    wins: usize,
+}
+
+fn standings(config: &TournamentConfig, games: &[GameRecord]) -> Vec<Standing> {
+    let mut by_entrant: BTreeMap<(MatchKind, usize, usize, usize), Totals> = BTreeMap::new();
+    for game in games {
+        for (seat, &entrant) in game.entrants.iter().enumerate() {
+            let totals = by_entrant
+                .entry((game.mode, game.width, game.height, entrant))
+                .or_default();
+            totals.scores.push(game.result.scores[seat]);
+            totals.survival.push(game.result.survival[seat]);
+            if game.winner == Some(seat) {
+                totals.wins += 1;
+            }
+        }
+    }
+    by_entrant
+        .into_iter()
+        .map(|((mode, width, height, entrant), totals)| {
+            let games = totals.scores.len();
+            let versus = mode == MatchKind::Versus;
+            Standing {
+                entrant: config.entrants[entrant].to_string(),
+                mode,
+                width,
+                height,
+                games,
+                mean_score: mean(totals.scores.iter().map(|&score| score as f64)),
+                median_score: median(totals.scores.clone()),
+                max_score: totals.scores.iter().copied().max().unwrap_or(0),
+                mean_survival_ticks: mean(totals.survival.iter().map(|&ticks| ticks as f64)),
+                wins: versus.then_some(totals.wins),
+                win_rate: versus.then(|| totals.wins as f64 / games as f64),
+            }
+        })
+        .collect()
+}
+
+fn mean(values: impl ExactSizeIterator<Item = f64>) -> f64 {
+    let count = values.len();
+    if count == 0 {
+        return 0.0;
+    }
+    values.sum::<f64>() / count as f64
+}
+
+fn median(mut values: Vec<usize>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_unstable();
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) as f64 / 2.0
+    } else {
+        values[middle] as f64
+    }
+}
+
+const CSV_HEADER: &str = "entrant,mode,width,height,games,mean_score,median_score,max_score,\
+mean_survival_ticks,wins,win_rate";
+
+pub fn write_csv(standings: &[Standing], writer: &mut impl Write) -> Result<()> {
+    writeln!(writer, "{}", CSV_HEADER)?;
+    for standing in standings {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{:.2},{:.2},{},{:.2},{},{}",
+            csv_field(&standing.entrant),
+            standing.mode,
+            standing.width,
+            standing.height,
+            standing.games,
+            standing.mean_score,
+            standing.median_score,
+            standing.max_score,
+            standing.mean_survival_ticks,
+            standing.wins.map_or(String::new(), |wins| wins.to_string()),
+            standing
+                .win_rate
+                .map_or(String::new(), |rate| format!("{:.3}", rate)),
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Quotes a field when it contains anything that would break the row apart
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn write_json(results: &TournamentResults, writer: &mut impl Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, results)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The standings as an aligned table for the terminal
+pub fn table(standings: &[Standing]) -> String {
+    let name_width = standings
+        .iter()
+        .map(|standing| standing.entrant.chars().count())
+        .chain([7])
+        .max()
+        .unwrap_or(7);
+    let mut lines = vec![format!(
+        "{:<name_width$}  {:<6}  {:>7}  {:>5}  {:>6}  {:>6}  {:>5}  {:>8}  {:>5}",
+        "entrant", "mode", "board", "games", "mean", "median", "max", "survival", "wins"
+    )];
+    for standing in standings {
+        lines.push(format!(
+            "{:<name_width$}  {:<6}  {:>7}  {:>5}  {:>6.1}  {:>6.1}  {:>5}  {:>8.0}  {:>5}",
+            standing.entrant,
+            standing.mode,
+            format!("{}x{}", standing.width, standing.height),
+            standing.games,
+            standing.mean_score,
+            standing.median_score,
+            standing.max_score,
+            standing.mean_survival_ticks,
+            standing
+                .win_rate
+                .map_or(String::from("-"), |rate| format!("{:.0}%", rate * 100.0)),
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TournamentConfig {
+        TournamentConfig {
+            entrants: vec![
+                Entrant::Builtin(Difficulty::Easy),
+                Entrant::Builtin(Difficulty::Medium),
+            ],
+            seeds: vec![1, 2, 3],
+            sizes: vec![(20, 12)],
+            modes: vec![MatchKind::Solo, MatchKind::Versus],
+            max_ticks: 300,
+            ..TournamentConfig::default()
+        }
+    }
+
+    #[test]
+    fn entrants_are_builtin_bots_or_commands() {
+        assert_eq!(Entrant::parse("hard"), Entrant::Builtin(Difficulty::Hard));
+        assert_eq!(
+            Entrant::parse("python3 bot.py"),
+            Entrant::External(String::from("python3 bot.py"))
+        );
+    }
+
+    #[test]
+    fn versus_pairs_play_from_both_sides() {
+        let fixtures = config().fixtures();
+        // 2 solo games and 2 versus seatings for each of the 3 seeds
+        assert_eq!(fixtures.len(), 12);
+        let seatings: Vec<&Vec<usize>> = fixtures
+            .iter()
+            .filter(|fixture| fixture.mode == MatchKind::Versus && fixture.seed == 1)
+            .map(|fixture| &fixture.players)
+            .collect();
+        assert_eq!(seatings, vec![&vec![0, 1], &vec![1, 0]]);
+    }
+
+    #[test]
+    fn parallel_runs_match_sequential_runs() {
+        let sequential = run(&config(), |_, _| {}).unwrap();
+        let parallel = run(
+            &TournamentConfig {
+                jobs: 4,
+                ..config()
+            },
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(sequential, parallel);
+
+        let standings = &sequential.standings;
+        assert_eq!(standings.len(), 4);
+        let versus: Vec<&Standing> = standings
+            .iter()
+            .filter(|standing| standing.mode == MatchKind::Versus)
+            .collect();
+        assert!(versus.iter().all(|standing| standing.games == 6));
+        let solo = standings
+            .iter()
+            .find(|standing| standing.mode == MatchKind::Solo)
+            .unwrap();
+        assert_eq!(solo.win_rate, None);
+    }
+
+    #[test]
+    fn medians_average_the_middle_pair() {
+        assert_eq!(median(vec![5, 1, 3]), 3.0);
+        assert_eq!(median(vec![4, 1, 3, 2]), 2.5);
+        assert_eq!(median(Vec::new()), 0.0);
+    }
+
+    #[test]
+    fn csv_quotes_awkward_names() {
+        let standing = Standing {
+            entrant: String::from("./bot --greeting \"hi, there\""),
+            mode: MatchKind::Versus,
+            width: 20,
+            height: 12,
+            games: 4,
+            mean_score: 2.5,
+            median_score: 2.0,
+            max_score: 6,
+            mean_survival_ticks: 120.0,
+            wins: Some(1),
+            win_rate: Some(0.25),
+        };
+        let mut buffer = Vec::new();
+        write_csv(&[standing], &mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "\"./bot --greeting \"\"hi, there\"\"\",versus,20,12,4,2.50,2.00,6,120.00,1,0.250"
+        );
+    }
+}
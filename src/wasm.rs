@@ -0,0 +1,133 @@
+//! Bindings for the browser front-end in `web/`, built with the `wasm` feature and no default
+//! features. The page owns the timer and the canvas; this side only runs the game and hands
+//! back the board as one byte per cell.
+
+use crate::game::{GameArea, GameState};
+use crate::player::{Direction, Position};
+use wasm_bindgen::prelude::*;
+
+/// What `WebGame::cells` stores for each cell
+pub const EMPTY: u8 = 0;
+pub const WALL: u8 = 1;
+pub const HEAD: u8 = 2;
+pub const TAIL: u8 = 3;
+pub const FOOD: u8 = 4;
+
+#[wasm_bindgen]
+pub struct WebGame {
+    game_state: GameState,
+}
+
+#[wasm_bindgen]
+impl WebGame {
+    /// Starts a single player game; a random seed is used when none is given
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, seed: Option<u32>) -> Result<WebGame, JsError> {
+        GameArea::check(width, height).map_err(|error| JsError::new(&error.to_string()))?;
+        let seed = seed.map_or_else(rand::random, u64::from);
+        Ok(Self {
+            game_state: GameState::seeded(width, height, 1, seed),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.game_state.game_area.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.game_state.game_area.height
+    }
+
+    pub fn score(&self) -> usize {
+        self.game_state.score()
+    }
+
+    #[wasm_bindgen(js_name = isOver)]
+    pub fn is_over(&self) -> bool {
+        self.game_state.is_over()
+    }
+
+    /// Steers with a `KeyboardEvent.key` value: the arrows, wasd or hjkl. Returns whether the
+    /// key was a movement key, so the page knows to stop it scrolling.
+    pub fn key(&mut self, key: &str) -> bool {
+        let direction = match key {
+            "ArrowUp" | "w" | "k" => Direction::Up,
+            "ArrowRight" | "d" | "l" => Direction::Right,
+            "ArrowDown" | "s" | "j" => Direction::Down,
+            "ArrowLeft" | "a" | "h" => Direction::Left,
+            _ => return false,
+        };
+        self.game_state.steer(0, direction);
+        true
+    }
+
+    /// Advances the game by one tick, returning whether it is still running
+    pub fn tick(&mut self) -> bool {
+        !self.game_state.is_over() && self.game_state.tick().is_some()
+    }
+
+    /// The board in row-major order, one of `EMPTY`, `WALL`, `HEAD`, `TAIL` or `FOOD` per cell
+    pub fn cells(&self) -> Vec<u8> {
+        let (width, height) = (self.width(), self.height());
+        let mut cells = vec![EMPTY; width * height];
+        let mut set = |position: Position, value: u8| {
+            if position.column_number < width && position.row_number < height {
+                cells[position.row_number * width + position.column_number] = value;
+            }
+        };
+        for column in 0..width {
+            set(Position::new(column, 0), WALL);
+            set(Position::new(column, height - 1), WALL);
+        }
+        for row in 0..height {
+            set(Position::new(0, row), WALL);
+            set(Position::new(width - 1, row), WALL);
+        }
+        for food in self.game_state.powerup_positions() {
+            set(food, FOOD);
+        }
+        for snake in self.game_state.snakes().iter().filter(|snake| snake.alive) {
+            for &position in &snake.tail.positions {
+                set(position, TAIL);
+            }
+            set(snake.player.head_position, HEAD);
+        }
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn cells_show_the_board() {
+        let game = WebGame::new(12, 8, Some(3)).unwrap();
+        let cells = game.cells();
+        assert_eq!(cells.len(), 12 * 8);
+        assert_eq!(
+            cells.iter().filter(|&&cell| cell == WALL).count(),
+            2 * 12 + 2 * 6
+        );
+        assert_eq!(cells[4 * 12 + 6], HEAD);
+        assert_eq!(cells[4 * 12 + 5], TAIL);
+        assert_eq!(cells.iter().filter(|&&cell| cell == FOOD).count(), 1);
+    }
+
+    #[test]
+    fn movement_keys_steer_and_others_are_ignored() {
+        let mut game = WebGame::new(12, 8, Some(3)).unwrap();
+        assert!(!game.key("Enter"));
+        assert!(game.key("ArrowUp"));
+        let mut ticks = 0;
+        while game.tick() {
+            ticks += 1;
+        }
+        // From row 4 the snake reaches the top wall on its fourth move
+        assert_eq!(ticks, 3);
+        assert!(game.is_over());
+        assert!(!game.tick());
+    }
+}
@@ -1,13 +1,62 @@
+use crate::ai::{BoardView, Controller, Difficulty};
+use crate::backend::{Backend, Clear, Key};
+use crate::config::Config;
 use crate::constants::*;
-use std::io::{stdin, stdout, Write};
-use termion::event::Key;
-use termion::input::TermRead;
-use termion::raw::IntoRawMode;
-use termion::{clear, cursor};
+use crate::font;
+use crate::game::{GameBoard, GameState, Mode, Rules};
+use crate::profile::DEFAULT_PROFILE;
+use crate::render::Theme;
+use color_eyre::eyre::Result;
 
-const WELCOME_MESSAGE: &str = "HAPPY BIRTHDAY LAURENCE!!!";
+const DEMO_DIFFICULTY: Difficulty = Difficulty::Medium;
+const TITLE: &str = "SNAKE";
 
-fn get_inner_row(text: Option<&str>, width: usize) -> String {
+/// A computer controlled snake that plays behind the welcome message and in the settings preview
+pub(crate) struct Demo {
+    game_state: GameState,
+    controller: Box<dyn Controller + Send>,
+}
+
+impl Demo {
+    pub(crate) fn new(width: usize, height: usize, seed: u64, rules: Rules) -> Self {
+        Self {
+            game_state: GameState::seeded(width, height, 1, seed).with_rules(rules),
+            controller: DEMO_DIFFICULTY.controller(),
+        }
+    }
+
+    /// Plays one tick, starting a fresh game whenever the snake dies
+    pub(crate) fn advance(&mut self) {
+        let direction = self.controller.decide(&BoardView::new(&self.game_state, 0));
+        self.game_state.steer(0, direction);
+        if self.game_state.tick().is_none() {
+            let GameState { game_area, .. } = self.game_state;
+            let seed = self.game_state.seed().wrapping_add(1);
+            let rules = self.game_state.rules();
+            *self = Demo::new(game_area.width, game_area.height, seed, rules);
+        }
+    }
+
+    /// The whole demo board, one row per line
+    pub(crate) fn board(&self, theme: Theme) -> String {
+        GameBoard::try_from(&self.game_state)
+            .map(|board| board.render(theme, self.game_state.rules().wrap))
+            .unwrap_or_default()
+    }
+
+    /// Draws the demo board wherever the welcome screen is blank
+    fn frame(&self, rows: &[String], blank_row: &str, theme: Theme) -> String {
+        let board = self.board(theme);
+        rows.iter()
+            .zip(board.split("\n\r"))
+            .map(|(row, board_row)| if row == blank_row { board_row } else { row })
+            .collect::<Vec<&str>>()
+            .join("\r\n")
+    }
+}
+
+/// A row of the box frame with `text` centered in it, cut short if it doesn't fit
+pub(crate) fn get_inner_row(text: Option<&str>, width: usize) -> String {
     let mut output = String::new();
     output.push(VERTICAL_WALL);
     match text {
@@ -17,17 +66,18 @@ fn get_inner_row(text: Option<&str>, width: usize) -> String {
             output.push_str(&inner);
         }
         Some(text) => {
-            let text_length: usize = text.len();
+            let text: String = text.chars().take(width - 2).collect();
+            let text_length: usize = text.chars().count();
             let total_space_characters: usize = width - text_length - 2;
-            if total_space_characters % 2 == 0 {
+            if total_space_characters.is_multiple_of(2) {
                 let spaces = " ".repeat(total_space_characters / 2);
                 output.push_str(&spaces);
-                output.push_str(text);
+                output.push_str(&text);
                 output.push_str(&spaces);
             } else {
                 let spaces = " ".repeat(total_space_characters / 2);
                 output.push_str(&spaces);
-                output.push_str(text);
+                output.push_str(&text);
                 output.push(' ');
                 output.push_str(&spaces);
             }
@@ -37,85 +87,415 @@ fn get_inner_row(text: Option<&str>, width: usize) -> String {
     output
 }
 
-pub fn display_welcome_screen(width: usize, height: usize) -> Option<()> {
-    let top_border = format!(
+/// `text` in big letters, a row of the box frame per line, when they fit in `height` rows of a
+/// frame `width` wide. A single row of plain text otherwise.
+pub(crate) fn banner_rows(text: &str, theme: Theme, width: usize, height: usize) -> Vec<String> {
+    font::banner(text, theme.fill(), width - 2, height)
+        .iter()
+        .map(|line| get_inner_row(Some(line), width))
+        .collect()
+}
+
+/// The box frame around `content`, which is centered top to bottom. Content that doesn't fit is
+/// cut off at the bottom.
+pub(crate) fn framed_rows(content: Vec<String>, width: usize, height: usize) -> Vec<String> {
+    let inner_rows = height - 2;
+    let spare_rows = inner_rows.saturating_sub(content.len());
+    let mut rows = vec![format!(
         "{}{}{}",
         TOP_LEFT_CORNER,
         HORIZONTAL_WALL.to_string().repeat(width - 2),
         TOP_RIGHT_CORNER
-    );
-    let bottom_border = format!(
+    )];
+    rows.extend(vec![
+        get_inner_row(None, width);
+        spare_rows - spare_rows / 2
+    ]);
+    rows.extend(content.into_iter().take(inner_rows));
+    rows.extend(vec![get_inner_row(None, width); spare_rows / 2]);
+    rows.push(format!(
         "{}{}{}",
         BOTTOM_LEFT_CORNER,
         HORIZONTAL_WALL.to_string().repeat(width - 2),
         BOTTOM_RIGHT_CORNER
-    );
+    ));
+    rows
+}
+
+/// The next or previous entry of `all` after `current`, wrapping around
+pub(crate) fn cycle<T: Copy + PartialEq>(all: &[T], current: T, forwards: bool) -> T {
+    let index = all.iter().position(|&item| item == current).unwrap_or(0);
+    let step = if forwards { 1 } else { all.len() - 1 };
+    all[(index + step) % all.len()]
+}
 
-    let word_rows: Vec<String> = WELCOME_MESSAGE
-        .split(' ')
-        .flat_map(|word| vec![get_inner_row(Some(word), width), get_inner_row(None, width)])
-        .collect();
-    let word_rows_length: usize = word_rows.len();
-    let number_of_empty_rows: usize = height - 2 - word_rows_length;
+/// What the player picked from the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    /// Resume the saved game
+    Continue,
+    Play,
+    /// Make a new profile and play as it
+    NewProfile,
+    Leaderboard,
+    Stats,
+    Achievements,
+    Replays,
+    Settings,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Continue,
+    Play,
+    Mode,
+    Difficulty,
+    Profile,
+    Leaderboard,
+    Stats,
+    Achievements,
+    Replays,
+    Settings,
+    Help,
+    Quit,
+}
+
+/// The main menu. Up and down (or k and j) move between entries, enter or space picks one, and
+/// left and right (or h and l) change the mode, difficulty and profile in place. Picking the
+/// profile makes a new one.
+#[derive(Debug, Clone)]
+pub struct Menu {
+    pub mode: Mode,
+    pub difficulty: Difficulty,
+    /// The index of the profile picked out of `profiles`
+    pub profile: usize,
+    profiles: Vec<String>,
+    can_continue: bool,
+    selected: Item,
+}
+
+impl Default for Menu {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Solo,
+            difficulty: Difficulty::Medium,
+            profile: 0,
+            profiles: vec![String::from(DEFAULT_PROFILE)],
+            can_continue: false,
+            selected: Item::Play,
+        }
+    }
+}
 
-    let empty_row = get_inner_row(None, width);
-    let half_of_empty_rows = vec![empty_row; number_of_empty_rows / 2];
+impl Menu {
+    /// Offers Continue at the top of the menu, selecting it whenever it first shows up
+    pub fn offer_continue(&mut self, can_continue: bool) {
+        if can_continue && !self.can_continue {
+            self.selected = Item::Continue;
+        }
+        self.can_continue = can_continue;
+    }
 
-    let mut rows = vec![top_border];
-    rows.extend(half_of_empty_rows.clone());
-    rows.extend(word_rows);
-    rows.extend(half_of_empty_rows.clone());
-    rows.push(bottom_border);
+    /// Lets the player pick one of `names` to play as, starting from the one at `selected`
+    pub fn offer_profiles(&mut self, names: Vec<String>, selected: usize) {
+        self.profiles = names;
+        self.profile = selected.min(self.profiles.len().saturating_sub(1));
+    }
 
-    if number_of_empty_rows % 2 > 0 {
-        rows.insert(1, get_inner_row(None, width));
+    fn items(&self) -> Vec<Item> {
+        let mut items = vec![
+            Item::Play,
+            Item::Mode,
+            Item::Difficulty,
+            Item::Profile,
+            Item::Leaderboard,
+            Item::Stats,
+            Item::Achievements,
+            Item::Replays,
+            Item::Settings,
+            Item::Help,
+            Item::Quit,
+        ];
+        if self.can_continue {
+            items.insert(0, Item::Continue);
+        }
+        items
     }
 
-    let welcome = rows.join("\r\n");
+    fn selected_index(&self) -> usize {
+        let items = self.items();
+        items
+            .iter()
+            .position(|&item| item == self.selected)
+            .unwrap_or(0)
+    }
+
+    fn label(&self, item: Item) -> String {
+        match item {
+            Item::Continue => String::from("CONTINUE"),
+            Item::Play => String::from("PLAY"),
+            Item::Mode => format!("MODE: {}", self.mode.name()).to_uppercase(),
+            Item::Difficulty => format!("DIFFICULTY: {}", self.difficulty).to_uppercase(),
+            Item::Profile => {
+                let name = self.profiles.get(self.profile).map_or("", String::as_str);
+                format!("PLAYER: {}", name).to_uppercase()
+            }
+            Item::Leaderboard => String::from("LEADERBOARD"),
+            Item::Stats => String::from("STATS"),
+            Item::Achievements => String::from("ACHIEVEMENTS"),
+            Item::Replays => String::from("REPLAYS"),
+            Item::Settings => String::from("SETTINGS"),
+            Item::Help => String::from("HELP"),
+            Item::Quit => String::from("QUIT"),
+        }
+    }
 
-    print!("{}{}{}\n\r", clear::All, cursor::Goto(1, 1), welcome);
+    /// Moves the selection or changes an option. Returns the item picked, if any.
+    fn handle_key(&mut self, key: Key) -> Option<Item> {
+        let items = self.items();
+        let index = self.selected_index();
+        match key {
+            Key::Up | Key::Char('k') => {
+                self.selected = items[(index + items.len() - 1) % items.len()];
+            }
+            Key::Down | Key::Char('j') | Key::Char('\t') => {
+                self.selected = items[(index + 1) % items.len()];
+            }
+            Key::Left | Key::Char('h') => self.change_option(false),
+            Key::Right | Key::Char('l') => self.change_option(true),
+            Key::Char('\n') | Key::Char(' ') => match self.selected {
+                Item::Mode | Item::Difficulty => self.change_option(true),
+                item => return Some(item),
+            },
+            _ => (),
+        }
+        None
+    }
 
-    let stdin = stdin();
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    for c in stdin.keys() {
-        match c.unwrap() {
-            Key::Char('q') | Key::Ctrl('c') => return None,
-            Key::Char(_) => {
-                break;
+    fn change_option(&mut self, forwards: bool) {
+        match self.selected {
+            Item::Mode => self.mode = cycle(&Mode::ALL, self.mode, forwards),
+            Item::Difficulty => {
+                self.difficulty = cycle(&Difficulty::ALL, self.difficulty, forwards)
+            }
+            Item::Profile if !self.profiles.is_empty() => {
+                let indices: Vec<usize> = (0..self.profiles.len()).collect();
+                self.profile = cycle(&indices, self.profile, forwards);
             }
             _ => (),
         }
     }
-    write!(stdout, "{}{}", clear::All, cursor::Goto(1, 1)).unwrap();
-    stdout.flush().unwrap();
-    Some(())
+
+    /// The title and banner above the menu entries, inside the box frame. The title and then
+    /// the banner are left out when there isn't room for them, and only the entries around the
+    /// selection are shown when there isn't room for all of them.
+    fn rows(&self, banner: Option<&str>, theme: Theme, width: usize, height: usize) -> Vec<String> {
+        let inner_rows = height - 2;
+        let items = self.items();
+        let selected = self.selected_index();
+        let shown = items.len().min(inner_rows);
+        let first = (selected + 1).saturating_sub(shown);
+        let mut content: Vec<String> = items[first..first + shown]
+            .iter()
+            .enumerate()
+            .map(|(offset, &item)| {
+                let label = self.label(item);
+                if first + offset == selected {
+                    get_inner_row(Some(&format!("> {} <", label)), width)
+                } else {
+                    get_inner_row(Some(&label), width)
+                }
+            })
+            .collect();
+        if let Some(banner) = banner.filter(|_| inner_rows >= content.len() + 2) {
+            content.insert(0, get_inner_row(Some(banner), width));
+            content.insert(1, get_inner_row(None, width));
+        }
+        // Each row of the title needs room, and a blank row under it
+        let spare_rows = inner_rows - content.len();
+        if spare_rows >= 2 {
+            let mut title = banner_rows(TITLE, theme, width, spare_rows - 1);
+            title.push(get_inner_row(None, width));
+            content.splice(0..0, title);
+        }
+        framed_rows(content, width, height)
+    }
+}
+
+/// Shows the menu over a demo game started from `seed`, played on the board, rules and theme of
+/// `config`, until the player picks something. Returns `None` if the player quit.
+pub fn display_welcome_screen(
+    terminal: &mut impl Backend,
+    config: &Config,
+    seed: u64,
+    menu: &mut Menu,
+) -> Result<Option<Choice>> {
+    let (width, height) = (config.width, config.height);
+    terminal.clear(Clear::All)?;
+    terminal.set_cursor_visible(false)?;
+    let mut demo = Demo::new(width, height, seed, config.rules());
+    let blank_row = get_inner_row(None, width);
+    let choice = 'demo: loop {
+        while let Some(key) = terminal.poll_key()? {
+            let picked = match key {
+                key if key.is_quit() => Some(Item::Quit),
+                key => menu.handle_key(key),
+            };
+            let choice = match picked {
+                None | Some(Item::Mode | Item::Difficulty) => continue,
+                Some(Item::Quit) => {
+                    terminal.set_cursor_visible(true)?;
+                    terminal.flush()?;
+                    return Ok(None);
+                }
+                Some(Item::Continue) => Choice::Continue,
+                Some(Item::Play) => Choice::Play,
+                Some(Item::Profile) => Choice::NewProfile,
+                Some(Item::Leaderboard) => Choice::Leaderboard,
+                Some(Item::Stats) => Choice::Stats,
+                Some(Item::Achievements) => Choice::Achievements,
+                Some(Item::Replays) => Choice::Replays,
+                Some(Item::Settings) => Choice::Settings,
+                Some(Item::Help) => Choice::Help,
+            };
+            break 'demo choice;
+        }
+        let rows = menu.rows(config.banner(), config.theme, width, height);
+        let welcome = demo.frame(&rows, &blank_row, config.theme);
+        terminal.move_to(0, 0)?;
+        terminal.write(&welcome)?;
+        terminal.write("\n\r")?;
+        terminal.flush()?;
+        demo.advance();
+        terminal.sleep(config.tick_duration());
+    };
+    terminal.clear(Clear::All)?;
+    terminal.move_to(0, 0)?;
+    terminal.set_cursor_visible(true)?;
+    terminal.flush()?;
+    Ok(Some(choice))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::get_inner_row;
+    use super::*;
+    use crate::config::DEFAULT_BANNER;
 
     #[test]
     fn get_row_returns_string_of_expected_length_without_input() {
         let row: String = get_inner_row(None, 10);
-        assert_eq!(row.len(), 10);
+        assert_eq!(row.chars().count(), 10);
     }
 
     #[test]
     fn get_row_returns_string_of_expected_length_with_input() {
         let row: String = get_inner_row(Some("TEST"), 10);
-        assert_eq!(row.len(), 10);
+        assert_eq!(row.chars().count(), 10);
     }
 
     #[test]
     fn get_row_returns_expected_string_with_input() {
         let row: String = get_inner_row(Some("WELCOME"), 13);
-        assert_eq!(&row, "+  WELCOME  +");
+        assert_eq!(&row, "║  WELCOME  ║");
     }
 
     #[test]
     fn get_row_returns_expected_string_with_no_input() {
         let row: String = get_inner_row(None, 13);
-        assert_eq!(&row, "+           +");
+        assert_eq!(&row, "║           ║");
+    }
+
+    #[test]
+    fn get_row_cuts_text_that_does_not_fit() {
+        let row: String = get_inner_row(Some("ÜBERLANGER TEXT"), 8);
+        assert_eq!(&row, "║ÜBERLA║");
+    }
+
+    #[test]
+    fn demo_shows_through_blank_rows_only() {
+        let blank_row = get_inner_row(None, 20);
+        let rows = vec![
+            String::from("╔══════════════════╗"),
+            blank_row.clone(),
+            get_inner_row(Some("HELLO"), 20),
+            String::from("╚══════════════════╝"),
+        ];
+        let mut demo = Demo::new(20, 4, 0, Rules::default());
+        demo.advance();
+        let frame = demo.frame(&rows, &blank_row, Theme::Classic);
+        let lines: Vec<&str> = frame.split("\r\n").collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], rows[2]);
+        assert_eq!(lines[0], rows[0]);
+    }
+
+    #[test]
+    fn the_selection_wraps_around_and_options_change_in_place() {
+        let mut menu = Menu::default();
+        assert_eq!(menu.handle_key(Key::Up), None);
+        assert_eq!(menu.handle_key(Key::Char('\n')), Some(Item::Quit));
+        menu.handle_key(Key::Down);
+        menu.handle_key(Key::Down);
+        assert_eq!(menu.handle_key(Key::Char('\n')), None);
+        assert_eq!(menu.mode, Mode::Versus);
+        menu.handle_key(Key::Right);
+        assert_eq!(menu.mode, Mode::TwoPlayer);
+        menu.handle_key(Key::Left);
+        menu.handle_key(Key::Down);
+        menu.handle_key(Key::Left);
+        assert_eq!(menu.difficulty, Difficulty::Easy);
+        menu.handle_key(Key::Left);
+        assert_eq!(menu.difficulty, Difficulty::Hard);
+    }
+
+    #[test]
+    fn profiles_are_picked_in_place() {
+        let mut menu = Menu::default();
+        menu.offer_profiles(vec![String::from("ada"), String::from("grace")], 1);
+        for _ in 0..3 {
+            menu.handle_key(Key::Down);
+        }
+        assert!(menu.label(Item::Profile).contains("GRACE"));
+        menu.handle_key(Key::Right);
+        assert_eq!(menu.profile, 0);
+        menu.handle_key(Key::Left);
+        assert_eq!(menu.label(Item::Profile), "PLAYER: GRACE");
+        assert_eq!(menu.handle_key(Key::Char('\n')), Some(Item::Profile));
+    }
+
+    #[test]
+    fn the_title_is_drawn_big_when_there_is_room() {
+        let title = font::banner(TITLE, '#', 38, 5);
+        let menu = Menu::default();
+        let rows = menu.rows(Some(DEFAULT_BANNER), Theme::Ascii, 40, 22);
+        assert_eq!(rows.len(), 22);
+        assert_eq!(rows[2], get_inner_row(Some(&title[0]), 40));
+        assert_eq!(rows[8], get_inner_row(Some(DEFAULT_BANNER), 40));
+
+        let rows = menu.rows(None, Theme::Ascii, 40, 15);
+        assert_eq!(rows[1], get_inner_row(Some(TITLE), 40));
+    }
+
+    #[test]
+    fn banners_are_centered_in_the_frame() {
+        let rows = banner_rows("GAME OVER", Theme::Classic, 40, 5);
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|row| row.chars().count() == 40));
+        assert!(rows[0].starts_with("║  █"));
+        assert_eq!(
+            banner_rows("GAME OVER", Theme::Classic, 30, 5),
+            [get_inner_row(Some("GAME OVER"), 30)]
+        );
+    }
+
+    #[test]
+    fn menus_too_tall_for_the_board_keep_the_selection_in_view() {
+        let mut menu = Menu::default();
+        menu.handle_key(Key::Up);
+        let rows = menu.rows(Some(DEFAULT_BANNER), Theme::Classic, 20, 6);
+        assert_eq!(rows.len(), 6);
+        assert!(rows[4].contains("> QUIT <"));
+        assert!(!rows.iter().any(|row| row.contains("HAPPY")));
     }
 }
@@ -0,0 +1,8 @@
+#!/bin/sh
+# Builds the game for the browser into web/pkg. Needs the wasm32-unknown-unknown target
+# (rustup target add wasm32-unknown-unknown) and wasm-bindgen-cli matching Cargo.lock.
+# Serve web/ over http, for example with `python3 -m http.server -d web`, and open it.
+set -e
+cd "$(dirname "$0")/.."
+cargo build --release --lib --target wasm32-unknown-unknown --no-default-features --features wasm
+wasm-bindgen --target web --out-dir web/pkg target/wasm32-unknown-unknown/release/snake.wasm
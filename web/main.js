@@ -0,0 +1,63 @@
+// Browser front-end: the wasm module runs the game, this file owns the timer, the keyboard
+// and the canvas. Build the module with build.sh first.
+import init, { WebGame } from "./pkg/snake.js";
+
+const WIDTH = 40;
+const HEIGHT = 25;
+const CELL_SIZE = 16;
+const TICK_MS = 75;
+// Indexed by the cell values from WebGame.cells: empty, wall, head, tail, food
+const COLOURS = ["#111", "#555", "#9f6", "#4a3", "#f44"];
+
+const canvas = document.getElementById("board");
+const status = document.getElementById("status");
+const context = canvas.getContext("2d");
+canvas.width = WIDTH * CELL_SIZE;
+canvas.height = HEIGHT * CELL_SIZE;
+
+let game;
+let timer;
+
+function draw() {
+  const cells = game.cells();
+  for (let row = 0; row < HEIGHT; row++) {
+    for (let column = 0; column < WIDTH; column++) {
+      context.fillStyle = COLOURS[cells[row * WIDTH + column]];
+      context.fillRect(column * CELL_SIZE, row * CELL_SIZE, CELL_SIZE, CELL_SIZE);
+    }
+  }
+}
+
+function start() {
+  game?.free();
+  game = new WebGame(WIDTH, HEIGHT);
+  status.textContent = "score 0";
+  draw();
+  clearInterval(timer);
+  timer = setInterval(() => {
+    const running = game.tick();
+    draw();
+    if (running) {
+      status.textContent = `score ${game.score()}`;
+    } else {
+      clearInterval(timer);
+      timer = undefined;
+      status.textContent = `game over, score ${game.score()}. press space to play again`;
+    }
+  }, TICK_MS);
+}
+
+document.addEventListener("keydown", (event) => {
+  if (timer === undefined) {
+    if (event.key === " ") {
+      event.preventDefault();
+      start();
+    }
+  } else if (game.key(event.key)) {
+    event.preventDefault();
+  }
+});
+
+await init();
+start();
+canvas.focus();